@@ -1,4 +1,9 @@
-use std::{env, fs::File, path::PathBuf};
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use gl_generator::{Api, Fallbacks, Profile, Registry, StructGenerator};
 
@@ -7,8 +12,48 @@ fn main() {
 
     println!("cargo:rerun-if-changed=build.rs");
 
+    write_sample_modules(&dest);
+
     let mut file = File::create(dest.join("gl_bindings.rs")).unwrap();
-    Registry::new(Api::Gles2, (3, 0), Profile::Core, Fallbacks::All, [])
-        .write_bindings(StructGenerator, &mut file)
-        .unwrap();
+    Registry::new(
+        Api::Gles2,
+        (3, 0),
+        Profile::Core,
+        Fallbacks::All,
+        [
+            "GL_EXT_texture_compression_s3tc",
+            "GL_EXT_texture_compression_s3tc_srgb",
+            "GL_EXT_texture_compression_rgtc",
+            "GL_EXT_texture_compression_bptc",
+            "GL_KHR_texture_compression_astc_ldr",
+        ],
+    )
+    .write_bindings(StructGenerator, &mut file)
+    .unwrap();
+}
+
+/// Declare every file in `src/samples` but `mod.rs` as a sample module and
+/// list their `ENTRY`s, so adding a sample is adding its file.
+fn write_sample_modules(dest: &Path) {
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/samples");
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut modules: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .filter(|path| !path.ends_with("mod.rs"))
+        .collect();
+    modules.sort();
+
+    let mut file = File::create(dest.join("samples.rs")).unwrap();
+    let mut names = vec![];
+    for path in modules {
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let path = path.to_str().unwrap();
+        writeln!(file, "#[path = {path:?}]\nmod {name};").unwrap();
+        names.push(format!("{name}::ENTRY"));
+    }
+    let entries = names.join(", ");
+    writeln!(file, "static ENTRIES: &[SampleEntry] = &[{entries}];").unwrap();
 }
@@ -0,0 +1,34 @@
+#!/bin/sh
+# Download the Khronos glTF sample models the importer is tested against,
+# with their license notes, into this directory. Commit the result to vendor
+# them, the tests needing them are #[ignore]d until then.
+set -eu
+
+BASE=https://raw.githubusercontent.com/KhronosGroup/glTF-Sample-Assets/main
+cd "$(dirname "$0")"
+
+curl -fsSL -o LICENSE.md "$BASE/LICENSE.md"
+
+fetch() {
+    model=$1
+    variant=$2
+    file=$3
+    mkdir -p "$model"
+    curl -fsSL -o "$model/$file" "$BASE/Models/$model/$variant/$file"
+}
+
+for model in Triangle TriangleWithoutIndices SimpleSparseAccessor Cameras \
+    BoxTextured BoxVertexColors SimpleSkin SimpleMorph; do
+    fetch "$model" glTF "$model.gltf"
+    # External buffers and images, embedded data URIs need nothing.
+    grep -o '"uri" *: *"[^"]*"' "$model/$model.gltf" | sed 's/.*"\([^"]*\)"$/\1/' |
+        grep -v '^data:' | while read -r uri; do
+            fetch "$model" glTF "$uri"
+        done
+done
+fetch Box glTF-Binary Box.glb
+
+for model in Triangle TriangleWithoutIndices SimpleSparseAccessor Cameras Box \
+    BoxTextured BoxVertexColors SimpleSkin SimpleMorph; do
+    curl -fsSL -o "$model/README.md" "$BASE/Models/$model/README.md"
+done
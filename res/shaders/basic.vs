@@ -2,6 +2,10 @@
 
 layout(location = 0) in vec4 position;
 
+uniform mat4 u_model;
+uniform mat4 u_view;
+uniform mat4 u_proj;
+
 void main() {
-    gl_Position = position;
+    gl_Position = u_proj * u_view * u_model * position;
 }
\ No newline at end of file
@@ -5,9 +5,11 @@ layout(location = 1) in vec2 tex_coord;
 
 out vec2 v_tex_coord;
 
+uniform mat4 u_model;
+uniform mat4 u_view;
 uniform mat4 u_proj;
 
 void main() {
-    gl_Position = position * u_proj;
+    gl_Position = u_proj * u_view * u_model * position;
     v_tex_coord = tex_coord;
 }
\ No newline at end of file
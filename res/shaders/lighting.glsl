@@ -0,0 +1,55 @@
+// Declarations and functions of the lit programs, put after their #version
+// line by LightBuffer::prelude in src/lighting/mod.rs.
+
+// Set by LightBuffer::prelude.
+#ifndef MAX_LIGHTS
+#define MAX_LIGHTS 16
+#endif
+
+// Laid out as GpuLight in src/lighting/mod.rs.
+struct Light {
+    vec4 position;    // w: 0 directional, 1 point, 2 spot
+    vec4 direction;   // w: range, 0 for none
+    vec4 color;       // times intensity
+    vec4 attenuation; // constant, linear, quadratic
+    vec4 cone;        // cosines of the inner and outer angles
+};
+
+layout(std140) uniform Lights {
+    vec4 ambient;
+    ivec4 light_count;
+    Light lights[MAX_LIGHTS];
+};
+
+// Add the Blinn-Phong terms of `light` at `position`, facing `normal` and
+// seen from `view`, to `diffuse` and `specular`.
+void add_light(Light light, vec3 position, vec3 normal, vec3 view, float shininess,
+               inout vec3 diffuse, inout vec3 specular) {
+    int kind = int(light.position.w);
+    vec3 to_light = -light.direction.xyz;
+    float strength = 1.0;
+    if (kind != 0) {
+        vec3 offset = light.position.xyz - position;
+        float light_distance = length(offset);
+        to_light = offset / light_distance;
+
+        vec3 k = light.attenuation.xyz;
+        strength = 1.0 / (k.x + k.y * light_distance + k.z * light_distance * light_distance);
+        float range = light.direction.w;
+        if (range > 0.0) {
+            strength *= clamp(1.0 - pow(light_distance / range, 4.0), 0.0, 1.0);
+        }
+        if (kind == 2) {
+            float cos_angle = dot(-to_light, light.direction.xyz);
+            strength *= smoothstep(light.cone.y, light.cone.x, cos_angle);
+        }
+    }
+
+    float n_dot_l = dot(normal, to_light);
+    if (n_dot_l <= 0.0) {
+        return;
+    }
+    vec3 halfway = normalize(to_light + view);
+    diffuse += light.color.rgb * n_dot_l * strength;
+    specular += light.color.rgb * pow(max(dot(normal, halfway), 0.0), shininess) * strength;
+}
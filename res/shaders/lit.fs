@@ -0,0 +1,71 @@
+#version 330 core
+
+// Lights and add_light come from lighting.glsl, see LightBuffer::prelude in
+// src/lighting/mod.rs.
+
+in vec3 v_position;
+in vec2 v_uv;
+in vec3 v_normal;
+in vec4 v_tangent;
+in vec3 v_diffuse_light;
+in vec3 v_specular_light;
+
+layout(location = 0) out vec4 color;
+
+uniform vec3 u_camera_position;
+uniform int u_per_vertex;
+
+uniform vec3 u_diffuse;
+uniform vec3 u_specular;
+uniform vec3 u_emissive;
+uniform float u_shininess;
+
+uniform sampler2D u_diffuse_map;
+uniform sampler2D u_specular_map;
+uniform sampler2D u_normal_map;
+uniform sampler2D u_emissive_map;
+uniform int u_use_diffuse_map;
+uniform int u_use_specular_map;
+uniform int u_use_normal_map;
+uniform int u_use_emissive_map;
+
+// The normal, bent by the normal map if there is one.
+vec3 surface_normal() {
+    vec3 n = normalize(v_normal);
+    if (u_use_normal_map == 0) {
+        return n;
+    }
+    vec3 t = normalize(v_tangent.xyz - n * dot(n, v_tangent.xyz));
+    vec3 b = cross(n, t) * v_tangent.w;
+    vec3 mapped = texture(u_normal_map, v_uv).xyz * 2.0 - 1.0;
+    return normalize(mat3(t, b, n) * mapped);
+}
+
+void main() {
+    vec3 diffuse = u_diffuse;
+    if (u_use_diffuse_map != 0) {
+        diffuse *= texture(u_diffuse_map, v_uv).rgb;
+    }
+    vec3 specular = u_specular;
+    if (u_use_specular_map != 0) {
+        specular *= texture(u_specular_map, v_uv).rgb;
+    }
+    vec3 emissive = u_emissive;
+    if (u_use_emissive_map != 0) {
+        emissive *= texture(u_emissive_map, v_uv).rgb;
+    }
+
+    vec3 diffuse_light = v_diffuse_light;
+    vec3 specular_light = v_specular_light;
+    if (u_per_vertex == 0) {
+        vec3 n = surface_normal();
+        vec3 view = normalize(u_camera_position - v_position);
+        diffuse_light = ambient.rgb;
+        specular_light = vec3(0.0);
+        for (int i = 0; i < light_count.x; i++) {
+            add_light(lights[i], v_position, n, view, u_shininess, diffuse_light, specular_light);
+        }
+    }
+
+    color = vec4(diffuse * diffuse_light + specular * specular_light + emissive, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+#version 330 core
+
+// Lights and add_light come from lighting.glsl, see LightBuffer::prelude in
+// src/lighting/mod.rs.
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec3 normal;
+layout(location = 3) in vec4 tangent;
+
+out vec3 v_position;
+out vec2 v_uv;
+out vec3 v_normal;
+out vec4 v_tangent;
+// The lighting of the vertex, when shading per vertex.
+out vec3 v_diffuse_light;
+out vec3 v_specular_light;
+
+uniform mat4 u_model;
+uniform mat4 u_view;
+uniform mat4 u_proj;
+uniform mat3 u_normal;
+uniform vec3 u_camera_position;
+uniform float u_shininess;
+uniform int u_per_vertex;
+
+void main() {
+    vec4 world = u_model * vec4(position, 1.0);
+    v_position = world.xyz;
+    v_uv = uv;
+    v_normal = u_normal * normal;
+    v_tangent = vec4(mat3(u_model) * tangent.xyz, tangent.w);
+
+    v_diffuse_light = vec3(0.0);
+    v_specular_light = vec3(0.0);
+    if (u_per_vertex != 0) {
+        vec3 n = normalize(v_normal);
+        vec3 view = normalize(u_camera_position - v_position);
+        v_diffuse_light = ambient.rgb;
+        for (int i = 0; i < light_count.x; i++) {
+            add_light(lights[i], v_position, n, view, u_shininess, v_diffuse_light, v_specular_light);
+        }
+    }
+
+    gl_Position = u_proj * u_view * world;
+}
\ No newline at end of file
@@ -0,0 +1,44 @@
+#version 330 core
+
+// Match MAX_MORPH_TARGETS in src/scene/mod.rs and MORPH_TEXTURE_WIDTH in
+// src/mesh/morph.rs.
+const int MAX_MORPH_TARGETS = 32;
+const int MORPH_TEXTURE_WIDTH = 1024;
+
+layout(location = 0) in vec3 position;
+layout(location = 2) in vec3 normal;
+
+out vec3 v_normal;
+
+uniform mat4 u_model;
+uniform mat4 u_view;
+uniform mat4 u_proj;
+uniform mat3 u_normal;
+
+// Position and normal deltas of every target, two texels per vertex.
+uniform sampler2D u_morph_deltas;
+uniform int u_morph_count;
+uniform int u_morph_vertices;
+uniform float u_morph_weights[MAX_MORPH_TARGETS];
+
+vec3 delta(int texel) {
+    ivec2 coord = ivec2(texel % MORPH_TEXTURE_WIDTH, texel / MORPH_TEXTURE_WIDTH);
+    return texelFetch(u_morph_deltas, coord, 0).xyz;
+}
+
+void main() {
+    vec3 morphed = position;
+    vec3 morphed_normal = normal;
+    for (int i = 0; i < u_morph_count; i++) {
+        float weight = u_morph_weights[i];
+        if (weight == 0.0) {
+            continue;
+        }
+        int texel = 2 * (i * u_morph_vertices + gl_VertexID);
+        morphed += weight * delta(texel);
+        morphed_normal += weight * delta(texel + 1);
+    }
+
+    gl_Position = u_proj * u_view * u_model * vec4(morphed, 1.0);
+    v_normal = u_normal * morphed_normal;
+}
\ No newline at end of file
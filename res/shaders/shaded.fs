@@ -0,0 +1,15 @@
+#version 330 core
+
+in vec3 v_normal;
+
+layout(location = 0) out vec4 color;
+
+uniform vec4 u_color;
+
+// A fixed light from the upper front, enough to show the shape.
+const vec3 LIGHT_DIR = normalize(vec3(0.4, 0.8, 0.6));
+
+void main() {
+    float diffuse = max(dot(normalize(v_normal), LIGHT_DIR), 0.0);
+    color = vec4(u_color.rgb * (0.25 + 0.75 * diffuse), u_color.a);
+}
\ No newline at end of file
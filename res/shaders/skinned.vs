@@ -0,0 +1,27 @@
+#version 330 core
+
+// Matches MAX_JOINTS in src/scene/mod.rs.
+const int MAX_JOINTS = 64;
+
+layout(location = 0) in vec4 position;
+layout(location = 2) in vec3 normal;
+layout(location = 6) in uvec4 joints;
+layout(location = 7) in vec4 weights;
+
+out vec3 v_normal;
+
+uniform mat4 u_model;
+uniform mat4 u_view;
+uniform mat4 u_proj;
+uniform mat3 u_normal;
+uniform mat4 u_joints[MAX_JOINTS];
+
+void main() {
+    mat4 skin = weights.x * u_joints[joints.x]
+              + weights.y * u_joints[joints.y]
+              + weights.z * u_joints[joints.z]
+              + weights.w * u_joints[joints.w];
+
+    gl_Position = u_proj * u_view * u_model * skin * position;
+    v_normal = u_normal * mat3(skin) * normal;
+}
\ No newline at end of file
@@ -0,0 +1,113 @@
+//! Derive macros for `rust_gl`.
+//!
+//! The generated code refers to `crate::vertex_array`, so the macros are only
+//! meant to be used inside `rust_gl` itself.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, LitInt, Member};
+
+/// Implement `Vertex` for a `#[repr(C)]` struct, one attribute per field.
+///
+/// Field attributes:
+/// - `#[location(n)]` binds the field to shader location `n`, otherwise it
+///   takes the next free one.
+/// - `#[normalized]` maps integer fields to `[0, 1]` or `[-1, 1]` floats.
+/// - `#[integer]` passes integer fields to `int`/`uint` shader inputs.
+#[proc_macro_derive(Vertex, attributes(location, normalized, integer))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_vertex(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_vertex(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !has_repr_c(&input) {
+        return Err(Error::new(
+            input.ident.span(),
+            "Vertex structs must be #[repr(C)] to have a predictable layout.",
+        ));
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "Vertex can only be derived for structs.",
+        ));
+    };
+    if matches!(data.fields, Fields::Unit) {
+        return Err(Error::new(
+            input.ident.span(),
+            "A vertex needs at least one field.",
+        ));
+    }
+
+    let mut attributes = vec![];
+    for (index, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(index.into()),
+        };
+
+        let mut location = quote!(::core::option::Option::None);
+        let mut kind = None;
+        for attr in field.attrs.iter() {
+            if attr.path().is_ident("location") {
+                let n: LitInt = attr.parse_args()?;
+                n.base10_parse::<u32>()?;
+                location = quote!(::core::option::Option::Some(#n));
+            } else if attr.path().is_ident("normalized") || attr.path().is_ident("integer") {
+                attr.meta.require_path_only()?;
+                if kind.is_some() {
+                    return Err(Error::new(
+                        attr.span(),
+                        "A field is either #[normalized] or #[integer].",
+                    ));
+                }
+                kind = Some(if attr.path().is_ident("normalized") {
+                    "Normalized"
+                } else {
+                    "Integer"
+                });
+            }
+        }
+
+        let kind = format_ident!("{}", kind.unwrap_or("Float"));
+        let ty = &field.ty;
+        attributes.push(quote! {
+            crate::vertex_array::VertexBufferElement::of::<#ty>(
+                crate::vertex_array::AttributeKind::#kind,
+                ::core::mem::offset_of!(Self, #member),
+                #location,
+            )
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics crate::vertex_array::Vertex for #name #ty_generics #where_clause {
+            const ATTRIBUTES: &'static [crate::vertex_array::VertexBufferElement] = &[
+                #(#attributes),*
+            ];
+        }
+    })
+}
+
+fn has_repr_c(input: &DeriveInput) -> bool {
+    input.attrs.iter().any(|attr| {
+        attr.path().is_ident("repr")
+            && attr
+                .meta
+                .require_list()
+                .map(|list| {
+                    list.tokens
+                        .clone()
+                        .into_iter()
+                        .any(|token| token.to_token_stream().to_string() == "C")
+                })
+                .unwrap_or(false)
+    })
+}
@@ -0,0 +1,361 @@
+use nalgebra::{Quaternion, SVector, UnitQuaternion, Vector3, Vector4};
+
+use crate::transform::Transform;
+
+/// How values between keyframes are found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Hold each key until the next one.
+    Step,
+    /// Straight lines, rotations along the shortest arc.
+    #[default]
+    Linear,
+    /// Hermite splines, with an in tangent, the value and an out tangent per
+    /// key as in glTF.
+    CubicSpline,
+}
+
+/// The part of a transform a [`Channel`] animates, with its key values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Property {
+    Translation(Vec<Vector3<f32>>),
+    /// Quaternion coordinates in `xyzw` order. Spline tangents do not have
+    /// to be unit length.
+    Rotation(Vec<Vector4<f32>>),
+    Scale(Vec<Vector3<f32>>),
+    /// Morph target weights, the same number for every key.
+    Weights(Vec<f32>),
+}
+
+impl Property {
+    fn len(&self) -> usize {
+        match self {
+            Property::Translation(values) | Property::Scale(values) => values.len(),
+            Property::Rotation(values) => values.len(),
+            Property::Weights(values) => values.len(),
+        }
+    }
+}
+
+/// Keyframes of one property of one target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    /// Which transform of the pose is animated.
+    pub target: usize,
+    /// Key times in seconds, increasing.
+    pub times: Vec<f32>,
+    pub interpolation: Interpolation,
+    pub property: Property,
+}
+
+impl Channel {
+    /// Panics unless there is a value per key, or three for splines. Weights
+    /// may have several values per key.
+    pub fn new(
+        target: usize,
+        times: Vec<f32>,
+        interpolation: Interpolation,
+        property: Property,
+    ) -> Self {
+        let per_key = match interpolation {
+            Interpolation::CubicSpline => 3,
+            _ => 1,
+        };
+        assert!(!times.is_empty(), "A channel needs keys.");
+        let len = property.len();
+        let keys = times.len() * per_key;
+        match property {
+            Property::Weights(_) => assert!(
+                len > 0 && len.is_multiple_of(keys),
+                "Key times and values do not match."
+            ),
+            _ => assert_eq!(len, keys, "Key times and values do not match."),
+        }
+        Self {
+            target,
+            times,
+            interpolation,
+            property,
+        }
+    }
+
+    /// The last key time.
+    pub fn duration(&self) -> f32 {
+        *self.times.last().unwrap()
+    }
+
+    /// Set the animated property of `transform` to its value at `time`,
+    /// holding the first and last keys outside of them. Weights are left to
+    /// [`Channel::apply_weights`].
+    pub fn apply(&self, time: f32, transform: &mut Transform) {
+        let key = Key::find(&self.times, time);
+        match &self.property {
+            Property::Translation(values) => {
+                transform.translation = key.sample(values, self.interpolation)
+            }
+            Property::Scale(values) => transform.scale = key.sample(values, self.interpolation),
+            Property::Rotation(values) => {
+                let rotation = |v: Vector4<f32>| UnitQuaternion::new_normalize(Quaternion::from(v));
+                transform.rotation = match self.interpolation {
+                    Interpolation::Linear => {
+                        rotation(values[key.from]).slerp(&rotation(values[key.to]), key.t)
+                    }
+                    _ => rotation(key.sample(values, self.interpolation)),
+                };
+            }
+            Property::Weights(_) => {}
+        }
+    }
+
+    /// Set `weights` to the animated morph target weights at `time`, if this
+    /// channel animates them.
+    pub fn apply_weights(&self, time: f32, weights: &mut Vec<f32>) {
+        let Property::Weights(values) = &self.property else {
+            return;
+        };
+        let count = values.len() / self.times.len();
+        let count = match self.interpolation {
+            Interpolation::CubicSpline => count / 3,
+            _ => count,
+        };
+
+        let key = Key::find(&self.times, time);
+        weights.resize(count, 0.);
+        for (index, weight) in weights.iter_mut().enumerate() {
+            let values: Vec<_> = values
+                .iter()
+                .skip(index)
+                .step_by(count)
+                .map(|&value| SVector::from([value]))
+                .collect();
+            *weight = key.sample(&values, self.interpolation).x;
+        }
+    }
+}
+
+/// The keys around a point in time.
+struct Key {
+    from: usize,
+    to: usize,
+    /// How far along from `from` to `to`, in `[0, 1]`.
+    t: f32,
+    /// Seconds between the keys.
+    span: f32,
+}
+
+impl Key {
+    fn find(times: &[f32], time: f32) -> Self {
+        let last = times.len() - 1;
+        let to = times.partition_point(|&t| t <= time);
+        if to == 0 || to > last {
+            let key = to.saturating_sub(1).min(last);
+            return Self {
+                from: key,
+                to: key,
+                t: 0.,
+                span: 0.,
+            };
+        }
+
+        let from = to - 1;
+        let span = times[to] - times[from];
+        Self {
+            from,
+            to,
+            t: (time - times[from]) / span,
+            span,
+        }
+    }
+
+    fn sample<const D: usize>(
+        &self,
+        values: &[SVector<f32, D>],
+        interpolation: Interpolation,
+    ) -> SVector<f32, D> {
+        match interpolation {
+            Interpolation::Step => values[self.from],
+            Interpolation::Linear => values[self.from].lerp(&values[self.to], self.t),
+            Interpolation::CubicSpline => {
+                let (p0, m0) = (values[3 * self.from + 1], values[3 * self.from + 2]);
+                let (m1, p1) = (values[3 * self.to], values[3 * self.to + 1]);
+                let t = self.t;
+                let (t2, t3) = (t * t, t * t * t);
+                p0 * (2. * t3 - 3. * t2 + 1.)
+                    + m0 * (self.span * (t3 - 2. * t2 + t))
+                    + p1 * (-2. * t3 + 3. * t2)
+                    + m1 * (self.span * (t3 - t2))
+            }
+        }
+    }
+}
+
+/// Channels played together, such as a walk cycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub name: String,
+    pub channels: Vec<Channel>,
+    /// Seconds until the last key of any channel.
+    pub duration: f32,
+}
+
+impl Clip {
+    pub fn new(name: &str, channels: Vec<Channel>) -> Self {
+        let duration = channels.iter().map(Channel::duration).fold(0., f32::max);
+        Self {
+            name: name.to_string(),
+            channels,
+            duration,
+        }
+    }
+
+    /// Write the animated properties at `time` into `pose`, leaving the rest.
+    /// Channels whose target is outside of `pose` are skipped.
+    pub fn sample(&self, time: f32, pose: &mut [Transform]) {
+        for channel in &self.channels {
+            if let Some(transform) = pose.get_mut(channel.target) {
+                channel.apply(time, transform);
+            }
+        }
+    }
+
+    /// Like [`Clip::sample`], for the morph target weights of each target.
+    pub fn sample_weights(&self, time: f32, weights: &mut [Vec<f32>]) {
+        for channel in &self.channels {
+            if let Some(weights) = weights.get_mut(channel.target) {
+                channel.apply_weights(time, weights);
+            }
+        }
+    }
+
+    /// Whether any channel animates morph target weights.
+    pub fn has_weights(&self) -> bool {
+        self.channels
+            .iter()
+            .any(|channel| matches!(channel.property, Property::Weights(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn translation(interpolation: Interpolation, values: Vec<Vector3<f32>>) -> Channel {
+        let times = match interpolation {
+            Interpolation::CubicSpline => vec![0., 2.],
+            _ => vec![0., 1., 2.],
+        };
+        Channel::new(0, times, interpolation, Property::Translation(values))
+    }
+
+    fn at(channel: &Channel, time: f32) -> Transform {
+        let mut transform = Transform::IDENTITY;
+        channel.apply(time, &mut transform);
+        transform
+    }
+
+    #[test]
+    fn step_and_linear_hold_the_ends() {
+        let values = vec![Vector3::zeros(), Vector3::x(), Vector3::y()];
+        let step = translation(Interpolation::Step, values.clone());
+        assert_eq!(at(&step, 0.9).translation, Vector3::zeros());
+        assert_eq!(at(&step, 1.5).translation, Vector3::x());
+
+        let linear = translation(Interpolation::Linear, values);
+        assert_relative_eq!(at(&linear, 0.25).translation, Vector3::x() * 0.25);
+        assert_relative_eq!(at(&linear, 1.5).translation, Vector3::new(0.5, 0.5, 0.));
+        assert_eq!(at(&linear, -1.).translation, Vector3::zeros());
+        assert_eq!(at(&linear, 5.).translation, Vector3::y());
+    }
+
+    #[test]
+    fn cubic_splines_pass_through_keys_with_their_tangents() {
+        // A straight line from 0 to 2 with matching tangents stays linear.
+        let tangent = Vector3::x();
+        let spline = translation(
+            Interpolation::CubicSpline,
+            vec![
+                tangent,
+                Vector3::zeros(),
+                tangent,
+                tangent,
+                Vector3::x() * 2.,
+                tangent,
+            ],
+        );
+        for time in [0., 0.5, 1., 1.5, 2.] {
+            assert_relative_eq!(
+                at(&spline, time).translation,
+                Vector3::x() * time,
+                epsilon = 1e-6
+            );
+        }
+
+        // Flat tangents ease in and out.
+        let eased = translation(
+            Interpolation::CubicSpline,
+            vec![
+                Vector3::zeros(),
+                Vector3::zeros(),
+                Vector3::zeros(),
+                Vector3::zeros(),
+                Vector3::x(),
+                Vector3::zeros(),
+            ],
+        );
+        assert_relative_eq!(at(&eased, 1.).translation, Vector3::x() * 0.5);
+        assert!(at(&eased, 0.5).translation.x < 0.25);
+    }
+
+    #[test]
+    fn rotations_are_slerped() {
+        let quarter = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), FRAC_PI_2);
+        let channel = Channel::new(
+            0,
+            vec![0., 1.],
+            Interpolation::Linear,
+            Property::Rotation(vec![UnitQuaternion::identity().coords, quarter.coords]),
+        );
+        let half = at(&channel, 0.5).rotation;
+        assert_relative_eq!(half.angle(), FRAC_PI_2 / 2., epsilon = 1e-6);
+    }
+
+    #[test]
+    fn weights_are_sampled_per_target() {
+        let channel = Channel::new(
+            0,
+            vec![0., 1.],
+            Interpolation::Linear,
+            Property::Weights(vec![0., 1., 1., 0.]),
+        );
+        let mut weights = vec![];
+        channel.apply_weights(0.25, &mut weights);
+        assert_relative_eq!(weights.as_slice(), [0.25, 0.75].as_slice());
+
+        // Splines carry tangents for every weight.
+        let spline = Channel::new(
+            0,
+            vec![0., 2.],
+            Interpolation::CubicSpline,
+            Property::Weights(vec![0., 0., 0., 0., 0., 0., 0., 0., 0., 1., 0., 0.]),
+        );
+        spline.apply_weights(1., &mut weights);
+        assert_relative_eq!(weights.as_slice(), [0., 0.5].as_slice());
+    }
+
+    #[test]
+    fn clips_skip_targets_outside_the_pose() {
+        let mut channel = translation(
+            Interpolation::Linear,
+            vec![Vector3::zeros(), Vector3::x(), Vector3::y()],
+        );
+        channel.target = 3;
+        let clip = Clip::new("clip", vec![channel]);
+        assert_eq!(clip.duration, 2.);
+
+        let mut pose = [Transform::IDENTITY; 2];
+        clip.sample(1., &mut pose);
+        assert_eq!(pose, [Transform::IDENTITY; 2]);
+    }
+}
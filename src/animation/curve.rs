@@ -0,0 +1,183 @@
+use nalgebra::{SVector, UnitQuaternion};
+
+use super::Easing;
+
+/// A value that can be tweened.
+pub trait Animatable: Copy {
+    /// The value `t` of the way from `self` to `other`. `t` may leave
+    /// `[0, 1]` for easings that overshoot.
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl<const D: usize> Animatable for SVector<f32, D> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+/// Along the shortest arc.
+impl Animatable for UnitQuaternion<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// Component-wise, for colors such as [`crate::scene::Material::color`].
+impl<const N: usize> Animatable for [f32; N] {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].interpolate(&other[i], t))
+    }
+}
+
+/// A value at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    /// Seconds from the start of the curve.
+    pub time: f32,
+    pub value: T,
+    /// How the value moves here from the previous key.
+    pub easing: Easing,
+}
+
+/// Keyframes of one value, eased between and held before the first and
+/// after the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Curve<T> {
+    /// A curve that stays at `value`, until keys are added.
+    pub fn new(value: T) -> Self {
+        Self {
+            keys: vec![Keyframe {
+                time: 0.,
+                value,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    /// From `from` to `to` over `duration` seconds.
+    pub fn tween(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self::new(from).with_key(duration, to, easing)
+    }
+
+    /// Add a key reached with `easing`, after any others at the same time.
+    pub fn with_key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        let index = self.keys.partition_point(|key| key.time <= time);
+        self.keys.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    /// Add a key `duration` seconds after the last one.
+    pub fn then(self, duration: f32, value: T, easing: Easing) -> Self {
+        let time = self.duration() + duration;
+        self.with_key(time, value, easing)
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// The last key time.
+    pub fn duration(&self) -> f32 {
+        self.keys.last().unwrap().time
+    }
+
+    pub fn sample(&self, time: f32) -> T {
+        let to = self.keys.partition_point(|key| key.time <= time);
+        if to == 0 {
+            return self.keys[0].value;
+        }
+        if to == self.keys.len() {
+            return self.keys[to - 1].value;
+        }
+
+        let (from, to) = (&self.keys[to - 1], &self.keys[to]);
+        let t = (time - from.time) / (to.time - from.time);
+        from.value.interpolate(&to.value, to.easing.apply(t))
+    }
+}
+
+/// How a curve carries on past its last key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    /// Play once and hold the end.
+    #[default]
+    Once,
+    /// Start over from the beginning.
+    Loop,
+    /// Play backwards to the beginning, then forwards again.
+    PingPong,
+}
+
+impl LoopMode {
+    /// Map `time` onto `[0, duration]`.
+    pub fn apply(self, time: f32, duration: f32) -> f32 {
+        if duration <= 0. {
+            return 0.;
+        }
+        match self {
+            LoopMode::Once => time.clamp(0., duration),
+            LoopMode::Loop => time.rem_euclid(duration),
+            LoopMode::PingPong => {
+                let time = time.rem_euclid(2. * duration);
+                if time > duration {
+                    2. * duration - time
+                } else {
+                    time
+                }
+            }
+        }
+    }
+
+    pub fn repeats(self) -> bool {
+        self != LoopMode::Once
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn curves_ease_between_keys_and_hold_the_ends() {
+        let curve = Curve::new(Vector3::zeros())
+            .then(1., Vector3::x(), Easing::Linear)
+            .then(1., Vector3::y(), Easing::QuadIn);
+        assert_eq!(curve.duration(), 2.);
+        assert_eq!(curve.sample(-1.), Vector3::zeros());
+        assert_relative_eq!(curve.sample(0.5), Vector3::x() * 0.5);
+        assert_relative_eq!(curve.sample(1.5), Vector3::new(0.75, 0.25, 0.));
+        assert_eq!(curve.sample(3.), Vector3::y());
+
+        let color = Curve::tween([0., 0., 0., 1.], [1., 0.5, 0., 1.], 2., Easing::Linear);
+        assert_eq!(color.sample(1.), [0.5, 0.25, 0., 1.]);
+    }
+
+    #[test]
+    fn loop_modes() {
+        assert_eq!(LoopMode::Once.apply(3., 2.), 2.);
+        assert_eq!(LoopMode::Loop.apply(3., 2.), 1.);
+        assert_eq!(LoopMode::Loop.apply(-0.5, 2.), 1.5);
+        assert_eq!(LoopMode::PingPong.apply(1.5, 2.), 1.5);
+        assert_eq!(LoopMode::PingPong.apply(2.5, 2.), 1.5);
+        assert_eq!(LoopMode::PingPong.apply(4.5, 2.), 0.5);
+    }
+}
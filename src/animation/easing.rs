@@ -0,0 +1,122 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// How progress between two keys is shaped, the usual tweening curves.
+///
+/// `In` variants start slow, `Out` variants end slow and `InOut` do both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Hold the previous value until the key is reached.
+    Step,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    /// Pull back a little before setting off.
+    BackIn,
+    /// Overshoot a little and settle.
+    BackOut,
+    /// Spring past the end and oscillate into place.
+    ElasticOut,
+    /// Bounce off the end like a dropped ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// Eased progress for `t` in `[0, 1]`. Every curve maps 0 to 0 and 1 to
+    /// 1, the back and elastic ones leave that range in between.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::Step => {
+                if t < 1. {
+                    0.
+                } else {
+                    1.
+                }
+            }
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1. - (1. - t) * (1. - t),
+            Easing::QuadInOut => in_out(t, |t| t * t),
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1. - (1. - t).powi(3),
+            Easing::CubicInOut => in_out(t, |t| t * t * t),
+            Easing::SineIn => 1. - (t * FRAC_PI_2).cos(),
+            Easing::SineOut => (t * FRAC_PI_2).sin(),
+            Easing::SineInOut => (1. - (t * PI).cos()) / 2.,
+            Easing::ExpoIn if t == 0. => 0.,
+            Easing::ExpoIn => 2f32.powf(10. * t - 10.),
+            Easing::ExpoOut if t == 1. => 1.,
+            Easing::ExpoOut => 1. - 2f32.powf(-10. * t),
+            Easing::BackIn => back_in(t),
+            Easing::BackOut => 1. - back_in(1. - t),
+            Easing::ElasticOut if t == 0. || t == 1. => t,
+            Easing::ElasticOut => {
+                2f32.powf(-10. * t) * ((t * 10. - 0.75) * (2. * PI / 3.)).sin() + 1.
+            }
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+/// The first half eased in by `ease`, the second its mirror image.
+fn in_out(t: f32, ease: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease(2. * t) / 2.
+    } else {
+        1. - ease(2. - 2. * t) / 2.
+    }
+}
+
+fn back_in(t: f32) -> f32 {
+    const OVERSHOOT: f32 = 1.70158;
+    t * t * ((OVERSHOOT + 1.) * t - OVERSHOOT)
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1. / D {
+        N * t * t
+    } else if t < 2. / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn easings_keep_their_ends() {
+        use Easing::*;
+        for easing in [
+            Linear, QuadIn, QuadOut, QuadInOut, CubicIn, CubicOut, CubicInOut, SineIn, SineOut,
+            SineInOut, ExpoIn, ExpoOut, BackIn, BackOut, ElasticOut, BounceOut,
+        ] {
+            assert_relative_eq!(easing.apply(0.), 0., epsilon = 1e-6);
+            assert_relative_eq!(easing.apply(1.), 1., epsilon = 1e-6);
+        }
+        assert!(QuadIn.apply(0.5) < 0.5 && QuadOut.apply(0.5) > 0.5);
+        assert_relative_eq!(QuadInOut.apply(0.5), 0.5);
+        assert!(BackOut.apply(0.7) > 1.);
+        assert_eq!(Step.apply(0.99), 0.);
+    }
+}
@@ -0,0 +1,32 @@
+mod clip;
+mod curve;
+mod easing;
+mod player;
+mod timeline;
+
+pub use clip::{Channel, Clip, Interpolation, Property};
+pub use curve::{Animatable, Curve, Keyframe, LoopMode};
+pub use easing::Easing;
+pub use player::AnimationPlayer;
+pub use timeline::{Binding, Timeline, Track, UniformCurve};
+
+use crate::transform::Transform;
+
+/// Move every transform of `pose` towards the one in `other` by `weight`.
+pub fn blend(pose: &mut [Transform], other: &[Transform], weight: f32) {
+    for (transform, other) in pose.iter_mut().zip(other) {
+        *transform = transform.lerp(other, weight);
+    }
+}
+
+/// Move the morph target weights of every target towards those in `other`
+/// by `weight`, missing weights counting as zero.
+pub fn blend_weights(weights: &mut [Vec<f32>], other: &[Vec<f32>], weight: f32) {
+    for (weights, other) in weights.iter_mut().zip(other) {
+        weights.resize(weights.len().max(other.len()), 0.);
+        for (index, value) in weights.iter_mut().enumerate() {
+            let target = other.get(index).copied().unwrap_or(0.);
+            *value += (target - *value) * weight;
+        }
+    }
+}
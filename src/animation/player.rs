@@ -0,0 +1,144 @@
+use super::{blend, blend_weights, Clip};
+use crate::{
+    scene::{NodeId, Scene},
+    transform::Transform,
+};
+
+#[derive(Debug, Clone, Copy)]
+struct Playback {
+    clip: usize,
+    time: f32,
+}
+
+/// Plays [`Clip`]s on scene nodes and cross-fades between them.
+///
+/// Channel targets index into the player's nodes, such as the joints of a
+/// skeleton. Nodes no channel animates keep the transform and morph target
+/// weights they had when the player was made.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    clips: Vec<Clip>,
+    targets: Vec<NodeId>,
+    rest: Vec<Transform>,
+    rest_weights: Vec<Vec<f32>>,
+    /// Whether any clip animates morph target weights.
+    morphs: bool,
+    current: Option<Playback>,
+    /// The clip being faded out, and the fade's progress and length.
+    previous: Option<(Playback, f32, f32)>,
+    pub speed: f32,
+    /// Start over at the end, otherwise hold the last pose.
+    pub looping: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(scene: &Scene, targets: Vec<NodeId>, clips: Vec<Clip>) -> Self {
+        let rest = targets.iter().map(|&id| *scene.local(id)).collect();
+        let rest_weights = targets
+            .iter()
+            .map(|&id| scene.node(id).morph_weights.clone())
+            .collect();
+        let morphs = clips.iter().any(Clip::has_weights);
+        Self {
+            clips,
+            targets,
+            rest,
+            rest_weights,
+            morphs,
+            current: None,
+            previous: None,
+            speed: 1.,
+            looping: true,
+        }
+    }
+
+    pub fn clips(&self) -> &[Clip] {
+        &self.clips
+    }
+
+    /// Index of the clip called `name`.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.clips.iter().position(|clip| clip.name == name)
+    }
+
+    /// The clip playing, or being faded in.
+    pub fn current(&self) -> Option<usize> {
+        self.current.map(|playback| playback.clip)
+    }
+
+    /// Switch to `clip` from its start, blending from the current pose over
+    /// `fade` seconds.
+    pub fn play(&mut self, clip: usize, fade: f32) {
+        assert!(clip < self.clips.len(), "No clip {}.", clip);
+        let next = Playback { clip, time: 0. };
+        self.previous = match self.current.replace(next) {
+            Some(current) if fade > 0. => Some((current, 0., fade)),
+            _ => None,
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    /// Advance by `delta` seconds and pose the target nodes.
+    pub fn update(&mut self, scene: &mut Scene, delta: f32) {
+        let Some(mut current) = self.current else {
+            return;
+        };
+        let delta = delta * self.speed;
+        advance(&mut current, &self.clips, delta, self.looping);
+        self.current = Some(current);
+        let (mut pose, mut weights) = self.pose(current);
+
+        if let Some((mut previous, progress, fade)) = self.previous {
+            let progress = progress + delta;
+            if progress >= fade {
+                self.previous = None;
+            } else {
+                advance(&mut previous, &self.clips, delta, self.looping);
+                self.previous = Some((previous, progress, fade));
+                let (mut from, mut from_weights) = self.pose(previous);
+                blend(&mut from, &pose, progress / fade);
+                blend_weights(&mut from_weights, &weights, progress / fade);
+                pose = from;
+                weights = from_weights;
+            }
+        }
+
+        for (&id, transform) in self.targets.iter().zip(pose) {
+            scene.set_local(id, transform);
+        }
+        if self.morphs {
+            for (&id, weights) in self.targets.iter().zip(weights) {
+                scene.node_mut(id).morph_weights = weights;
+            }
+        }
+    }
+
+    /// Transforms and morph target weights of the targets, the weights only
+    /// when some clip animates them.
+    fn pose(&self, playback: Playback) -> (Vec<Transform>, Vec<Vec<f32>>) {
+        let clip = &self.clips[playback.clip];
+        let mut pose = self.rest.clone();
+        clip.sample(playback.time, &mut pose);
+
+        let mut weights = vec![];
+        if self.morphs {
+            weights = self.rest_weights.clone();
+            clip.sample_weights(playback.time, &mut weights);
+        }
+        (pose, weights)
+    }
+}
+
+fn advance(playback: &mut Playback, clips: &[Clip], delta: f32, looping: bool) {
+    let duration = clips[playback.clip].duration;
+    playback.time += delta;
+    if looping && duration > 0. {
+        playback.time = playback.time.rem_euclid(duration);
+    } else {
+        playback.time = playback.time.clamp(0., duration);
+    }
+}
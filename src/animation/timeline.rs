@@ -0,0 +1,308 @@
+use nalgebra::{UnitQuaternion, Vector3};
+use std::ffi::CString;
+
+use super::{Curve, LoopMode};
+use crate::{
+    clock::FrameTime,
+    renderer::gl,
+    resources::{Handle, ResourceManager},
+    scene::{NodeId, Scene},
+    shaders::Shader,
+};
+
+/// A curve for a shader uniform of the matching type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UniformCurve {
+    Float(Curve<f32>),
+    Vec3(Curve<Vector3<f32>>),
+    Vec4(Curve<[f32; 4]>),
+}
+
+impl UniformCurve {
+    fn duration(&self) -> f32 {
+        match self {
+            UniformCurve::Float(curve) => curve.duration(),
+            UniformCurve::Vec3(curve) => curve.duration(),
+            UniformCurve::Vec4(curve) => curve.duration(),
+        }
+    }
+}
+
+/// What a [`Track`] drives, with the curve it is driven by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Binding {
+    Translation(NodeId, Curve<Vector3<f32>>),
+    Rotation(NodeId, Curve<UnitQuaternion<f32>>),
+    Scale(NodeId, Curve<Vector3<f32>>),
+    /// The color of one of the node's materials.
+    MaterialColor(NodeId, usize, Curve<[f32; 4]>),
+    /// A uniform of the shader, see [`Binding::uniform`].
+    Uniform(Handle<Shader>, CString, UniformCurve),
+}
+
+impl Binding {
+    /// Drive the uniform called `name` of `shader`.
+    ///
+    /// Panics if `name` contains a NUL.
+    pub fn uniform(shader: Handle<Shader>, name: &str, curve: UniformCurve) -> Self {
+        let name = CString::new(name)
+            .unwrap_or_else(|_| panic!("Uniform name {:?} contains a NUL.", name));
+        Binding::Uniform(shader, name, curve)
+    }
+
+    fn duration(&self) -> f32 {
+        match self {
+            Binding::Translation(_, curve) | Binding::Scale(_, curve) => curve.duration(),
+            Binding::Rotation(_, curve) => curve.duration(),
+            Binding::MaterialColor(_, _, curve) => curve.duration(),
+            Binding::Uniform(_, _, curve) => curve.duration(),
+        }
+    }
+}
+
+/// A binding played from `delay` seconds into its timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub binding: Binding,
+    pub delay: f32,
+    pub loop_mode: LoopMode,
+}
+
+impl Track {
+    pub fn new(binding: Binding) -> Self {
+        Self {
+            binding,
+            delay: 0.,
+            loop_mode: LoopMode::Once,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    /// When the track reaches its end, `None` if it repeats forever.
+    pub fn end(&self) -> Option<f32> {
+        (!self.loop_mode.repeats()).then(|| self.delay + self.binding.duration())
+    }
+
+    /// Time along the curve at `time` on the timeline.
+    fn local_time(&self, time: f32) -> f32 {
+        let time = (time - self.delay).max(0.);
+        self.loop_mode.apply(time, self.binding.duration())
+    }
+}
+
+/// Tracks played together on a clock of their own, advanced by the frame
+/// clock.
+///
+/// Scene bindings are written by [`Timeline::apply_to_scene`] and uniforms
+/// by [`Timeline::apply_uniforms`], so a timeline can drive either or both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    tracks: Vec<Track>,
+    time: f32,
+    playing: bool,
+    pub speed: f32,
+    /// How the whole timeline carries on past [`Timeline::duration`].
+    pub loop_mode: LoopMode,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    /// An empty timeline, playing.
+    pub fn new() -> Self {
+        Self {
+            tracks: vec![],
+            time: 0.,
+            playing: true,
+            speed: 1.,
+            loop_mode: LoopMode::Once,
+        }
+    }
+
+    pub fn with_track(mut self, track: Track) -> Self {
+        self.tracks.push(track);
+        self
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn add(&mut self, track: Track) {
+        self.tracks.push(track);
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Seconds until every track that does not repeat has ended.
+    pub fn duration(&self) -> f32 {
+        self.tracks.iter().filter_map(Track::end).fold(0., f32::max)
+    }
+
+    /// Seconds played, not counting the timeline's own looping.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether the timeline played to its end and holds it.
+    pub fn is_finished(&self) -> bool {
+        !self.loop_mode.repeats()
+            && self.tracks.iter().all(|track| track.end().is_some())
+            && self.time >= self.duration()
+    }
+
+    /// Advance by the frame's delta while playing.
+    pub fn update(&mut self, time: &FrameTime) {
+        if self.playing {
+            self.time += time.delta * self.speed;
+        }
+    }
+
+    /// Time on the tracks, after the timeline's own looping. Timelines
+    /// whose tracks all repeat leave that to the tracks.
+    fn track_time(&self) -> f32 {
+        let duration = self.duration();
+        if self.loop_mode.repeats() && duration > 0. {
+            self.loop_mode.apply(self.time, duration)
+        } else {
+            self.time
+        }
+    }
+
+    /// Write the transform and material bindings at the current time into
+    /// `scene`.
+    pub fn apply_to_scene(&self, scene: &mut Scene) {
+        let time = self.track_time();
+        for track in &self.tracks {
+            let t = track.local_time(time);
+            match &track.binding {
+                Binding::Translation(id, curve) => {
+                    let mut transform = *scene.local(*id);
+                    transform.translation = curve.sample(t);
+                    scene.set_local(*id, transform);
+                }
+                Binding::Rotation(id, curve) => {
+                    let mut transform = *scene.local(*id);
+                    transform.rotation = curve.sample(t);
+                    scene.set_local(*id, transform);
+                }
+                Binding::Scale(id, curve) => {
+                    let mut transform = *scene.local(*id);
+                    transform.scale = curve.sample(t);
+                    scene.set_local(*id, transform);
+                }
+                Binding::MaterialColor(id, material, curve) => {
+                    if let Some(material) = scene.node_mut(*id).materials.get_mut(*material) {
+                        material.color = curve.sample(t);
+                    }
+                }
+                Binding::Uniform(..) => {}
+            }
+        }
+    }
+
+    /// Set the uniform bindings at the current time. Uniforms a shader does
+    /// not have are skipped.
+    pub fn apply_uniforms(&self, gl: &gl::Gl, resources: &ResourceManager) {
+        let time = self.track_time();
+        for track in &self.tracks {
+            let Binding::Uniform(shader, name, curve) = &track.binding else {
+                continue;
+            };
+            let shader = resources.get(shader);
+            if !shader.has_uniform(gl, name) {
+                continue;
+            }
+
+            let t = track.local_time(time);
+            match curve {
+                UniformCurve::Float(curve) => shader.set_uniform_1f(gl, name, curve.sample(t)),
+                UniformCurve::Vec3(curve) => {
+                    let [x, y, z] = curve.sample(t).into();
+                    shader.set_uniform_3f(gl, name, x, y, z);
+                }
+                UniformCurve::Vec4(curve) => {
+                    let [x, y, z, w] = curve.sample(t);
+                    shader.set_uniform_4f(gl, name, x, y, z, w);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{animation::Easing, scene::Node};
+    use approx::assert_relative_eq;
+
+    fn advance(timeline: &mut Timeline, delta: f32) {
+        timeline.update(&FrameTime {
+            delta,
+            ..FrameTime::default()
+        });
+    }
+
+    #[test]
+    fn tracks_start_after_their_delay_and_loop() {
+        let mut scene = Scene::new();
+        let id = scene.add(Node::new("node"));
+        let slide = Curve::tween(Vector3::zeros(), Vector3::x(), 1., Easing::Linear);
+        let grow = Curve::tween(Vector3::repeat(1.), Vector3::repeat(2.), 2., Easing::Linear);
+        let mut timeline = Timeline::new()
+            .with_track(Track::new(Binding::Translation(id, slide)).with_delay(1.))
+            .with_track(Track::new(Binding::Scale(id, grow)).with_loop_mode(LoopMode::PingPong));
+        assert_eq!(timeline.duration(), 2.);
+
+        advance(&mut timeline, 0.5);
+        timeline.apply_to_scene(&mut scene);
+        assert_eq!(scene.local(id).translation, Vector3::zeros());
+        assert_relative_eq!(scene.local(id).scale, Vector3::repeat(1.25));
+
+        advance(&mut timeline, 1.);
+        timeline.apply_to_scene(&mut scene);
+        assert_relative_eq!(scene.local(id).translation, Vector3::x() * 0.5);
+
+        advance(&mut timeline, 1.5);
+        timeline.apply_to_scene(&mut scene);
+        assert!(timeline.is_playing() && !timeline.is_finished());
+        assert_eq!(scene.local(id).translation, Vector3::x());
+        assert_relative_eq!(scene.local(id).scale, Vector3::repeat(1.5));
+
+        timeline.pause();
+        advance(&mut timeline, 1.);
+        assert_eq!(timeline.time(), 3.);
+    }
+}
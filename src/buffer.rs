@@ -0,0 +1,421 @@
+use crate::{
+    gl_call,
+    renderer::gl::{
+        self,
+        types::{GLbitfield, GLenum, GLuint},
+    },
+};
+use bytemuck::Pod;
+use std::{
+    cell::Cell,
+    marker::PhantomData,
+    mem::{size_of, size_of_val, MaybeUninit},
+    ops::{Deref, DerefMut, Range},
+    ptr::null,
+    slice,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferTarget {
+    Array,
+    Element,
+    Uniform,
+    PixelPack,
+    PixelUnpack,
+    CopyRead,
+    CopyWrite,
+    TransformFeedback,
+}
+
+impl BufferTarget {
+    pub fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Array => gl::ARRAY_BUFFER,
+            Self::Element => gl::ELEMENT_ARRAY_BUFFER,
+            Self::Uniform => gl::UNIFORM_BUFFER,
+            Self::PixelPack => gl::PIXEL_PACK_BUFFER,
+            Self::PixelUnpack => gl::PIXEL_UNPACK_BUFFER,
+            Self::CopyRead => gl::COPY_READ_BUFFER,
+            Self::CopyWrite => gl::COPY_WRITE_BUFFER,
+            Self::TransformFeedback => gl::TRANSFORM_FEEDBACK_BUFFER,
+        }
+    }
+
+    /// Whether the gpu writes into buffers on this target for the cpu to read.
+    fn is_read_back(&self) -> bool {
+        matches!(self, Self::PixelPack | Self::TransformFeedback)
+    }
+}
+
+/// How often the contents are expected to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferUsage {
+    /// Written once, drawn many times.
+    Static,
+    /// Rewritten now and then, drawn many times.
+    Dynamic,
+    /// Rewritten about every time it is used.
+    Stream,
+}
+
+impl BufferUsage {
+    fn gl_enum(&self, target: BufferTarget) -> GLenum {
+        match (self, target.is_read_back()) {
+            (Self::Static, false) => gl::STATIC_DRAW,
+            (Self::Dynamic, false) => gl::DYNAMIC_DRAW,
+            (Self::Stream, false) => gl::STREAM_DRAW,
+            (Self::Static, true) => gl::STATIC_READ,
+            (Self::Dynamic, true) => gl::DYNAMIC_READ,
+            (Self::Stream, true) => gl::STREAM_READ,
+        }
+    }
+}
+
+/// What a [`BufferMapping`] is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapAccess {
+    Read,
+    /// Overwrite the mapped range, its previous contents are discarded. The
+    /// mapping can only be written, see [`BufferMapping::write`].
+    Write,
+    ReadWrite,
+}
+
+impl MapAccess {
+    fn gl_bits(&self) -> GLbitfield {
+        match self {
+            Self::Read => gl::MAP_READ_BIT,
+            Self::Write => gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_RANGE_BIT,
+            Self::ReadWrite => gl::MAP_READ_BIT | gl::MAP_WRITE_BIT,
+        }
+    }
+}
+
+/// A gl buffer object holding `T`s.
+pub struct Buffer<T: Pod = u8> {
+    id: GLuint,
+    target: BufferTarget,
+    usage: BufferUsage,
+    len: Cell<usize>,
+    // Gl objects belong to the thread of their context.
+    _marker: PhantomData<(T, *const ())>,
+}
+
+impl<T: Pod> Buffer<T> {
+    /// Create a buffer for `target` filled with `data`.
+    ///
+    /// The buffer is left bound, so vertex attributes can be set up right after.
+    pub fn new(gl: &gl::Gl, target: BufferTarget, data: &[T], usage: BufferUsage) -> Self {
+        let buffer = Self::create(gl, target, usage);
+        buffer.store(gl, data.len(), data.as_ptr() as *const _);
+        buffer
+    }
+
+    /// Create a buffer with room for `len` elements of undefined contents,
+    /// left bound.
+    pub fn with_len(gl: &gl::Gl, target: BufferTarget, len: usize, usage: BufferUsage) -> Self {
+        let buffer = Self::create(gl, target, usage);
+        buffer.store(gl, len, null());
+        buffer
+    }
+
+    fn create(gl: &gl::Gl, target: BufferTarget, usage: BufferUsage) -> Self {
+        let mut id = 0;
+        gl_call!(gl, GenBuffers(1, &mut id));
+
+        Self {
+            id,
+            target,
+            usage,
+            len: Cell::new(0),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Bind, then allocate new storage for `len` elements from `data`, or
+    /// uninitialized if null.
+    fn store(&self, gl: &gl::Gl, len: usize, data: *const std::ffi::c_void) {
+        // In OpenGl, `bind` means `select`.
+        self.bind(gl);
+        gl_call!(
+            gl,
+            BufferData(
+                self.target.gl_enum(),
+                (len * size_of::<T>()) as isize,
+                data,
+                self.usage.gl_enum(self.target)
+            )
+        );
+        self.len.set(len);
+    }
+
+    /// Overwrite the elements in `range` with `data`.
+    ///
+    /// Panics if the lengths differ or the range is out of bounds.
+    pub fn update(&self, gl: &gl::Gl, range: Range<usize>, data: &[T]) {
+        assert_eq!(range.len(), data.len(), "Update range and data differ.");
+        self.check_range(&range);
+
+        self.bind(gl);
+        gl_call!(
+            gl,
+            BufferSubData(
+                self.target.gl_enum(),
+                (range.start * size_of::<T>()) as isize,
+                size_of_val(data) as isize,
+                data.as_ptr() as *const _
+            )
+        );
+    }
+
+    /// Detach the current storage and allocate fresh storage of the same size,
+    /// so writing the next contents does not wait on draws still reading the
+    /// previous ones.
+    pub fn orphan(&self, gl: &gl::Gl) {
+        self.store(gl, self.len(), null());
+    }
+
+    /// Replace all contents with `data`, resizing the buffer if needed.
+    ///
+    /// Streams through fresh storage, see [`Buffer::orphan`].
+    pub fn replace(&self, gl: &gl::Gl, data: &[T]) {
+        self.store(gl, data.len(), null());
+        if !data.is_empty() {
+            self.update(gl, 0..data.len(), data);
+        }
+    }
+
+    /// Map the elements in `range` into client memory until the returned
+    /// guard drops.
+    pub fn map<'a>(
+        &'a self,
+        gl: &'a gl::Gl,
+        range: Range<usize>,
+        access: MapAccess,
+    ) -> BufferMapping<'a, T> {
+        self.check_range(&range);
+        if range.is_empty() {
+            // Gl refuses empty mappings.
+            return BufferMapping {
+                gl,
+                buffer: self,
+                ptr: std::ptr::null_mut(),
+                len: 0,
+                access,
+            };
+        }
+
+        self.bind(gl);
+        let ptr = gl_call!(
+            gl,
+            MapBufferRange(
+                self.target.gl_enum(),
+                (range.start * size_of::<T>()) as isize,
+                (range.len() * size_of::<T>()) as isize,
+                access.gl_bits()
+            )
+        );
+        assert!(!ptr.is_null(), "Mapping buffer {} failed.", self.id);
+
+        BufferMapping {
+            gl,
+            buffer: self,
+            ptr: ptr as *mut T,
+            len: range.len(),
+            access,
+        }
+    }
+
+    /// Copy `src_range` of `src` into this buffer, starting at element `dst_offset`.
+    /// Runs on the gpu without a round trip through client memory.
+    pub fn copy_from(
+        &self,
+        gl: &gl::Gl,
+        src: &Buffer<T>,
+        src_range: Range<usize>,
+        dst_offset: usize,
+    ) {
+        src.check_range(&src_range);
+        self.check_range(&(dst_offset..dst_offset + src_range.len()));
+
+        gl_call!(gl, BindBuffer(gl::COPY_READ_BUFFER, src.id));
+        gl_call!(gl, BindBuffer(gl::COPY_WRITE_BUFFER, self.id));
+        gl_call!(
+            gl,
+            CopyBufferSubData(
+                gl::COPY_READ_BUFFER,
+                gl::COPY_WRITE_BUFFER,
+                (src_range.start * size_of::<T>()) as isize,
+                (dst_offset * size_of::<T>()) as isize,
+                (src_range.len() * size_of::<T>()) as isize
+            )
+        );
+        gl_call!(gl, BindBuffer(gl::COPY_READ_BUFFER, 0));
+        gl_call!(gl, BindBuffer(gl::COPY_WRITE_BUFFER, 0));
+    }
+
+    pub fn bind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindBuffer(self.target.gl_enum(), self.id));
+    }
+
+    pub fn unbind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindBuffer(self.target.gl_enum(), 0));
+    }
+
+    /// Bind to an indexed uniform or transform feedback binding point.
+    pub fn bind_base(&self, gl: &gl::Gl, index: u32) {
+        assert!(
+            matches!(
+                self.target,
+                BufferTarget::Uniform | BufferTarget::TransformFeedback
+            ),
+            "Only uniform and transform feedback buffers have binding points."
+        );
+        gl_call!(gl, BindBufferBase(self.target.gl_enum(), index, self.id));
+    }
+
+    /// Forget the element type, e.g. to keep differently typed buffers in
+    /// the `ResourceManager`.
+    pub fn into_untyped(self) -> Buffer {
+        Buffer {
+            id: self.id,
+            target: self.target,
+            usage: self.usage,
+            len: Cell::new(self.size()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn target(&self) -> BufferTarget {
+        self.target
+    }
+
+    pub fn usage(&self) -> BufferUsage {
+        self.usage
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size in bytes.
+    pub fn size(&self) -> usize {
+        self.len() * size_of::<T>()
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe { gl.DeleteBuffers(1, &self.id) }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "Range {:?} is out of bounds of buffer {} with {} elements.",
+            range,
+            self.id,
+            self.len()
+        );
+    }
+}
+
+/// A mapped range of a [`Buffer`], unmapped when dropped.
+///
+/// Read and read-write mappings deref to the mapped elements. The contents
+/// of a [`MapAccess::Write`] mapping are undefined until written, so it only
+/// hands them out as [`BufferMapping::write`] or
+/// [`BufferMapping::as_uninit_mut`].
+pub struct BufferMapping<'a, T: Pod> {
+    gl: &'a gl::Gl,
+    buffer: &'a Buffer<T>,
+    ptr: *mut T,
+    len: usize,
+    access: MapAccess,
+}
+
+impl<T: Pod> BufferMapping<'_, T> {
+    /// Copy `data` into the mapping, starting at element `offset`.
+    ///
+    /// Panics for read only mappings or if `data` does not fit.
+    pub fn write(&mut self, offset: usize, data: &[T]) {
+        assert!(
+            offset + data.len() <= self.len,
+            "Writing {} elements at {} overflows a mapping of {}.",
+            data.len(),
+            offset,
+            self.len
+        );
+        let dst = &mut self.as_uninit_mut()[offset..offset + data.len()];
+        for (dst, src) in dst.iter_mut().zip(data) {
+            dst.write(*src);
+        }
+    }
+
+    /// The mapped elements as possibly uninitialized memory.
+    ///
+    /// Panics for read only mappings.
+    pub fn as_uninit_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        assert!(
+            self.access != MapAccess::Read,
+            "Buffer was mapped for reading only."
+        );
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.ptr as *mut MaybeUninit<T>, self.len) }
+    }
+}
+
+impl<T: Pod> Deref for BufferMapping<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        assert!(
+            self.access != MapAccess::Write,
+            "Buffer was mapped for writing only, its contents are undefined."
+        );
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T: Pod> DerefMut for BufferMapping<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        assert!(
+            self.access == MapAccess::ReadWrite,
+            "Only read-write mappings can be accessed as elements."
+        );
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T: Pod> Drop for BufferMapping<'_, T> {
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+
+        let gl = self.gl;
+        self.buffer.bind(gl);
+        let intact = gl_call!(gl, UnmapBuffer(self.buffer.target.gl_enum()));
+        if intact == gl::FALSE {
+            eprintln!(
+                "Contents of buffer {} were lost while it was mapped.",
+                self.buffer.id
+            );
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::f32::consts::FRAC_PI_2;
+
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+use super::{Camera, Projection};
+use crate::input::InputState;
+
+// Action names, bound in the input config.
+const FORWARD: &str = "camera_forward";
+const BACK: &str = "camera_back";
+const LEFT: &str = "camera_left";
+const RIGHT: &str = "camera_right";
+const UP: &str = "camera_up";
+const DOWN: &str = "camera_down";
+const FAST: &str = "camera_fast";
+const ORBIT: &str = "camera_orbit";
+const PAN: &str = "camera_pan";
+const LOOK: &str = "camera_look";
+
+/// Keeps pitch short of straight up or down, where yaw is undefined.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Moves a [`Camera`] from input, call once per frame from `Sample::update`.
+pub trait CameraController {
+    /// Apply this frame's input, `dt` is the frame time in seconds. Returns
+    /// `true` when the camera moved.
+    fn update(&mut self, camera: &mut Camera, input: &InputState, dt: f32) -> bool;
+}
+
+fn yaw_pitch(yaw: f32, pitch: f32) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch)
+}
+
+/// Circles around a target: drag `camera_orbit` to rotate, drag `camera_pan`
+/// to move the target and scroll to zoom.
+#[derive(Debug, Clone)]
+pub struct OrbitController {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Around +Y, in radians.
+    pub yaw: f32,
+    /// Above the target's horizon, in radians.
+    pub pitch: f32,
+    /// Radians per pixel dragged.
+    pub sensitivity: f32,
+    /// Distance factor per line scrolled.
+    pub zoom_step: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Point3<f32>, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.1,
+            max_distance: 1000.,
+            yaw: 0.,
+            pitch: 0.,
+            sensitivity: 0.005,
+            zoom_step: 1.1,
+        }
+    }
+
+    /// Place `camera` according to the controller without any input.
+    pub fn apply(&self, camera: &mut Camera) {
+        let orientation = yaw_pitch(self.yaw, -self.pitch);
+        camera.orientation = orientation;
+        camera.position = self.target - orientation * -Vector3::z() * self.distance;
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, _dt: f32) -> bool {
+        let [dx, dy] = input.cursor_delta();
+        let [_, scroll] = input.scroll();
+        let mut moved = false;
+
+        if input.action_held(ORBIT) && (dx, dy) != (0., 0.) {
+            self.yaw -= dx * self.sensitivity;
+            self.pitch = (self.pitch + dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+            moved = true;
+        }
+
+        if input.action_held(PAN) && (dx, dy) != (0., 0.) {
+            let scale = camera.world_per_pixel(self.distance);
+            self.target += (camera.up() * dy - camera.right() * dx) * scale;
+            moved = true;
+        }
+
+        if scroll != 0. {
+            self.distance = (self.distance * self.zoom_step.powf(-scroll))
+                .clamp(self.min_distance, self.max_distance);
+            moved = true;
+        }
+
+        self.apply(camera);
+        moved
+    }
+}
+
+/// First person flight: `camera_look` toggles a grabbed cursor that turns the
+/// camera, the movement actions fly along the view and `camera_fast` speeds up.
+#[derive(Debug, Clone)]
+pub struct FlyController {
+    /// Around +Y, in radians.
+    pub yaw: f32,
+    /// Above the horizon, in radians.
+    pub pitch: f32,
+    /// Units per second.
+    pub speed: f32,
+    /// Speed factor while `camera_fast` is held.
+    pub fast_factor: f32,
+    /// Radians per unit of mouse motion.
+    pub sensitivity: f32,
+}
+
+impl FlyController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            yaw: 0.,
+            pitch: 0.,
+            speed,
+            fast_factor: 4.,
+            sensitivity: 0.002,
+        }
+    }
+
+    /// Take over the orientation `camera` currently has.
+    pub fn from_camera(camera: &Camera, speed: f32) -> Self {
+        let forward = camera.forward();
+        Self {
+            yaw: (-forward.x).atan2(-forward.z),
+            pitch: forward.y.clamp(-1., 1.).asin(),
+            ..Self::new(speed)
+        }
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, dt: f32) -> bool {
+        let mut moved = false;
+
+        if input.action_pressed(LOOK) {
+            input.set_cursor_grab(!input.cursor_grab());
+        }
+
+        let [dx, dy] = input.mouse_motion();
+        if input.cursor_grab() && (dx, dy) != (0., 0.) {
+            self.yaw -= dx * self.sensitivity;
+            self.pitch = (self.pitch - dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+            moved = true;
+        }
+        camera.orientation = yaw_pitch(self.yaw, self.pitch);
+
+        let direction = camera.forward() * input.axis(BACK, FORWARD)
+            + camera.right() * input.axis(LEFT, RIGHT)
+            + Vector3::y() * input.axis(DOWN, UP);
+        if direction != Vector3::zeros() {
+            let speed = if input.action_held(FAST) {
+                self.speed * self.fast_factor
+            } else {
+                self.speed
+            };
+            camera.position += direction.normalize() * speed * dt;
+            moved = true;
+        }
+
+        moved
+    }
+}
+
+/// 2D navigation for orthographic cameras: drag `camera_pan` to move and
+/// scroll to zoom around the cursor.
+#[derive(Debug, Clone)]
+pub struct PanZoomController {
+    pub min_height: f32,
+    pub max_height: f32,
+    /// Height factor per line scrolled.
+    pub zoom_step: f32,
+}
+
+impl Default for PanZoomController {
+    fn default() -> Self {
+        Self {
+            min_height: 0.01,
+            max_height: 1000.,
+            zoom_step: 1.1,
+        }
+    }
+}
+
+impl CameraController for PanZoomController {
+    fn update(&mut self, camera: &mut Camera, input: &InputState, _dt: f32) -> bool {
+        let Projection::Orthographic { height, .. } = camera.projection else {
+            return false;
+        };
+
+        let [dx, dy] = input.cursor_delta();
+        let [_, scroll] = input.scroll();
+        let mut moved = false;
+
+        if input.action_held(PAN) && (dx, dy) != (0., 0.) {
+            let scale = camera.world_per_pixel(0.);
+            camera.position += (camera.up() * dy - camera.right() * dx) * scale;
+            moved = true;
+        }
+
+        if scroll != 0. {
+            let before = camera.world_per_pixel(0.);
+            let zoomed =
+                (height * self.zoom_step.powf(-scroll)).clamp(self.min_height, self.max_height);
+            if let Projection::Orthographic { height, .. } = &mut camera.projection {
+                *height = zoomed;
+            }
+            let after = camera.world_per_pixel(0.);
+
+            // Keep the point under the cursor in place.
+            if let Some([x, y]) = input.cursor() {
+                let (width, height) = camera.viewport();
+                let x = x - width as f32 / 2.;
+                let y = height as f32 / 2. - y;
+                camera.position += (camera.right() * x + camera.up() * y) * (before - after);
+            }
+            moved = true;
+        }
+
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::ActionMap;
+    use approx::assert_relative_eq;
+    use winit::{
+        dpi::PhysicalPosition,
+        event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
+    };
+
+    fn input() -> InputState {
+        InputState::new(ActionMap::parse("camera_orbit = MouseLeft").unwrap())
+    }
+
+    fn move_cursor(input: &InputState, x: f32, y: f32) {
+        input.handle_event(&WindowEvent::CursorMoved {
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(x as f64, y as f64),
+        });
+    }
+
+    fn scroll(input: &InputState, lines: f32) {
+        input.handle_event(&WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(0., lines),
+            phase: TouchPhase::Moved,
+        });
+    }
+
+    /// The world point shown at pixel `[x, y]` of an orthographic camera.
+    fn world_at(camera: &Camera, [x, y]: [f32; 2]) -> Point3<f32> {
+        let (width, height) = camera.viewport();
+        let scale = camera.world_per_pixel(0.);
+        camera.position
+            + camera.right() * (x - width as f32 / 2.) * scale
+            + camera.up() * (height as f32 / 2. - y) * scale
+    }
+
+    #[test]
+    fn orbit_pitch_stops_short_of_the_poles() {
+        let mut camera = Camera::perspective(1., 0.1, 100.);
+        let mut orbit = OrbitController::new(Point3::new(1., 2., 3.), 5.);
+        let input = input();
+        input.handle_event(&WindowEvent::MouseInput {
+            device_id: unsafe { DeviceId::dummy() },
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+        });
+
+        for dy in [1e5, -1e5] {
+            move_cursor(&input, 0., 0.);
+            input.end_frame();
+            move_cursor(&input, 0., dy);
+            assert!(orbit.update(&mut camera, &input, 0.));
+            input.end_frame();
+
+            assert_relative_eq!(orbit.pitch, MAX_PITCH * dy.signum());
+            // Still a usable view: looking at the target from `distance`.
+            assert!(camera.forward().y.abs() < 1.);
+            assert_relative_eq!(
+                camera.position + camera.forward() * orbit.distance,
+                orbit.target,
+                epsilon = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn pan_zoom_keeps_the_point_under_the_cursor() {
+        let mut camera = Camera::orthographic(10., -1., 1.);
+        camera.position = Point3::new(3., -2., 0.);
+        camera.resize(800, 600);
+        let mut controller = PanZoomController::default();
+        let input = input();
+
+        let cursor = [650., 120.];
+        move_cursor(&input, cursor[0], cursor[1]);
+        input.end_frame();
+        for lines in [2., -5.] {
+            let before = world_at(&camera, cursor);
+            scroll(&input, lines);
+            assert!(controller.update(&mut camera, &input, 0.));
+            input.end_frame();
+
+            assert_relative_eq!(world_at(&camera, cursor), before, epsilon = 1e-4);
+        }
+        let Projection::Orthographic { height, .. } = camera.projection else {
+            unreachable!();
+        };
+        assert_relative_eq!(height, 10. * 1.1f32.powi(3), epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,190 @@
+mod controller;
+
+pub use controller::{CameraController, FlyController, OrbitController, PanZoomController};
+
+use nalgebra::{Isometry3, Matrix4, Point3, Translation3, UnitQuaternion, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Parallel projection showing `height` world units vertically, the width
+    /// follows the aspect ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+    /// Perspective projection with a vertical field of view in radians,
+    /// `far` can be infinite.
+    Perspective { fovy: f32, near: f32, far: f32 },
+}
+
+/// A view into the world, looking down its local -Z axis with +Y up.
+///
+/// Call [`Camera::resize`] from `Sample::resize` so the projection keeps the
+/// window's aspect ratio.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub position: Point3<f32>,
+    pub orientation: UnitQuaternion<f32>,
+    pub projection: Projection,
+    viewport: (u32, u32),
+}
+
+impl Camera {
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            position: Point3::origin(),
+            orientation: UnitQuaternion::identity(),
+            projection,
+            viewport: (1, 1),
+        }
+    }
+
+    pub fn orthographic(height: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Orthographic { height, near, far })
+    }
+
+    pub fn perspective(fovy: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Perspective { fovy, near, far })
+    }
+
+    /// Move to `eye` and turn towards `target`.
+    pub fn look_at(&mut self, eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) {
+        self.position = eye;
+        self.orientation = Isometry3::look_at_rh(&eye, &target, &up).rotation.inverse();
+    }
+
+    /// Follow a new viewport size in pixels.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.viewport = (width.max(1), height.max(1));
+    }
+
+    pub fn viewport(&self) -> (u32, u32) {
+        self.viewport
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.viewport.0 as f32 / self.viewport.1 as f32
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        self.orientation * -Vector3::z()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.orientation * Vector3::x()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.orientation * Vector3::y()
+    }
+
+    /// World to camera space.
+    pub fn view(&self) -> Matrix4<f32> {
+        Isometry3::from_parts(Translation3::from(self.position.coords), self.orientation)
+            .inverse()
+            .to_homogeneous()
+    }
+
+    /// Camera space to clip space.
+    pub fn projection_matrix(&self) -> Matrix4<f32> {
+        let aspect = self.aspect();
+        match self.projection {
+            Projection::Orthographic { height, near, far } => {
+                let (w, h) = (height * aspect / 2., height / 2.);
+                Matrix4::new_orthographic(-w, w, -h, h, near, far)
+            }
+            Projection::Perspective { fovy, near, far } if far.is_infinite() => {
+                // The limit of `new_perspective` as `far` grows.
+                let f = 1. / (fovy / 2.).tan();
+                #[rustfmt::skip]
+                let matrix = Matrix4::new(
+                    f / aspect, 0., 0., 0.,
+                    0., f, 0., 0.,
+                    0., 0., -1., -2. * near,
+                    0., 0., -1., 0.,
+                );
+                matrix
+            }
+            Projection::Perspective { fovy, near, far } => {
+                Matrix4::new_perspective(aspect, fovy, near, far)
+            }
+        }
+    }
+
+    pub fn view_projection(&self) -> Matrix4<f32> {
+        self.projection_matrix() * self.view()
+    }
+
+    /// World units covered by one pixel at `distance` in front of the camera.
+    pub fn world_per_pixel(&self, distance: f32) -> f32 {
+        let height = match self.projection {
+            Projection::Orthographic { height, .. } => height,
+            Projection::Perspective { fovy, .. } => 2. * distance * (fovy / 2.).tan(),
+        };
+        height / self.viewport.1 as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn look_at_gives_an_orthonormal_basis_towards_the_target() {
+        let mut camera = Camera::perspective(1., 0.1, 100.);
+        let (eye, target) = (Point3::new(3., 4., 5.), Point3::new(-1., 0., 2.));
+        camera.look_at(eye, target, Vector3::y());
+
+        let (forward, right, up) = (camera.forward(), camera.right(), camera.up());
+        assert_relative_eq!(forward, (target - eye).normalize(), epsilon = 1e-6);
+        for axis in [forward, right, up] {
+            assert_relative_eq!(axis.norm(), 1., epsilon = 1e-6);
+        }
+        assert_relative_eq!(forward.dot(&right), 0., epsilon = 1e-6);
+        assert_relative_eq!(forward.dot(&up), 0., epsilon = 1e-6);
+        assert_relative_eq!(right.dot(&up), 0., epsilon = 1e-6);
+        assert_relative_eq!(right.cross(&up), -forward, epsilon = 1e-6);
+        assert!(up.y > 0.);
+
+        // The view puts the eye at the origin and the target down -Z.
+        let view = camera.view();
+        assert_relative_eq!(view.transform_point(&eye), Point3::origin(), epsilon = 1e-5);
+        let distance = (target - eye).norm();
+        assert_relative_eq!(
+            view.transform_point(&target),
+            Point3::new(0., 0., -distance),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn projection_follows_the_aspect_after_resize() {
+        let mut camera = Camera::perspective(1., 0.1, 100.);
+        camera.resize(800, 400);
+        assert_relative_eq!(camera.aspect(), 2.);
+        let matrix = camera.projection_matrix();
+        assert_relative_eq!(matrix[(1, 1)] / matrix[(0, 0)], 2., epsilon = 1e-6);
+
+        // The infinite variant and orthographic keep the same relation.
+        camera.projection = Projection::Perspective {
+            fovy: 1.,
+            near: 0.1,
+            far: f32::INFINITY,
+        };
+        camera.resize(300, 600);
+        let matrix = camera.projection_matrix();
+        assert_relative_eq!(matrix[(1, 1)] / matrix[(0, 0)], 0.5, epsilon = 1e-6);
+
+        camera.projection = Projection::Orthographic {
+            height: 10.,
+            near: 0.,
+            far: 1.,
+        };
+        camera.resize(1280, 720);
+        let matrix = camera.projection_matrix();
+        assert_relative_eq!(matrix[(1, 1)] / matrix[(0, 0)], 16. / 9., epsilon = 1e-6);
+
+        // A minimized window doesn't divide by zero.
+        camera.resize(0, 0);
+        assert_eq!(camera.viewport(), (1, 1));
+        assert!(camera.projection_matrix().iter().all(|v| v.is_finite()));
+    }
+}
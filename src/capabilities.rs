@@ -0,0 +1,244 @@
+use crate::{
+    gl_call,
+    renderer::{get_gl_string, gl},
+    texture::{CompressionFamily, TextureFormat},
+};
+use std::{collections::HashSet, ffi::CStr};
+
+/// Version, limits and extensions of the current gl context.
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    gles: bool,
+    version: (u32, u32),
+    extensions: HashSet<String>,
+    max_texture_size: i32,
+    max_texture_units: i32,
+    max_renderbuffer_size: i32,
+    max_color_attachments: i32,
+    max_draw_buffers: i32,
+    max_samples: i32,
+    max_uniform_block_size: i32,
+}
+
+impl Capabilities {
+    pub fn query(gl: &gl::Gl) -> Self {
+        let version_str = get_gl_string(gl, gl::VERSION)
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (gles, version) = parse_version(&version_str);
+
+        let mut extensions = HashSet::new();
+        if gl.GetStringi.is_loaded() && version.0 >= 3 {
+            let mut count = 0;
+            gl_call!(gl, GetIntegerv(gl::NUM_EXTENSIONS, &mut count));
+            for i in 0..count.max(0) as u32 {
+                let s = gl_call!(gl, GetStringi(gl::EXTENSIONS, i));
+                if !s.is_null() {
+                    let ext = unsafe { CStr::from_ptr(s.cast()) };
+                    extensions.insert(ext.to_string_lossy().into_owned());
+                }
+            }
+        } else if let Some(s) = get_gl_string(gl, gl::EXTENSIONS) {
+            // Legacy contexts only expose the space separated extension string.
+            extensions.extend(s.to_string_lossy().split_whitespace().map(str::to_owned));
+        }
+
+        let mut max_texture_size = 0;
+        gl_call!(gl, GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size));
+        let mut max_texture_units = 0;
+        gl_call!(
+            gl,
+            GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut max_texture_units)
+        );
+        let mut max_renderbuffer_size = 0;
+        gl_call!(
+            gl,
+            GetIntegerv(gl::MAX_RENDERBUFFER_SIZE, &mut max_renderbuffer_size)
+        );
+        let mut max_color_attachments = 0;
+        gl_call!(
+            gl,
+            GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments)
+        );
+        let mut max_draw_buffers = 0;
+        gl_call!(gl, GetIntegerv(gl::MAX_DRAW_BUFFERS, &mut max_draw_buffers));
+        let mut max_samples = 0;
+        gl_call!(gl, GetIntegerv(gl::MAX_SAMPLES, &mut max_samples));
+        let mut max_uniform_block_size = 0;
+        gl_call!(
+            gl,
+            GetIntegerv(gl::MAX_UNIFORM_BLOCK_SIZE, &mut max_uniform_block_size)
+        );
+
+        Self {
+            gles,
+            version,
+            extensions,
+            max_texture_size,
+            max_texture_units,
+            max_renderbuffer_size,
+            max_color_attachments,
+            max_draw_buffers,
+            max_samples,
+            max_uniform_block_size,
+        }
+    }
+
+    /// The least a desktop GL 3.3 context guarantees, for tests without one.
+    #[cfg(test)]
+    pub(crate) fn gl33() -> Self {
+        Self {
+            gles: false,
+            version: (3, 3),
+            extensions: HashSet::new(),
+            max_texture_size: 1024,
+            max_texture_units: 48,
+            max_renderbuffer_size: 1024,
+            max_color_attachments: 8,
+            max_draw_buffers: 8,
+            max_samples: 4,
+            max_uniform_block_size: 16384,
+        }
+    }
+
+    /// Whether the context is OpenGL ES rather than desktop OpenGL.
+    pub fn is_gles(&self) -> bool {
+        self.gles
+    }
+
+    /// `(major, minor)` version of the context.
+    pub fn version(&self) -> (u32, u32) {
+        self.version
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    pub fn max_texture_size(&self) -> i32 {
+        self.max_texture_size
+    }
+
+    pub fn max_texture_units(&self) -> i32 {
+        self.max_texture_units
+    }
+
+    pub fn max_renderbuffer_size(&self) -> i32 {
+        self.max_renderbuffer_size
+    }
+
+    pub fn max_color_attachments(&self) -> i32 {
+        self.max_color_attachments
+    }
+
+    pub fn max_draw_buffers(&self) -> i32 {
+        self.max_draw_buffers
+    }
+
+    /// Highest sample count of multisampled renderbuffers.
+    pub fn max_samples(&self) -> i32 {
+        self.max_samples
+    }
+
+    /// Largest uniform block in bytes, at least 16384.
+    pub fn max_uniform_block_size(&self) -> i32 {
+        self.max_uniform_block_size
+    }
+
+    /// Whether `format` can be rendered to. Every uncompressed format can on
+    /// desktop, GLES 3.0 needs extensions for float color buffers.
+    pub fn is_renderable(&self, format: TextureFormat) -> bool {
+        if format.is_compressed() {
+            return false;
+        }
+        if !self.gles || format.is_depth() || !format.is_float() {
+            return true;
+        }
+
+        match format {
+            TextureFormat::R16f | TextureFormat::Rg16f | TextureFormat::Rgba16f => {
+                self.has_extension("GL_EXT_color_buffer_half_float")
+                    || self.has_extension("GL_EXT_color_buffer_float")
+            }
+            _ => self.has_extension("GL_EXT_color_buffer_float"),
+        }
+    }
+
+    /// Whether `format` can be sampled with linear filtering. 32 bit floats
+    /// need `GL_OES_texture_float_linear` on GLES, depth is compared rather
+    /// than filtered and left to point sampling.
+    pub fn is_filterable(&self, format: TextureFormat) -> bool {
+        match format {
+            TextureFormat::R32f | TextureFormat::Rg32f | TextureFormat::Rgba32f => {
+                !self.gles || self.has_extension("GL_OES_texture_float_linear")
+            }
+            _ => !format.is_depth(),
+        }
+    }
+
+    /// Whether depth buffers can be read back with `glReadPixels`, which
+    /// GLES only allows with `GL_NV_read_depth`.
+    pub fn can_read_depth(&self) -> bool {
+        !self.gles || self.has_extension("GL_NV_read_depth")
+    }
+
+    /// Whether blocks of the given compression family can be uploaded with
+    /// `glCompressedTexImage2D`.
+    pub fn supports_compression(&self, family: CompressionFamily) -> bool {
+        let at_least = |gles: bool, major: u32, minor: u32| {
+            self.gles == gles && self.version >= (major, minor)
+        };
+
+        match family {
+            // Mandatory in GLES 3.0, desktop gets it through ES3 compatibility.
+            CompressionFamily::Etc2 => {
+                at_least(true, 3, 0)
+                    || at_least(false, 4, 3)
+                    || self.has_extension("GL_ARB_ES3_compatibility")
+            }
+            CompressionFamily::S3tc => {
+                self.has_extension("GL_EXT_texture_compression_s3tc")
+                    || self.has_extension("GL_EXT_texture_compression_dxt1")
+                        && self.has_extension("GL_ANGLE_texture_compression_dxt3")
+                        && self.has_extension("GL_ANGLE_texture_compression_dxt5")
+            }
+            CompressionFamily::Rgtc => {
+                at_least(false, 3, 0)
+                    || self.has_extension("GL_EXT_texture_compression_rgtc")
+                    || self.has_extension("GL_ARB_texture_compression_rgtc")
+            }
+            CompressionFamily::Bptc => {
+                at_least(false, 4, 2)
+                    || self.has_extension("GL_EXT_texture_compression_bptc")
+                    || self.has_extension("GL_ARB_texture_compression_bptc")
+            }
+            CompressionFamily::Astc => {
+                at_least(true, 3, 2) || self.has_extension("GL_KHR_texture_compression_astc_ldr")
+            }
+        }
+    }
+
+    /// Whether the sRGB variants of S3TC formats are available.
+    pub fn supports_s3tc_srgb(&self) -> bool {
+        self.supports_compression(CompressionFamily::S3tc)
+            && (self.has_extension("GL_EXT_texture_sRGB")
+                || self.has_extension("GL_EXT_texture_compression_s3tc_srgb")
+                || self.has_extension("GL_NV_sRGB_formats"))
+    }
+}
+
+/// Parse strings like `"4.6 (Core Profile) Mesa 23.0"` or `"OpenGL ES 3.2 NVIDIA"`.
+fn parse_version(version: &str) -> (bool, (u32, u32)) {
+    let (gles, rest) = match version.strip_prefix("OpenGL ES") {
+        Some(rest) => (true, rest.trim_start_matches(|c: char| !c.is_ascii_digit())),
+        None => (false, version),
+    };
+
+    let mut numbers = rest
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse::<u32>().unwrap_or(0));
+    let major = numbers.next().unwrap_or(0);
+    let minor = numbers.next().unwrap_or(0);
+
+    (gles, (major, minor))
+}
@@ -0,0 +1,254 @@
+use std::time::{Duration, Instant};
+
+/// Longest delta a single tick may report, so a stall such as a dragged
+/// window does not turn into a burst of fixed updates.
+const MAX_DELTA: f64 = 0.25;
+
+/// Default fixed update rate.
+pub const DEFAULT_FIXED_STEP: f64 = 1.0 / 60.0;
+
+/// Timing of the current frame, all in scaled seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameTime {
+    /// Time since the previous frame, 0 while paused.
+    pub delta: f32,
+    /// Time since the clock started, not counting pauses.
+    pub total: f64,
+    /// Number of ticks so far, starting at 0.
+    pub frame: u64,
+    /// How far the current frame is between the last two fixed updates, in
+    /// `[0, 1)`, for interpolating their results.
+    pub alpha: f32,
+}
+
+/// Where time comes from.
+#[derive(Debug, Clone, Copy)]
+enum TimeSource {
+    /// The wall clock.
+    Real { last: Option<Instant> },
+    /// Every tick advances by the same step, so runs are reproducible.
+    Fixed { step: f64 },
+}
+
+/// Frame timing with a fixed timestep accumulator.
+///
+/// Call [`FrameClock::tick`] once per frame, then run one fixed update per
+/// [`FrameClock::step_fixed`] that returns `true`:
+///
+/// ```ignore
+/// let time = clock.tick();
+/// while clock.step_fixed() {
+///     physics.step(clock.fixed_step() as f32);
+/// }
+/// let alpha = clock.time().alpha;
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameClock {
+    source: TimeSource,
+    scale: f64,
+    paused: bool,
+    fixed_step: f64,
+    accumulator: f64,
+    ticks: u64,
+    time: FrameTime,
+}
+
+impl FrameClock {
+    /// A clock following the wall clock.
+    pub fn new() -> Self {
+        Self::with_source(TimeSource::Real { last: None })
+    }
+
+    /// A deterministic clock advancing `step` seconds per tick, for headless
+    /// and reproducible runs.
+    pub fn fixed(step: f64) -> Self {
+        assert!(step > 0.0, "The step of a fixed clock must be positive.");
+        Self::with_source(TimeSource::Fixed { step })
+    }
+
+    fn with_source(source: TimeSource) -> Self {
+        Self {
+            source,
+            scale: 1.0,
+            paused: false,
+            fixed_step: DEFAULT_FIXED_STEP,
+            accumulator: 0.0,
+            ticks: 0,
+            time: FrameTime::default(),
+        }
+    }
+
+    /// Advance to the next frame.
+    pub fn tick(&mut self) -> FrameTime {
+        let raw = match &mut self.source {
+            TimeSource::Real { last } => {
+                let now = Instant::now();
+                let delta = last.map_or(Duration::ZERO, |last| now - last);
+                *last = Some(now);
+                delta.as_secs_f64().min(MAX_DELTA)
+            }
+            TimeSource::Fixed { step } => *step,
+        };
+
+        let delta = if self.paused { 0.0 } else { raw * self.scale };
+
+        self.time.frame = self.ticks;
+        self.ticks += 1;
+        self.time.delta = delta as f32;
+        self.time.total += delta;
+        self.accumulator += delta;
+        self.time.alpha = self.alpha();
+
+        self.time
+    }
+
+    /// Consume one fixed step from the time accumulated by [`FrameClock::tick`],
+    /// returns `false` once less than a step is left.
+    pub fn step_fixed(&mut self) -> bool {
+        if self.accumulator < self.fixed_step {
+            return false;
+        }
+
+        self.accumulator -= self.fixed_step;
+        self.time.alpha = self.alpha();
+        true
+    }
+
+    fn alpha(&self) -> f32 {
+        (self.accumulator / self.fixed_step).clamp(0.0, 1.0) as f32
+    }
+
+    /// The current frame, as returned by the last [`FrameClock::tick`].
+    pub fn time(&self) -> FrameTime {
+        self.time
+    }
+
+    pub fn fixed_step(&self) -> f64 {
+        self.fixed_step
+    }
+
+    pub fn set_fixed_step(&mut self, step: f64) {
+        assert!(step > 0.0, "The fixed step must be positive.");
+        self.fixed_step = step;
+    }
+
+    /// Whether the clock ignores wall clock time and advances by fixed steps.
+    pub fn is_deterministic(&self) -> bool {
+        matches!(self.source, TimeSource::Fixed { .. })
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stop time, ticks keep counting frames but report no delta.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Speed time up or slow it down, 1 is real time.
+    pub fn set_scale(&mut self, scale: f64) {
+        assert!(scale >= 0.0, "Time can not run backwards.");
+        self.scale = scale;
+    }
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the fixed updates of one tick, returns how many ran.
+    fn fixed_updates(clock: &mut FrameClock) -> usize {
+        let mut count = 0;
+        while clock.step_fixed() {
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn fixed_clock_advances_by_its_step() {
+        let mut clock = FrameClock::fixed(0.5);
+        assert!(clock.is_deterministic());
+
+        let first = clock.tick();
+        let second = clock.tick();
+        assert_eq!((first.frame, first.delta, first.total), (0, 0.5, 0.5));
+        assert_eq!((second.frame, second.delta, second.total), (1, 0.5, 1.));
+    }
+
+    #[test]
+    fn fixed_updates_carry_the_remainder() {
+        // Three fixed steps per two ticks.
+        let mut clock = FrameClock::fixed(0.375);
+        clock.set_fixed_step(0.25);
+
+        clock.tick();
+        assert_eq!(clock.time().alpha, 1.);
+        assert_eq!(fixed_updates(&mut clock), 1);
+        assert_eq!(clock.time().alpha, 0.5);
+
+        clock.tick();
+        assert_eq!(fixed_updates(&mut clock), 2);
+        assert_eq!(clock.time().alpha, 0.);
+
+        clock.tick();
+        assert_eq!(fixed_updates(&mut clock), 1);
+        assert_eq!(clock.time().alpha, 0.5);
+    }
+
+    #[test]
+    fn slow_ticks_run_no_fixed_update() {
+        let mut clock = FrameClock::fixed(0.0625);
+        clock.set_fixed_step(0.25);
+
+        for alpha in [0.25, 0.5, 0.75] {
+            assert_eq!(clock.tick().alpha, alpha);
+            assert_eq!(fixed_updates(&mut clock), 0);
+        }
+        assert_eq!(clock.tick().alpha, 1.);
+        assert_eq!(fixed_updates(&mut clock), 1);
+        assert_eq!(clock.time().alpha, 0.);
+    }
+
+    #[test]
+    fn pause_stops_time_but_counts_frames() {
+        let mut clock = FrameClock::fixed(0.25);
+        clock.set_fixed_step(0.25);
+        clock.tick();
+        clock.set_paused(true);
+
+        let paused = clock.tick();
+        assert_eq!((paused.frame, paused.delta, paused.total), (1, 0., 0.25));
+        assert_eq!(fixed_updates(&mut clock), 1);
+        assert_eq!(fixed_updates(&mut clock), 0);
+
+        clock.set_paused(false);
+        assert_eq!(clock.tick().total, 0.5);
+    }
+
+    #[test]
+    fn scale_speeds_time_up_and_down() {
+        let mut clock = FrameClock::fixed(0.25);
+        clock.set_fixed_step(0.25);
+
+        clock.set_scale(2.);
+        assert_eq!(clock.tick().delta, 0.5);
+        assert_eq!(fixed_updates(&mut clock), 2);
+
+        clock.set_scale(0.5);
+        let slow = clock.tick();
+        assert_eq!((slow.delta, slow.total, slow.alpha), (0.125, 0.625, 0.5));
+        assert_eq!(fixed_updates(&mut clock), 0);
+    }
+}
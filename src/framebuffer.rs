@@ -0,0 +1,662 @@
+use crate::{
+    capabilities::Capabilities,
+    gl_call,
+    renderer::gl::{
+        self,
+        types::{GLbitfield, GLenum, GLuint},
+    },
+    texture::{Texture, TextureFormat},
+};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramebufferError {
+    /// `glCheckFramebufferStatus` did not return `GL_FRAMEBUFFER_COMPLETE`.
+    Incomplete(GLenum),
+    /// The format can not be rendered to on this context.
+    UnsupportedFormat(TextureFormat),
+    /// A depth format used as color or the other way around.
+    WrongAttachmentFormat(TextureFormat),
+    TooManyColorAttachments {
+        requested: usize,
+        max: i32,
+    },
+    TooManySamples {
+        requested: u32,
+        max: i32,
+    },
+    TooLarge {
+        width: u32,
+        height: u32,
+        max: i32,
+    },
+    /// GLES 3.0 has no multisampled textures, only renderbuffers.
+    MultisampledTexture,
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incomplete(status) => {
+                write!(f, "Framebuffer is incomplete: {}", status_name(*status))
+            }
+            Self::UnsupportedFormat(format) => {
+                write!(f, "Format {:?} is not renderable on this device", format)
+            }
+            Self::WrongAttachmentFormat(format) => {
+                write!(f, "Format {:?} does not fit its attachment point", format)
+            }
+            Self::TooManyColorAttachments { requested, max } => write!(
+                f,
+                "{} color attachments requested, at most {} are supported",
+                requested, max
+            ),
+            Self::TooManySamples { requested, max } => write!(
+                f,
+                "{} samples requested, at most {} are supported",
+                requested, max
+            ),
+            Self::TooLarge { width, height, max } => write!(
+                f,
+                "Framebuffer of {}x{} exceeds the maximum size of {}",
+                width, height, max
+            ),
+            Self::MultisampledTexture => write!(
+                f,
+                "Multisampled attachments must be renderbuffers, resolve into a texture instead"
+            ),
+        }
+    }
+}
+
+impl Error for FramebufferError {}
+
+fn status_name(status: GLenum) -> String {
+    match status {
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT".into(),
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+            "GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT".into()
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => "GL_FRAMEBUFFER_INCOMPLETE_DIMENSIONS".into(),
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE".into(),
+        gl::FRAMEBUFFER_UNSUPPORTED => "GL_FRAMEBUFFER_UNSUPPORTED".into(),
+        gl::FRAMEBUFFER_UNDEFINED => "GL_FRAMEBUFFER_UNDEFINED".into(),
+        _ => format!("0x{:04X}", status),
+    }
+}
+
+/// A gl renderbuffer, storage that can be rendered to but not sampled.
+pub struct Renderbuffer {
+    id: GLuint,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    samples: u32,
+}
+
+impl Renderbuffer {
+    pub fn new(gl: &gl::Gl, width: u32, height: u32, format: TextureFormat, samples: u32) -> Self {
+        let mut id = 0;
+        gl_call!(gl, GenRenderbuffers(1, &mut id));
+        gl_call!(gl, BindRenderbuffer(gl::RENDERBUFFER, id));
+        gl_call!(
+            gl,
+            RenderbufferStorageMultisample(
+                gl::RENDERBUFFER,
+                samples as i32,
+                format.gl_internal_format(),
+                width as i32,
+                height as i32
+            )
+        );
+        gl_call!(gl, BindRenderbuffer(gl::RENDERBUFFER, 0));
+
+        Self {
+            id,
+            width,
+            height,
+            format,
+            samples,
+        }
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe { gl.DeleteRenderbuffers(1, &self.id) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// 0 when not multisampled.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+/// What an attachment is stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    /// Can be sampled afterwards.
+    Texture,
+    /// Render only, required for multisampling.
+    Renderbuffer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachmentDesc {
+    pub format: TextureFormat,
+    pub kind: AttachmentKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramebufferSize {
+    Fixed(u32, u32),
+    /// Always as large as the window, resized by `Renderer::resize`.
+    Surface,
+}
+
+/// Layout of a [`Framebuffer`].
+///
+/// ```ignore
+/// let desc = FramebufferDesc::new(FramebufferSize::Surface)
+///     .with_color(TextureFormat::Rgba8, AttachmentKind::Texture)
+///     .with_depth(TextureFormat::Depth24, AttachmentKind::Renderbuffer);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramebufferDesc {
+    pub size: FramebufferSize,
+    /// Bound to `GL_COLOR_ATTACHMENT0 + i` and fragment output `i`.
+    pub color: Vec<AttachmentDesc>,
+    /// Depth or packed depth-stencil.
+    pub depth_stencil: Option<AttachmentDesc>,
+    /// 0 for a regular framebuffer.
+    pub samples: u32,
+}
+
+impl FramebufferDesc {
+    pub fn new(size: FramebufferSize) -> Self {
+        Self {
+            size,
+            color: vec![],
+            depth_stencil: None,
+            samples: 0,
+        }
+    }
+
+    pub fn with_color(mut self, format: TextureFormat, kind: AttachmentKind) -> Self {
+        self.color.push(AttachmentDesc { format, kind });
+        self
+    }
+
+    pub fn with_depth(mut self, format: TextureFormat, kind: AttachmentKind) -> Self {
+        self.depth_stencil = Some(AttachmentDesc { format, kind });
+        self
+    }
+
+    /// Multisample every attachment, all of them must be renderbuffers.
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    fn validate(
+        &self,
+        caps: &Capabilities,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        let max_color = caps.max_color_attachments().min(caps.max_draw_buffers());
+        if self.color.len() > max_color as usize {
+            return Err(FramebufferError::TooManyColorAttachments {
+                requested: self.color.len(),
+                max: max_color,
+            });
+        }
+        if self.samples as i32 > caps.max_samples() {
+            return Err(FramebufferError::TooManySamples {
+                requested: self.samples,
+                max: caps.max_samples(),
+            });
+        }
+        check_size(caps, width, height)?;
+
+        let color = self.color.iter().map(|attachment| (attachment, false));
+        let depth = self
+            .depth_stencil
+            .iter()
+            .map(|attachment| (attachment, true));
+        for (attachment, depth) in color.chain(depth) {
+            if attachment.format.is_depth() != depth {
+                return Err(FramebufferError::WrongAttachmentFormat(attachment.format));
+            }
+            if !caps.is_renderable(attachment.format) {
+                return Err(FramebufferError::UnsupportedFormat(attachment.format));
+            }
+            if self.samples > 0 && attachment.kind == AttachmentKind::Texture {
+                return Err(FramebufferError::MultisampledTexture);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether attachments of `width`x`height` can be allocated.
+fn check_size(caps: &Capabilities, width: u32, height: u32) -> Result<(), FramebufferError> {
+    let max = caps.max_renderbuffer_size().min(caps.max_texture_size());
+    if width.max(height) > max.max(0) as u32 {
+        return Err(FramebufferError::TooLarge { width, height, max });
+    }
+    Ok(())
+}
+
+enum Attachment {
+    Texture(Texture),
+    Renderbuffer(Renderbuffer),
+}
+
+impl Attachment {
+    fn new(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        desc: &AttachmentDesc,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Self {
+        match desc.kind {
+            AttachmentKind::Texture => {
+                Self::Texture(Texture::new(gl, caps, width, height, desc.format))
+            }
+            AttachmentKind::Renderbuffer => {
+                Self::Renderbuffer(Renderbuffer::new(gl, width, height, desc.format, samples))
+            }
+        }
+    }
+
+    fn attach(&self, gl: &gl::Gl, point: GLenum) {
+        match self {
+            Self::Texture(texture) => gl_call!(
+                gl,
+                FramebufferTexture2D(gl::FRAMEBUFFER, point, gl::TEXTURE_2D, texture.id(), 0)
+            ),
+            Self::Renderbuffer(renderbuffer) => gl_call!(
+                gl,
+                FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    point,
+                    gl::RENDERBUFFER,
+                    renderbuffer.id()
+                )
+            ),
+        }
+    }
+
+    fn texture(&self) -> Option<&Texture> {
+        match self {
+            Self::Texture(texture) => Some(texture),
+            Self::Renderbuffer(_) => None,
+        }
+    }
+
+    fn drop(&self, gl: &gl::Gl) {
+        match self {
+            Self::Texture(texture) => texture.drop(gl),
+            Self::Renderbuffer(renderbuffer) => renderbuffer.drop(gl),
+        }
+    }
+}
+
+/// A gl framebuffer object owning its attachments.
+pub struct Framebuffer {
+    id: GLuint,
+    desc: FramebufferDesc,
+    width: u32,
+    height: u32,
+    color: Vec<Attachment>,
+    depth_stencil: Option<Attachment>,
+}
+
+impl Framebuffer {
+    /// Create the framebuffer and its attachments. `surface` is the window size,
+    /// used when `desc.size` is [`FramebufferSize::Surface`].
+    pub fn new(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        desc: FramebufferDesc,
+        surface: (u32, u32),
+    ) -> Result<Self, FramebufferError> {
+        let (width, height) = match desc.size {
+            FramebufferSize::Fixed(width, height) => (width, height),
+            FramebufferSize::Surface => surface,
+        };
+        desc.validate(caps, width, height)?;
+
+        let mut id = 0;
+        gl_call!(gl, GenFramebuffers(1, &mut id));
+
+        let mut framebuffer = Self {
+            id,
+            desc,
+            width,
+            height,
+            color: vec![],
+            depth_stencil: None,
+        };
+        if let Err(e) = framebuffer.allocate(gl, caps) {
+            framebuffer.drop(gl);
+            return Err(e);
+        }
+
+        Ok(framebuffer)
+    }
+
+    /// (Re)create all attachments at the current size and check completeness.
+    fn allocate(&mut self, gl: &gl::Gl, caps: &Capabilities) -> Result<(), FramebufferError> {
+        self.drop_attachments(gl);
+
+        let (width, height, samples) = (self.width.max(1), self.height.max(1), self.desc.samples);
+        self.color = self
+            .desc
+            .color
+            .iter()
+            .map(|desc| Attachment::new(gl, caps, desc, width, height, samples))
+            .collect();
+        self.depth_stencil = self
+            .desc
+            .depth_stencil
+            .map(|desc| Attachment::new(gl, caps, &desc, width, height, samples));
+
+        gl_call!(gl, BindFramebuffer(gl::FRAMEBUFFER, self.id));
+        for (i, attachment) in self.color.iter().enumerate() {
+            attachment.attach(gl, gl::COLOR_ATTACHMENT0 + i as u32);
+        }
+        if let Some(attachment) = &self.depth_stencil {
+            let point = match self
+                .desc
+                .depth_stencil
+                .map(|desc| desc.format.has_stencil())
+            {
+                Some(true) => gl::DEPTH_STENCIL_ATTACHMENT,
+                _ => gl::DEPTH_ATTACHMENT,
+            };
+            attachment.attach(gl, point);
+        }
+
+        // Fragment output `i` goes to color attachment `i`.
+        let draw_buffers = self.draw_buffers();
+        gl_call!(
+            gl,
+            DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr())
+        );
+        let read_buffer = draw_buffers.first().copied().unwrap_or(gl::NONE);
+        gl_call!(gl, ReadBuffer(read_buffer));
+
+        let status = gl_call!(gl, CheckFramebufferStatus(gl::FRAMEBUFFER));
+        gl_call!(gl, BindFramebuffer(gl::FRAMEBUFFER, 0));
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(FramebufferError::Incomplete(status));
+        }
+        Ok(())
+    }
+
+    fn draw_buffers(&self) -> Vec<GLenum> {
+        (0..self.color.len() as u32)
+            .map(|i| gl::COLOR_ATTACHMENT0 + i)
+            .collect()
+    }
+
+    /// Reallocate the attachments at a new size, their contents are lost.
+    /// Sizes past the context's limits are refused and the old attachments
+    /// kept.
+    pub fn resize(
+        &mut self,
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        width: u32,
+        height: u32,
+    ) -> Result<(), FramebufferError> {
+        if (width, height) == (self.width, self.height) {
+            return Ok(());
+        }
+        check_size(caps, width, height)?;
+
+        self.width = width;
+        self.height = height;
+        self.allocate(gl, caps)
+    }
+
+    /// Render into this framebuffer, also sets the viewport to cover it.
+    pub fn bind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindFramebuffer(gl::FRAMEBUFFER, self.id));
+        gl_call!(gl, Viewport(0, 0, self.width as i32, self.height as i32));
+    }
+
+    /// Copy this framebuffer into `dst`, resolving multisampled attachments.
+    ///
+    /// Each color attachment goes to the one with the same index, depth and
+    /// stencil are copied as well when both sides have them. Sizes must
+    /// match if this framebuffer is multisampled.
+    pub fn resolve(&self, gl: &gl::Gl, dst: &Framebuffer) {
+        gl_call!(gl, BindFramebuffer(gl::READ_FRAMEBUFFER, self.id));
+        gl_call!(gl, BindFramebuffer(gl::DRAW_FRAMEBUFFER, dst.id));
+
+        for i in 0..self.color.len().min(dst.color.len()) as u32 {
+            let attachment = gl::COLOR_ATTACHMENT0 + i;
+            // Blits write to every draw buffer, so leave only the matching one.
+            let mut draw_buffers = vec![gl::NONE; i as usize + 1];
+            draw_buffers[i as usize] = attachment;
+
+            gl_call!(gl, ReadBuffer(attachment));
+            gl_call!(
+                gl,
+                DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr())
+            );
+            self.blit(gl, dst.width, dst.height, gl::COLOR_BUFFER_BIT);
+        }
+
+        let mut mask = 0;
+        if let (Some(src), Some(dst)) = (self.desc.depth_stencil, dst.desc.depth_stencil) {
+            if src.format == dst.format {
+                mask |= gl::DEPTH_BUFFER_BIT;
+                if src.format.has_stencil() {
+                    mask |= gl::STENCIL_BUFFER_BIT;
+                }
+            }
+        }
+        if mask != 0 {
+            self.blit(gl, dst.width, dst.height, mask);
+        }
+
+        // Restore the regular draw and read buffers of `dst` and `self`.
+        let draw_buffers = dst.draw_buffers();
+        gl_call!(
+            gl,
+            DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr())
+        );
+        let read_buffer = self.draw_buffers().first().copied().unwrap_or(gl::NONE);
+        gl_call!(gl, ReadBuffer(read_buffer));
+
+        gl_call!(gl, BindFramebuffer(gl::READ_FRAMEBUFFER, 0));
+        gl_call!(gl, BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0));
+    }
+
+    /// Copy the first color attachment to the window, scaled to `width`x`height`.
+    pub fn blit_to_surface(&self, gl: &gl::Gl, width: u32, height: u32) {
+        gl_call!(gl, BindFramebuffer(gl::READ_FRAMEBUFFER, self.id));
+        gl_call!(gl, BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0));
+        self.blit(gl, width, height, gl::COLOR_BUFFER_BIT);
+        gl_call!(gl, BindFramebuffer(gl::READ_FRAMEBUFFER, 0));
+    }
+
+    fn blit(&self, gl: &gl::Gl, width: u32, height: u32, mask: GLbitfield) {
+        // Depth, stencil and multisample resolves do not allow scaling or filtering.
+        let scaled = (width, height) != (self.width, self.height);
+        let filter = if scaled && mask == gl::COLOR_BUFFER_BIT {
+            gl::LINEAR
+        } else {
+            gl::NEAREST
+        };
+
+        gl_call!(
+            gl,
+            BlitFramebuffer(
+                0,
+                0,
+                self.width as i32,
+                self.height as i32,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                mask,
+                filter
+            )
+        );
+    }
+
+    /// The texture of color attachment `index`, if it is not a renderbuffer.
+    ///
+    /// Changes on resize, so look it up again when using it.
+    pub fn color_texture(&self, index: usize) -> Option<&Texture> {
+        self.color.get(index).and_then(Attachment::texture)
+    }
+
+    pub fn depth_texture(&self) -> Option<&Texture> {
+        self.depth_stencil.as_ref().and_then(Attachment::texture)
+    }
+
+    fn drop_attachments(&mut self, gl: &gl::Gl) {
+        for attachment in self.color.drain(..).chain(self.depth_stencil.take()) {
+            attachment.drop(gl);
+        }
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        for attachment in self.color.iter().chain(self.depth_stencil.iter()) {
+            attachment.drop(gl);
+        }
+        unsafe { gl.DeleteFramebuffers(1, &self.id) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn desc(&self) -> &FramebufferDesc {
+        &self.desc
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn follows_surface(&self) -> bool {
+        self.desc.size == FramebufferSize::Surface
+    }
+}
+
+/// Render to the window again, with a viewport of `width`x`height`.
+pub fn bind_default(gl: &gl::Gl, width: u32, height: u32) {
+    gl_call!(gl, BindFramebuffer(gl::FRAMEBUFFER, 0));
+    gl_call!(gl, Viewport(0, 0, width as i32, height as i32));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(desc: &FramebufferDesc, width: u32, height: u32) -> Result<(), FramebufferError> {
+        desc.validate(&Capabilities::gl33(), width, height)
+    }
+
+    #[test]
+    fn descriptions_within_the_limits_validate() {
+        let desc = FramebufferDesc::new(FramebufferSize::Surface)
+            .with_color(TextureFormat::Rgba8, AttachmentKind::Texture)
+            .with_color(TextureFormat::Rgba16f, AttachmentKind::Texture)
+            .with_depth(TextureFormat::Depth24Stencil8, AttachmentKind::Renderbuffer);
+        assert_eq!(validate(&desc, 1024, 768), Ok(()));
+
+        let multisampled = FramebufferDesc::new(FramebufferSize::Surface)
+            .with_color(TextureFormat::Rgba8, AttachmentKind::Renderbuffer)
+            .with_samples(4);
+        assert_eq!(validate(&multisampled, 640, 480), Ok(()));
+    }
+
+    #[test]
+    fn descriptions_past_the_limits_are_refused() {
+        let color = FramebufferDesc::new(FramebufferSize::Surface)
+            .with_color(TextureFormat::Rgba8, AttachmentKind::Texture);
+
+        assert_eq!(
+            validate(&color, 1025, 16),
+            Err(FramebufferError::TooLarge {
+                width: 1025,
+                height: 16,
+                max: 1024
+            })
+        );
+        assert_eq!(
+            validate(&color.clone().with_samples(4), 16, 16),
+            Err(FramebufferError::MultisampledTexture)
+        );
+        assert_eq!(
+            validate(&color.clone().with_samples(8), 16, 16),
+            Err(FramebufferError::TooManySamples {
+                requested: 8,
+                max: 4
+            })
+        );
+        assert_eq!(
+            validate(
+                &color.with_depth(TextureFormat::Rgba8, AttachmentKind::Texture),
+                16,
+                16
+            ),
+            Err(FramebufferError::WrongAttachmentFormat(
+                TextureFormat::Rgba8
+            ))
+        );
+
+        let mut many = FramebufferDesc::new(FramebufferSize::Surface);
+        for _ in 0..9 {
+            many = many.with_color(TextureFormat::Rgba8, AttachmentKind::Texture);
+        }
+        assert_eq!(
+            validate(&many, 16, 16),
+            Err(FramebufferError::TooManyColorAttachments {
+                requested: 9,
+                max: 8
+            })
+        );
+    }
+
+    #[test]
+    fn resizes_are_held_to_the_same_size_limit() {
+        let caps = Capabilities::gl33();
+        assert_eq!(check_size(&caps, 1024, 1024), Ok(()));
+        assert!(matches!(
+            check_size(&caps, 800, 4096),
+            Err(FramebufferError::TooLarge { max: 1024, .. })
+        ));
+    }
+}
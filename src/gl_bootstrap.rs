@@ -18,6 +18,7 @@ pub fn bootstrap_gl_window(
 ) -> Result<(Window, Config, Option<NotCurrentContext>), Box<dyn Error>> {
     let template = ConfigTemplateBuilder::new()
         .with_alpha_size(8)
+        .with_depth_size(24)
         .with_transparency(cfg!(cgl_backend));
 
     let display_builder = DisplayBuilder::new().with_window_builder(Some(win_builder));
@@ -0,0 +1,206 @@
+use std::{collections::HashMap, fmt, fs, io, path::Path};
+
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+/// A physical input an action can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionMapError::Io(err) => write!(f, "Can not read action map: {}", err),
+            ActionMapError::Parse { line, message } => {
+                write!(f, "Action map line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {}
+
+impl From<io::Error> for ActionMapError {
+    fn from(err: io::Error) -> Self {
+        ActionMapError::Io(err)
+    }
+}
+
+/// Named actions such as `camera_forward`, each bound to any number of keys
+/// or mouse buttons.
+///
+/// The config format is one action per line, `#` starts a comment:
+///
+/// ```text
+/// camera_forward = KeyW, ArrowUp
+/// camera_orbit   = MouseLeft
+/// ```
+///
+/// Keys use the names of winit's `KeyCode` variants, mouse buttons are
+/// `MouseLeft`, `MouseRight`, `MouseMiddle`, `MouseBack` and `MouseForward`.
+#[derive(Debug, Clone, Default)]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<Binding>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ActionMapError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ActionMapError> {
+        let mut map = Self::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let error = |message: String| ActionMapError::Parse {
+                line: index + 1,
+                message,
+            };
+
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (action, bindings) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `action = binding, ...`".into()))?;
+            let action = action.trim();
+            if action.is_empty() || action.contains(char::is_whitespace) {
+                return Err(error(format!("invalid action name `{}`", action)));
+            }
+
+            for name in bindings.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let binding = parse_binding(name)
+                    .ok_or_else(|| error(format!("unknown input `{}`", name)))?;
+                map.bind(action, binding);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Add `binding` to `action`, keeping the ones it already has.
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.bindings.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    /// Bindings of `action`, empty for unknown actions.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.bindings.keys().map(String::as_str)
+    }
+}
+
+fn parse_binding(name: &str) -> Option<Binding> {
+    let button = match name {
+        "MouseLeft" => Some(MouseButton::Left),
+        "MouseRight" => Some(MouseButton::Right),
+        "MouseMiddle" => Some(MouseButton::Middle),
+        "MouseBack" => Some(MouseButton::Back),
+        "MouseForward" => Some(MouseButton::Forward),
+        _ => None,
+    };
+
+    button
+        .map(Binding::Mouse)
+        .or_else(|| parse_key(name).map(Binding::Key))
+}
+
+macro_rules! key_codes {
+    ($($key:ident),* $(,)?) => {
+        fn parse_key(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_codes! {
+    KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM,
+    KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+    Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8,
+    Numpad9, NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide, NumpadDecimal,
+    NumpadEnter,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+    Home, End, PageUp, PageDown, Insert, Delete,
+    Space, Enter, Escape, Tab, Backspace, CapsLock,
+    ShiftLeft, ShiftRight, ControlLeft, ControlRight, AltLeft, AltRight,
+    SuperLeft, SuperRight,
+    Minus, Equal, BracketLeft, BracketRight, Backslash, Semicolon, Quote,
+    Backquote, Comma, Period, Slash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_bindings() {
+        let map = ActionMap::parse(
+            "# camera\n\
+             \n\
+             camera_forward = KeyW, ArrowUp  # both\n\
+             camera_orbit=MouseLeft,\n\
+             camera_forward = KeyW\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            map.bindings("camera_forward"),
+            [Binding::Key(KeyCode::KeyW), Binding::Key(KeyCode::ArrowUp)]
+        );
+        assert_eq!(
+            map.bindings("camera_orbit"),
+            [Binding::Mouse(MouseButton::Left)]
+        );
+        assert!(map.bindings("jump").is_empty());
+        assert_eq!(map.actions().count(), 2);
+    }
+
+    #[test]
+    fn parse_errors_name_their_line() {
+        let cases = [
+            ("jump = Space\njump Space", 2, "expected"),
+            ("\n\nmove left = KeyA", 3, "invalid action name"),
+            (" = KeyA", 1, "invalid action name"),
+            ("# ok\njump = Space, KeyNope", 2, "unknown input `KeyNope`"),
+        ];
+
+        for (source, expected_line, expected_message) in cases {
+            match ActionMap::parse(source) {
+                Err(ActionMapError::Parse { line, message }) => {
+                    assert_eq!(line, expected_line, "{:?}", source);
+                    assert!(message.contains(expected_message), "{}", message);
+                }
+                other => panic!("{:?} parsed as {:?}", source, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+mod actions;
+
+pub use actions::{ActionMap, ActionMapError, Binding};
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use winit::{
+    event::{
+        DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+    },
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+/// Pixels counted as one line when a touchpad reports pixel scrolling.
+const PIXELS_PER_LINE: f32 = 20.;
+
+#[derive(Debug, Default)]
+struct Frame {
+    held: HashSet<Binding>,
+    pressed: HashSet<Binding>,
+    released: HashSet<Binding>,
+
+    cursor: Option<[f32; 2]>,
+    cursor_delta: [f32; 2],
+    motion: [f32; 2],
+    cursor_grab: bool,
+    scroll: [f32; 2],
+    modifiers: ModifiersState,
+
+    touches: HashMap<u64, [f32; 2]>,
+}
+
+impl Frame {
+    fn set(&mut self, binding: Binding, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if self.held.insert(binding) {
+                    self.pressed.insert(binding);
+                }
+            }
+            ElementState::Released => {
+                if self.held.remove(&binding) {
+                    self.released.insert(binding);
+                }
+            }
+        }
+    }
+}
+
+/// Keyboard, mouse and touch state, fed with window events and queried by
+/// samples during update.
+///
+/// Pressed and released report the transitions since the last
+/// [`InputState::end_frame`], held reports the current state.
+#[derive(Debug, Default)]
+pub struct InputState {
+    frame: RefCell<Frame>,
+    actions: ActionMap,
+}
+
+impl InputState {
+    pub fn new(actions: ActionMap) -> Self {
+        Self {
+            frame: RefCell::default(),
+            actions,
+        }
+    }
+
+    pub fn handle_event(&self, event: &WindowEvent) {
+        let mut frame = self.frame.borrow_mut();
+
+        match *event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key),
+                        state,
+                        ..
+                    },
+                ..
+            } => frame.set(Binding::Key(key), state),
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                frame.set(Binding::Mouse(button), state)
+            }
+
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = [position.x as f32, position.y as f32];
+                if let Some([x, y]) = frame.cursor {
+                    frame.cursor_delta[0] += position[0] - x;
+                    frame.cursor_delta[1] += position[1] - y;
+                }
+                frame.cursor = Some(position);
+            }
+
+            WindowEvent::CursorLeft { .. } => frame.cursor = None,
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (x, y) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (x, y),
+                    MouseScrollDelta::PixelDelta(pos) => (
+                        pos.x as f32 / PIXELS_PER_LINE,
+                        pos.y as f32 / PIXELS_PER_LINE,
+                    ),
+                };
+                frame.scroll[0] += x;
+                frame.scroll[1] += y;
+            }
+
+            WindowEvent::ModifiersChanged(modifiers) => frame.modifiers = modifiers.state(),
+
+            WindowEvent::Touch(touch) => match touch.phase {
+                TouchPhase::Started | TouchPhase::Moved => {
+                    let location = [touch.location.x as f32, touch.location.y as f32];
+                    frame.touches.insert(touch.id, location);
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    frame.touches.remove(&touch.id);
+                }
+            },
+
+            // Release everything, the matching events go to another window.
+            WindowEvent::Focused(false) => {
+                let held = std::mem::take(&mut frame.held);
+                frame.released.extend(held);
+                frame.modifiers = ModifiersState::empty();
+                frame.touches.clear();
+                frame.cursor_grab = false;
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Record raw mouse motion, which keeps coming while the cursor is grabbed.
+    pub fn handle_device_event(&self, event: &DeviceEvent) {
+        let mut frame = self.frame.borrow_mut();
+        if let (DeviceEvent::MouseMotion { delta: (x, y) }, true) = (event, frame.cursor_grab) {
+            frame.motion[0] += *x as f32;
+            frame.motion[1] += *y as f32;
+        }
+    }
+
+    /// Forget this frame's transitions and motion, call after the update
+    /// that consumed them.
+    pub fn end_frame(&self) {
+        let mut frame = self.frame.borrow_mut();
+        frame.pressed.clear();
+        frame.released.clear();
+        frame.cursor_delta = [0., 0.];
+        frame.motion = [0., 0.];
+        frame.scroll = [0., 0.];
+    }
+
+    /// Whether anything is held or changed this frame.
+    pub fn is_active(&self) -> bool {
+        let frame = self.frame.borrow();
+        !frame.held.is_empty()
+            || !frame.released.is_empty()
+            || !frame.touches.is_empty()
+            || frame.cursor_delta != [0., 0.]
+            || frame.motion != [0., 0.]
+            || frame.scroll != [0., 0.]
+    }
+
+    pub fn is_held(&self, binding: Binding) -> bool {
+        self.frame.borrow().held.contains(&binding)
+    }
+
+    pub fn was_pressed(&self, binding: Binding) -> bool {
+        self.frame.borrow().pressed.contains(&binding)
+    }
+
+    pub fn was_released(&self, binding: Binding) -> bool {
+        self.frame.borrow().released.contains(&binding)
+    }
+
+    /// Whether `binding` was held when the frame started.
+    fn was_held_before(&self, binding: Binding) -> bool {
+        match (self.was_pressed(binding), self.was_released(binding)) {
+            (false, false) => self.is_held(binding),
+            (true, false) => false,
+            (false, true) => true,
+            // Released and pressed again, or tapped.
+            (true, true) => self.is_held(binding),
+        }
+    }
+
+    pub fn key_held(&self, key: KeyCode) -> bool {
+        self.is_held(Binding::Key(key))
+    }
+
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.was_pressed(Binding::Key(key))
+    }
+
+    pub fn key_released(&self, key: KeyCode) -> bool {
+        self.was_released(Binding::Key(key))
+    }
+
+    pub fn button_held(&self, button: MouseButton) -> bool {
+        self.is_held(Binding::Mouse(button))
+    }
+
+    pub fn button_pressed(&self, button: MouseButton) -> bool {
+        self.was_pressed(Binding::Mouse(button))
+    }
+
+    pub fn button_released(&self, button: MouseButton) -> bool {
+        self.was_released(Binding::Mouse(button))
+    }
+
+    /// Cursor position in physical pixels, `None` outside the window.
+    pub fn cursor(&self) -> Option<[f32; 2]> {
+        self.frame.borrow().cursor
+    }
+
+    /// Cursor motion this frame in physical pixels.
+    pub fn cursor_delta(&self) -> [f32; 2] {
+        self.frame.borrow().cursor_delta
+    }
+
+    /// Raw mouse motion this frame while the cursor is grabbed, in device units
+    /// rather than pixels and not limited by the window edges.
+    pub fn mouse_motion(&self) -> [f32; 2] {
+        self.frame.borrow().motion
+    }
+
+    /// Whether the cursor should be hidden and locked to the window.
+    pub fn cursor_grab(&self) -> bool {
+        self.frame.borrow().cursor_grab
+    }
+
+    /// Ask for the cursor to be grabbed, applied to the window after the frame.
+    /// Losing focus releases it.
+    pub fn set_cursor_grab(&self, grab: bool) {
+        self.frame.borrow_mut().cursor_grab = grab;
+    }
+
+    /// Scrolling this frame in lines, positive `y` scrolls up.
+    pub fn scroll(&self) -> [f32; 2] {
+        self.frame.borrow().scroll
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.frame.borrow().modifiers
+    }
+
+    /// Active touches as `(id, position)`.
+    pub fn touches(&self) -> Vec<(u64, [f32; 2])> {
+        let frame = self.frame.borrow();
+        frame.touches.iter().map(|(&id, &pos)| (id, pos)).collect()
+    }
+
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    /// Whether any binding of `action` is held.
+    pub fn action_held(&self, action: &str) -> bool {
+        let bindings = self.actions.bindings(action);
+        bindings.iter().any(|&binding| self.is_held(binding))
+    }
+
+    /// Whether `action` went from idle to active this frame.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        let bindings = self.actions.bindings(action);
+        bindings.iter().any(|&binding| self.was_pressed(binding))
+            && !bindings
+                .iter()
+                .any(|&binding| self.was_held_before(binding))
+    }
+
+    /// Whether `action` went from active to idle this frame.
+    pub fn action_released(&self, action: &str) -> bool {
+        let bindings = self.actions.bindings(action);
+        bindings.iter().any(|&binding| self.was_released(binding)) && !self.action_held(action)
+    }
+
+    /// `1` while only `positive` is held, `-1` while only `negative` is.
+    pub fn axis(&self, negative: &str, positive: &str) -> f32 {
+        self.action_held(positive) as i32 as f32 - self.action_held(negative) as i32 as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::DeviceId;
+
+    fn input() -> InputState {
+        InputState::new(ActionMap::parse("fire = MouseLeft, MouseRight").unwrap())
+    }
+
+    fn button(input: &InputState, button: MouseButton, state: ElementState) {
+        input.handle_event(&WindowEvent::MouseInput {
+            device_id: unsafe { DeviceId::dummy() },
+            state,
+            button,
+        });
+    }
+
+    #[test]
+    fn action_follows_any_of_its_bindings() {
+        let input = input();
+        button(&input, MouseButton::Left, ElementState::Pressed);
+        assert!(input.action_pressed("fire") && input.action_held("fire"));
+        input.end_frame();
+        assert!(!input.action_pressed("fire") && input.action_held("fire"));
+
+        // A second binding neither presses nor releases an active action.
+        button(&input, MouseButton::Right, ElementState::Pressed);
+        button(&input, MouseButton::Left, ElementState::Released);
+        assert!(!input.action_pressed("fire") && !input.action_released("fire"));
+        assert!(input.action_held("fire"));
+        input.end_frame();
+
+        button(&input, MouseButton::Right, ElementState::Released);
+        assert!(input.action_released("fire") && !input.action_held("fire"));
+        input.end_frame();
+        assert!(!input.action_released("fire"));
+    }
+
+    #[test]
+    fn tap_within_a_frame_is_pressed_and_released() {
+        let input = input();
+        button(&input, MouseButton::Left, ElementState::Pressed);
+        button(&input, MouseButton::Left, ElementState::Released);
+
+        assert!(input.action_pressed("fire") && input.action_released("fire"));
+        assert!(!input.action_held("fire"));
+    }
+
+    #[test]
+    fn focus_loss_releases_everything() {
+        let input = input();
+        button(&input, MouseButton::Left, ElementState::Pressed);
+        button(&input, MouseButton::Middle, ElementState::Pressed);
+        input.set_cursor_grab(true);
+        input.end_frame();
+
+        input.handle_event(&WindowEvent::Focused(false));
+        assert!(input.action_released("fire") && !input.action_held("fire"));
+        assert!(input.button_released(MouseButton::Middle));
+        assert!(!input.button_held(MouseButton::Middle));
+        assert!(!input.cursor_grab());
+
+        // The release the window never saw changes nothing.
+        input.end_frame();
+        button(&input, MouseButton::Left, ElementState::Released);
+        assert!(!input.action_released("fire"));
+    }
+}
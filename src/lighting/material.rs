@@ -0,0 +1,172 @@
+use std::ffi::CStr;
+
+use crate::{
+    gl_call,
+    model::{ModelMaterial, TextureRef},
+    renderer::gl,
+    resources::{Handle, ResourceManager},
+    shaders::Shader,
+    texture::Texture,
+};
+
+/// Texture slot of the first map of a [`BlinnPhongMaterial`], past those
+/// [`crate::scene::render_scene`] binds `u_texture` and morph targets to.
+pub const FIRST_MAP_SLOT: i32 = 2;
+
+/// Surface properties for the Blinn-Phong shaders, `lit.vs` and `lit.fs`,
+/// drawn by [`crate::scene::render_scene`] through
+/// [`crate::scene::Material::with_lighting`].
+///
+/// Maps multiply the matching color, the normal map perturbs the surface in
+/// tangent space. They are bound from [`FIRST_MAP_SLOT`] on in the order of
+/// the fields.
+#[derive(Debug, Clone)]
+pub struct BlinnPhongMaterial {
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    /// Exponent of the specular highlight, higher is tighter.
+    pub shininess: f32,
+    pub emissive: [f32; 3],
+    pub diffuse_map: Option<Handle<Texture>>,
+    pub specular_map: Option<Handle<Texture>>,
+    pub normal_map: Option<Handle<Texture>>,
+    pub emissive_map: Option<Handle<Texture>>,
+    /// Light the vertices and blend between them rather than every pixel,
+    /// which leaves out the normal map.
+    pub per_vertex: bool,
+}
+
+impl Default for BlinnPhongMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [0.5, 0.5, 0.5],
+            shininess: 32.,
+            emissive: [0., 0., 0.],
+            diffuse_map: None,
+            specular_map: None,
+            normal_map: None,
+            emissive_map: None,
+            per_vertex: false,
+        }
+    }
+}
+
+impl BlinnPhongMaterial {
+    pub fn new(diffuse: [f32; 3]) -> Self {
+        Self {
+            diffuse,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_specular(mut self, specular: [f32; 3], shininess: f32) -> Self {
+        self.specular = specular;
+        self.shininess = shininess;
+        self
+    }
+
+    pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    pub fn with_diffuse_map(mut self, map: Handle<Texture>) -> Self {
+        self.diffuse_map = Some(map);
+        self
+    }
+
+    pub fn with_specular_map(mut self, map: Handle<Texture>) -> Self {
+        self.specular_map = Some(map);
+        self
+    }
+
+    pub fn with_normal_map(mut self, map: Handle<Texture>) -> Self {
+        self.normal_map = Some(map);
+        self
+    }
+
+    pub fn with_emissive_map(mut self, map: Handle<Texture>) -> Self {
+        self.emissive_map = Some(map);
+        self
+    }
+
+    pub fn with_per_vertex(mut self, per_vertex: bool) -> Self {
+        self.per_vertex = per_vertex;
+        self
+    }
+
+    /// The Phong terms and maps of an imported material. Maps that can not
+    /// be loaded are left out.
+    pub fn from_model(gl: &gl::Gl, resources: &ResourceManager, material: &ModelMaterial) -> Self {
+        let load = |map: &Option<TextureRef>| map.as_ref().and_then(|map| map.load(gl, resources));
+
+        Self {
+            diffuse: material.diffuse,
+            specular: material.specular,
+            shininess: material.shininess.max(1.),
+            emissive: material.emissive,
+            diffuse_map: load(&material.diffuse_map),
+            specular_map: load(&material.specular_map),
+            normal_map: load(&material.normal_map),
+            emissive_map: load(&material.emissive_map),
+            per_vertex: false,
+        }
+    }
+
+    /// Each map with its sampler and the flag telling whether it is there.
+    fn maps(&self) -> [(&'static CStr, &'static CStr, &Option<Handle<Texture>>); 4] {
+        [
+            (c"u_diffuse_map", c"u_use_diffuse_map", &self.diffuse_map),
+            (c"u_specular_map", c"u_use_specular_map", &self.specular_map),
+            (c"u_normal_map", c"u_use_normal_map", &self.normal_map),
+            (c"u_emissive_map", c"u_use_emissive_map", &self.emissive_map),
+        ]
+    }
+
+    /// Set the material uniforms of `shader` and bind the maps, until
+    /// [`BlinnPhongMaterial::unbind`].
+    pub fn apply(&self, gl: &gl::Gl, resources: &ResourceManager, shader: &Shader) {
+        let uniforms = [
+            (c"u_diffuse", self.diffuse),
+            (c"u_specular", self.specular),
+            (c"u_emissive", self.emissive),
+        ];
+        for (name, [r, g, b]) in uniforms {
+            if shader.has_uniform(gl, name) {
+                shader.set_uniform_3f(gl, name, r, g, b);
+            }
+        }
+        if shader.has_uniform(gl, c"u_shininess") {
+            shader.set_uniform_1f(gl, c"u_shininess", self.shininess);
+        }
+        if shader.has_uniform(gl, c"u_per_vertex") {
+            shader.set_uniform_1i(gl, c"u_per_vertex", self.per_vertex as i32);
+        }
+
+        for (slot, (sampler, flag, map)) in (FIRST_MAP_SLOT..).zip(self.maps()) {
+            if let Some(map) = map {
+                resources.get(map).bind(gl, slot, None);
+            }
+
+            if shader.has_uniform(gl, sampler) {
+                shader.set_uniform_1i(gl, sampler, slot);
+            }
+            if shader.has_uniform(gl, flag) {
+                shader.set_uniform_1i(gl, flag, map.is_some() as i32);
+            }
+        }
+    }
+
+    /// Unbind the maps [`BlinnPhongMaterial::apply`] bound, leaving slot 0
+    /// active.
+    pub fn unbind(&self, gl: &gl::Gl, resources: &ResourceManager) {
+        for (slot, (_, _, map)) in (FIRST_MAP_SLOT..).zip(self.maps()) {
+            if let Some(map) = map {
+                gl_call!(gl, ActiveTexture(gl::TEXTURE0 + slot as u32));
+                resources.get(map).unbind(gl);
+            }
+        }
+        gl_call!(gl, ActiveTexture(gl::TEXTURE0));
+    }
+}
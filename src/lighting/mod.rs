@@ -0,0 +1,184 @@
+mod material;
+
+pub use material::{BlinnPhongMaterial, FIRST_MAP_SLOT};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    buffer::{Buffer, BufferTarget, BufferUsage},
+    capabilities::Capabilities,
+    renderer::gl,
+    scene::{LightKind, SceneLight},
+    shaders::{Shader, ShaderPrelude},
+};
+
+/// Uniform buffer binding point of the `Lights` block.
+pub const LIGHT_BLOCK_BINDING: u32 = 0;
+
+/// Most lights a [`LightBuffer`] holds, however large uniform blocks may be.
+pub const MAX_LIGHTS: usize = 64;
+
+/// One light as the `Light` struct of the shaders lays it out in std140.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+struct GpuLight {
+    /// World position, `w` is 0 for directional, 1 for point and 2 for spot
+    /// lights.
+    position: [f32; 4],
+    /// Direction the light shines in, `w` is the range or 0 for none.
+    direction: [f32; 4],
+    /// Color times intensity.
+    color: [f32; 4],
+    /// Constant, linear and quadratic attenuation.
+    attenuation: [f32; 4],
+    /// Cosines of the inner and outer spot angles.
+    cone: [f32; 4],
+}
+
+impl GpuLight {
+    fn new(light: &SceneLight) -> Self {
+        let SceneLight {
+            light,
+            position,
+            direction,
+        } = *light;
+        let (kind, range, cone) = match light.kind {
+            LightKind::Directional => (0., 0., [1., 1.]),
+            LightKind::Point { range } => (1., range, [1., 1.]),
+            LightKind::Spot {
+                range,
+                inner_angle,
+                outer_angle,
+            } => (2., range, [inner_angle.cos(), outer_angle.cos()]),
+        };
+        let range = if range.is_finite() { range } else { 0. };
+        let [r, g, b] = light.color.map(|c| c * light.intensity);
+        let attenuation = light.attenuation;
+
+        Self {
+            position: [position.x, position.y, position.z, kind],
+            direction: [direction.x, direction.y, direction.z, range],
+            color: [r, g, b, 0.],
+            attenuation: [
+                attenuation.constant,
+                attenuation.linear,
+                attenuation.quadratic,
+                0.,
+            ],
+            cone: [cone[0], cone[1], 0., 0.],
+        }
+    }
+}
+
+/// The members of the `Lights` block before the light array.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Pod, Zeroable)]
+struct Header {
+    ambient: [f32; 4],
+    /// Number of lights in `x`.
+    count: [i32; 4],
+}
+
+/// The scene's lights in a uniform buffer shared by every lit program.
+///
+/// Programs get the `Lights` block from `res/shaders/lighting.glsl` through
+/// [`LightBuffer::prelude`], which sizes it to what both [`MAX_LIGHTS`] and
+/// the context's uniform block limit allow.
+pub struct LightBuffer {
+    buffer: Buffer<u8>,
+    capacity: usize,
+}
+
+impl LightBuffer {
+    pub fn new(gl: &gl::Gl, capabilities: &Capabilities) -> Self {
+        let capacity = Self::capacity_for(capabilities);
+        let size = size_of::<Header>() + capacity * size_of::<GpuLight>();
+        let buffer = Buffer::with_len(gl, BufferTarget::Uniform, size, BufferUsage::Dynamic);
+        Self { buffer, capacity }
+    }
+
+    /// How many lights fit in the largest uniform block of the context.
+    pub fn capacity_for(capabilities: &Capabilities) -> usize {
+        let size = (capabilities.max_uniform_block_size().max(0) as usize)
+            .saturating_sub(size_of::<Header>());
+        (size / size_of::<GpuLight>()).clamp(1, MAX_LIGHTS)
+    }
+
+    /// Number of lights uploaded at most, the rest are dropped.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The `Lights` block and `add_light` for loading lit programs with, see
+    /// [`crate::resources::ResourceManager::load_shader_with_prelude`].
+    pub fn prelude(&self) -> ShaderPrelude {
+        ShaderPrelude::default()
+            .with_define("MAX_LIGHTS", self.capacity)
+            .with_include("lighting.glsl")
+    }
+
+    /// Make `shader` read its `Lights` block from this buffer. Returns
+    /// whether it has one.
+    pub fn attach(&self, gl: &gl::Gl, shader: &Shader) -> bool {
+        shader.bind_uniform_block(gl, c"Lights", LIGHT_BLOCK_BINDING)
+    }
+
+    /// Upload `lights`, such as [`crate::scene::Scene::lights`], and the
+    /// `ambient` light that reaches every surface.
+    pub fn update(&self, gl: &gl::Gl, ambient: [f32; 3], lights: &[SceneLight]) {
+        let lights: Vec<_> = lights
+            .iter()
+            .take(self.capacity)
+            .map(GpuLight::new)
+            .collect();
+        let [r, g, b] = ambient;
+        let header = Header {
+            ambient: [r, g, b, 0.],
+            count: [lights.len() as i32, 0, 0, 0],
+        };
+
+        let mut data = bytemuck::bytes_of(&header).to_vec();
+        data.extend_from_slice(bytemuck::cast_slice(&lights));
+        self.buffer.update(gl, 0..data.len(), &data);
+    }
+
+    /// Bind to [`LIGHT_BLOCK_BINDING`].
+    pub fn bind(&self, gl: &gl::Gl) {
+        self.buffer.bind_base(gl, LIGHT_BLOCK_BINDING);
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.buffer.drop(gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Light;
+    use nalgebra::{Point3, Vector3};
+
+    #[test]
+    fn lights_are_packed_for_std140() {
+        assert_eq!(size_of::<Header>(), 32);
+        assert_eq!(size_of::<GpuLight>(), 80);
+
+        let spot = Light::new(LightKind::Spot {
+            range: f32::INFINITY,
+            inner_angle: 0.,
+            outer_angle: std::f32::consts::FRAC_PI_2,
+        })
+        .with_color([1., 0.5, 0.])
+        .with_intensity(2.);
+        let light = GpuLight::new(&SceneLight {
+            light: spot,
+            position: Point3::new(1., 2., 3.),
+            direction: -Vector3::y(),
+        });
+        assert_eq!(light.position, [1., 2., 3., 2.]);
+        assert_eq!(light.direction, [0., -1., 0., 0.]);
+        assert_eq!(light.color, [2., 1., 0., 0.]);
+        assert_eq!(light.attenuation, [1., 0., 1., 0.]);
+        assert!(light.cone[0] == 1. && light.cone[1].abs() < 1e-6);
+    }
+}
@@ -1,13 +1,30 @@
 extern crate nalgebra as na;
 
+pub mod animation;
+pub mod buffer;
+pub mod camera;
+pub mod capabilities;
+pub mod clock;
 pub mod error;
+pub mod framebuffer;
 pub mod gl_bootstrap;
+pub mod input;
+pub mod lighting;
+pub mod mesh;
+pub mod model;
+pub mod render_state;
 pub mod renderer;
+pub mod resources;
 pub mod samples;
+pub mod scene;
 pub mod shaders;
 pub mod texture;
+pub mod transform;
+pub mod vertex_array;
 
 use crate::gl_bootstrap::bootstrap_gl_window;
+use clock::{FrameClock, DEFAULT_FIXED_STEP};
+use input::{ActionMap, InputState};
 use glutin::{
     context::{NotCurrentGlContext, PossiblyCurrentContext},
     display::{GetGlDisplay, GlDisplay},
@@ -15,27 +32,156 @@ use glutin::{
 };
 use glutin_winit::GlWindow;
 use renderer::Renderer;
-use samples::Sample;
-use std::num::NonZeroU32;
+use samples::{SampleEntry, SAMPLES};
+use std::{num::NonZeroU32, process::exit};
 use winit::{
-    dpi::PhysicalSize, event::{Event, WindowEvent}, event_loop::EventLoopBuilder, window::WindowBuilder
+    dpi::PhysicalSize,
+    event::{DeviceEvent, ElementState, Event, KeyEvent, WindowEvent},
+    event_loop::EventLoopBuilder,
+    keyboard::{KeyCode, PhysicalKey},
+    window::{CursorGrabMode, Window, WindowBuilder},
 };
 
-const SAMPLE: Sample = Sample::TexturedSquare;
+const DEFAULT_SAMPLE: &str = "textured_square";
+
+const DEFAULT_INPUT_CONFIG: &str = "res/input.cfg";
+
+const USAGE: &str = "Usage: rust_gl [--sample <name>] [--list] [--fixed-time] [--input <path>]
+
+Options:
+    --sample <name>  Start with the given sample.
+    --list           Print the available samples and exit.
+    --fixed-time     Advance time by 1/60 s per frame instead of following
+                     the wall clock, so every run renders the same frames.
+    --input <path>   Load action bindings from the given file instead of
+                     res/input.cfg.
+
+Left/right or the number keys switch samples while running, space pauses.";
+
+struct Args {
+    sample: &'static SampleEntry,
+    fixed_time: bool,
+    input_config: String,
+}
+
+/// Parse the command line, exiting for `--list`, `--help` and bad input.
+fn parse_args() -> Args {
+    let mut sample = DEFAULT_SAMPLE.to_string();
+    let mut fixed_time = false;
+    let mut input_config = DEFAULT_INPUT_CONFIG.to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--list" => {
+                for (i, entry) in SAMPLES.iter().enumerate() {
+                    println!("{} {}", i + 1, entry.name);
+                }
+                exit(0);
+            }
+            "--sample" => match args.next() {
+                Some(name) => sample = name,
+                None => {
+                    eprintln!("--sample needs a name.\n\n{}", USAGE);
+                    exit(2);
+                }
+            },
+            "--fixed-time" => fixed_time = true,
+            "--input" => match args.next() {
+                Some(path) => input_config = path,
+                None => {
+                    eprintln!("--input needs a path.\n\n{}", USAGE);
+                    exit(2);
+                }
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => match arg.strip_prefix("--sample=") {
+                Some(name) => sample = name.to_string(),
+                None => {
+                    eprintln!("Unknown argument {}.\n\n{}", arg, USAGE);
+                    exit(2);
+                }
+            },
+        }
+    }
+
+    let sample = samples::find(&sample).unwrap_or_else(|| {
+        eprintln!("Unknown sample {}, see --list.", sample);
+        exit(2);
+    });
+
+    Args {
+        sample,
+        fixed_time,
+        input_config,
+    }
+}
+
+/// The sample a key switches to from the one at `current`, if any.
+fn sample_shortcut(key: KeyCode, current: usize) -> Option<usize> {
+    let digit = match key {
+        KeyCode::ArrowRight => return Some((current + 1) % SAMPLES.len()),
+        KeyCode::ArrowLeft => return Some((current + SAMPLES.len() - 1) % SAMPLES.len()),
+        KeyCode::Digit1 | KeyCode::Numpad1 => 1,
+        KeyCode::Digit2 | KeyCode::Numpad2 => 2,
+        KeyCode::Digit3 | KeyCode::Numpad3 => 3,
+        KeyCode::Digit4 | KeyCode::Numpad4 => 4,
+        KeyCode::Digit5 | KeyCode::Numpad5 => 5,
+        KeyCode::Digit6 | KeyCode::Numpad6 => 6,
+        KeyCode::Digit7 | KeyCode::Numpad7 => 7,
+        KeyCode::Digit8 | KeyCode::Numpad8 => 8,
+        KeyCode::Digit9 | KeyCode::Numpad9 => 9,
+        _ => return None,
+    };
+
+    (digit <= SAMPLES.len()).then(|| digit - 1)
+}
+
+fn window_title(entry: &SampleEntry) -> String {
+    format!("Gl Window - {}", entry.name)
+}
+
+/// Hide the cursor and keep it in the window, or give it back.
+fn set_cursor_grab(window: &Window, grab: bool) {
+    let result = if grab {
+        // Not every platform can lock the cursor in place.
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+
+    if let Err(err) = result {
+        eprintln!("Cursor grab failed: {err}");
+    }
+    window.set_cursor_visible(!grab);
+}
 
 fn main() {
+    let args = parse_args();
+
+    let actions = ActionMap::load(&args.input_config).unwrap_or_else(|err| {
+        eprintln!("{}, no actions are bound.", err);
+        ActionMap::new()
+    });
+
     let event_loop = EventLoopBuilder::<()>::default().build().unwrap();
 
     let win_bld = WindowBuilder::new()
         .with_transparent(true)
         .with_visible(false)
         .with_inner_size(PhysicalSize::new(800, 800))
-        .with_title("Gl Window");
+        .with_title(window_title(args.sample));
 
     let (window, gl_config, mut not_current_context) =
         bootstrap_gl_window(&event_loop, win_bld).expect("Bootstrap gl window failed.");
 
     let mut state = None;
+    let mut cursor_grabbed = false;
 
     event_loop
         .run(|event, target| match event {
@@ -63,7 +209,16 @@ fn main() {
                 state = Some(GlState {
                     context: gl_context,
                     surface: gl_surface,
-                    renderer: Renderer::new(&gl_display, SAMPLE),
+                    renderer: Renderer::new(
+                        &gl_display,
+                        args.sample,
+                        if args.fixed_time {
+                            FrameClock::fixed(DEFAULT_FIXED_STEP)
+                        } else {
+                            FrameClock::new()
+                        },
+                        InputState::new(actions.clone()),
+                    ),
                 })
             }
 
@@ -79,28 +234,82 @@ fn main() {
                 }
             }
 
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => target.exit(),
+            Event::WindowEvent { event, .. } => {
+                if let Some(GlState { renderer, .. }) = state.as_ref() {
+                    renderer.event(&event);
+                }
+
+                match event {
+                    WindowEvent::CloseRequested => target.exit(),
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                physical_key: PhysicalKey::Code(key),
+                                state: ElementState::Pressed,
+                                repeat: false,
+                                ..
+                            },
+                        ..
+                    } => {
+                        if let Some(GlState { renderer, .. }) = state.as_ref() {
+                            if key == KeyCode::Space {
+                                let mut clock = renderer.clock().borrow_mut();
+                                let paused = clock.is_paused();
+                                clock.set_paused(!paused);
+                                window.request_redraw();
+                            }
 
-                WindowEvent::Resized(size) => {
-                    if let Some(GlState { renderer, .. }) = state.as_ref() {
-                        renderer.resize(size.width as i32, size.height as i32)
+                            let current = SAMPLES
+                                .iter()
+                                .position(|entry| entry.name == renderer.sample_entry().name)
+                                .unwrap_or(0);
+
+                            if let Some(next) = sample_shortcut(key, current) {
+                                if next != current {
+                                    renderer.switch_sample(&SAMPLES[next]);
+                                    window.set_title(&window_title(&SAMPLES[next]));
+                                    window.request_redraw();
+                                }
+                            }
+                        }
                     }
-                }
 
-                WindowEvent::RedrawRequested => {
-                    if let Some(GlState {
-                        context,
-                        surface,
-                        renderer,
-                    }) = state.as_ref()
-                    {
-                        renderer.draw();
-                        surface.swap_buffers(context).unwrap();
+                    WindowEvent::Resized(size) => {
+                        if let Some(GlState { renderer, .. }) = state.as_ref() {
+                            renderer.resize(size.width as i32, size.height as i32)
+                        }
+                    }
+
+                    WindowEvent::RedrawRequested => {
+                        if let Some(GlState {
+                            context,
+                            surface,
+                            renderer,
+                        }) = state.as_ref()
+                        {
+                            renderer.draw();
+                            surface.swap_buffers(context).unwrap();
+
+                            let grab = renderer.input().cursor_grab();
+                            if grab != cursor_grabbed {
+                                set_cursor_grab(&window, grab);
+                                cursor_grabbed = grab;
+                            }
+                        }
                     }
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
+
+            Event::DeviceEvent {
+                event: event @ DeviceEvent::MouseMotion { .. },
+                ..
+            } => {
+                if let Some(GlState { renderer, .. }) = state.as_ref() {
+                    renderer.device_event(&event);
+                }
+            }
             _ => {}
         })
         .unwrap();
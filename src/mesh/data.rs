@@ -0,0 +1,236 @@
+use bytemuck::{Pod, Zeroable};
+use nalgebra::{Point3, Vector2, Vector3};
+
+use super::{Aabb, Mesh, MorphTarget, Topology};
+use crate::{
+    capabilities::Capabilities,
+    renderer::gl,
+    vertex_array::{Vertex, VertexBufferLayout},
+};
+
+/// Shader locations of the optional [`MeshData`] attributes, which follow
+/// the four of [`MeshVertex`].
+pub const COLOR_LOCATION: u32 = 4;
+pub const UV1_LOCATION: u32 = 5;
+pub const JOINTS_LOCATION: u32 = 6;
+pub const WEIGHTS_LOCATION: u32 = 7;
+
+/// The vertex format of generated and imported meshes.
+///
+/// Positions and UVs come first, so shaders written for
+/// `basic_texture.vs` can draw these meshes as they are.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable, Vertex)]
+pub struct MeshVertex {
+    #[location(0)]
+    pub position: [f32; 3],
+    #[location(1)]
+    pub uv: [f32; 2],
+    #[location(2)]
+    pub normal: [f32; 3],
+    /// `xyz` along increasing `u`, `w` is the handedness of the bitangent.
+    #[location(3)]
+    pub tangent: [f32; 4],
+}
+
+/// Indexed geometry on the CPU, ready to upload as a [`Mesh`].
+///
+/// The optional attributes are either empty or hold one entry per vertex,
+/// each is uploaded to its own buffer.
+#[derive(Debug, Clone, Default)]
+pub struct MeshData {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    pub topology: Topology,
+    /// Linear RGBA at [`COLOR_LOCATION`].
+    pub colors: Vec<[f32; 4]>,
+    /// A second UV set at [`UV1_LOCATION`].
+    pub uvs1: Vec<[f32; 2]>,
+    /// Skin joint indices, integers at [`JOINTS_LOCATION`].
+    pub joints: Vec<[u16; 4]>,
+    /// Skin weights at [`WEIGHTS_LOCATION`].
+    pub weights: Vec<[f32; 4]>,
+    /// Shapes to blend towards, uploaded as a [`super::MorphTexture`].
+    pub morph_targets: Vec<MorphTarget>,
+}
+
+impl MeshData {
+    /// Triangles without optional attributes.
+    pub fn new(vertices: Vec<MeshVertex>, indices: Vec<u32>) -> Self {
+        Self {
+            vertices,
+            indices,
+            ..Self::default()
+        }
+    }
+
+    /// Bounds of all vertices, `None` when there are none.
+    pub fn aabb(&self) -> Option<Aabb> {
+        let points: Vec<_> = self
+            .vertices
+            .iter()
+            .map(|v| Point3::from(v.position))
+            .collect();
+        Aabb::from_points(points.iter())
+    }
+
+    /// Append `other`, which has to have the same topology. Optional
+    /// attributes only one side has are filled with defaults on the other,
+    /// and morph targets, matched by index, with zero deltas.
+    ///
+    /// Panics for strips, fans and loops, which can not be joined.
+    pub fn append(&mut self, other: MeshData) {
+        assert_eq!(self.topology, other.topology, "Topologies differ.");
+        assert!(
+            matches!(
+                self.topology,
+                Topology::Points | Topology::Lines | Topology::Triangles
+            ),
+            "Only lists can be appended."
+        );
+
+        let (len, other_len) = (self.vertices.len(), other.vertices.len());
+        append_attribute(&mut self.colors, other.colors, len, other_len, [1.; 4]);
+        append_attribute(&mut self.uvs1, other.uvs1, len, other_len, [0.; 2]);
+        append_attribute(&mut self.joints, other.joints, len, other_len, [0; 4]);
+        append_attribute(&mut self.weights, other.weights, len, other_len, [0.; 4]);
+
+        let mut other_targets = other.morph_targets.into_iter();
+        for index in 0..self.morph_targets.len().max(other_targets.len()) {
+            if index == self.morph_targets.len() {
+                let name = other_targets.as_slice()[0].name.clone();
+                self.morph_targets.push(MorphTarget::zeros(&name, len));
+            }
+            let target = &mut self.morph_targets[index];
+            let other = other_targets
+                .next()
+                .unwrap_or_else(|| MorphTarget::zeros(&target.name, other_len));
+            append_attribute(
+                &mut target.positions,
+                other.positions,
+                len,
+                other_len,
+                [0.; 3],
+            );
+            append_attribute(&mut target.normals, other.normals, len, other_len, [0.; 3]);
+        }
+
+        let offset = len as u32;
+        self.vertices.extend(other.vertices);
+        self.indices
+            .extend(other.indices.into_iter().map(|i| i + offset));
+    }
+
+    /// Fill in tangents from the UV layout, normals have to be set already.
+    /// Only triangle lists have tangents.
+    pub fn compute_tangents(&mut self) {
+        if self.topology != Topology::Triangles {
+            return;
+        }
+
+        let mut tangents = vec![Vector3::zeros(); self.vertices.len()];
+        let mut bitangents = vec![Vector3::zeros(); self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &self.vertices[triangle[i] as usize]);
+            let e1 = Vector3::from(b.position) - Vector3::from(a.position);
+            let e2 = Vector3::from(c.position) - Vector3::from(a.position);
+            let d1 = Vector2::from(b.uv) - Vector2::from(a.uv);
+            let d2 = Vector2::from(c.uv) - Vector2::from(a.uv);
+
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < f32::EPSILON {
+                continue;
+            }
+            let tangent = (e1 * d2.y - e2 * d1.y) / det;
+            let bitangent = (e2 * d1.x - e1 * d2.x) / det;
+
+            for &i in triangle {
+                tangents[i as usize] += tangent;
+                bitangents[i as usize] += bitangent;
+            }
+        }
+
+        for (i, vertex) in self.vertices.iter_mut().enumerate() {
+            let normal = Vector3::from(vertex.normal);
+            // Gram-Schmidt, falling back to any direction along the surface
+            // where the UVs collapse, such as the poles of a sphere.
+            let mut tangent = tangents[i] - normal * normal.dot(&tangents[i]);
+            if tangent.norm_squared() < 1e-12 {
+                let axis = if normal.x.abs() < 0.9 {
+                    Vector3::x()
+                } else {
+                    Vector3::y()
+                };
+                tangent = axis - normal * normal.dot(&axis);
+            }
+            let tangent = tangent.normalize();
+
+            let handedness = if normal.cross(&tangent).dot(&bitangents[i]) < 0. {
+                -1.
+            } else {
+                1.
+            };
+            vertex.tangent = [tangent.x, tangent.y, tangent.z, handedness];
+        }
+    }
+
+    /// Upload as an indexed mesh, with 16 bit indices when they fit.
+    pub fn upload(&self, gl: &gl::Gl, caps: &Capabilities) -> Mesh {
+        let mut mesh = if self.vertices.len() <= u16::MAX as usize {
+            let indices: Vec<u16> = self.indices.iter().map(|&i| i as u16).collect();
+            Mesh::indexed(gl, self.topology, &self.vertices, &indices)
+        } else {
+            Mesh::indexed(gl, self.topology, &self.vertices, &self.indices)
+        };
+
+        if !self.colors.is_empty() {
+            let mut layout = VertexBufferLayout::new();
+            layout.push::<f32>(4).location(COLOR_LOCATION);
+            mesh = mesh.with_attributes(gl, &self.colors, &layout);
+        }
+        if !self.uvs1.is_empty() {
+            let mut layout = VertexBufferLayout::new();
+            layout.push::<f32>(2).location(UV1_LOCATION);
+            mesh = mesh.with_attributes(gl, &self.uvs1, &layout);
+        }
+        if !self.joints.is_empty() {
+            let mut layout = VertexBufferLayout::new();
+            layout.push_integer::<u16>(4).location(JOINTS_LOCATION);
+            mesh = mesh.with_attributes(gl, &self.joints, &layout);
+        }
+        if !self.weights.is_empty() {
+            let mut layout = VertexBufferLayout::new();
+            layout.push::<f32>(4).location(WEIGHTS_LOCATION);
+            mesh = mesh.with_attributes(gl, &self.weights, &layout);
+        }
+        if !self.morph_targets.is_empty() {
+            mesh = mesh.with_morph_targets(gl, caps, &self.morph_targets);
+        }
+
+        match self.aabb() {
+            Some(aabb) => mesh.with_bounds(aabb),
+            None => mesh,
+        }
+    }
+}
+
+/// Append `other` to `values`, padding whichever side is missing the
+/// attribute with `default`.
+fn append_attribute<T: Copy>(
+    values: &mut Vec<T>,
+    other: Vec<T>,
+    len: usize,
+    other_len: usize,
+    default: T,
+) {
+    if values.is_empty() && other.is_empty() {
+        return;
+    }
+    values.resize(len, default);
+    if other.is_empty() {
+        values.resize(len + other_len, default);
+    } else {
+        values.extend(other);
+    }
+}
@@ -0,0 +1,411 @@
+mod aabb;
+mod data;
+mod morph;
+pub mod shapes;
+
+pub use aabb::Aabb;
+pub use data::{
+    MeshData, MeshVertex, COLOR_LOCATION, JOINTS_LOCATION, UV1_LOCATION, WEIGHTS_LOCATION,
+};
+pub use morph::{MorphTarget, MorphTexture, MORPH_TEXTURE_WIDTH};
+
+use crate::{
+    buffer::{Buffer, BufferTarget, BufferUsage},
+    capabilities::Capabilities,
+    gl_call,
+    renderer::gl,
+    vertex_array::{Vertex, VertexArray, VertexBufferLayout},
+};
+use bytemuck::Pod;
+use gl::types::{GLenum, GLsizei};
+use std::ops::Range;
+
+/// How vertices are assembled into primitives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Topology {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    #[default]
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl Topology {
+    pub fn gl_mode(self) -> GLenum {
+        match self {
+            Topology::Points => gl::POINTS,
+            Topology::Lines => gl::LINES,
+            Topology::LineStrip => gl::LINE_STRIP,
+            Topology::LineLoop => gl::LINE_LOOP,
+            Topology::Triangles => gl::TRIANGLES,
+            Topology::TriangleStrip => gl::TRIANGLE_STRIP,
+            Topology::TriangleFan => gl::TRIANGLE_FAN,
+        }
+    }
+
+    /// Number of primitives `count` vertices make, ignoring restarts.
+    pub fn primitive_count(self, count: u32) -> u32 {
+        match self {
+            Topology::Points => count,
+            Topology::Lines => count / 2,
+            Topology::LineStrip => count.saturating_sub(1),
+            Topology::LineLoop if count > 1 => count,
+            Topology::LineLoop => 0,
+            Topology::Triangles => count / 3,
+            Topology::TriangleStrip | Topology::TriangleFan => count.saturating_sub(2),
+        }
+    }
+}
+
+/// Width of the indices in an index buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexType {
+    U8,
+    U16,
+    U32,
+}
+
+impl IndexType {
+    pub fn gl_type(self) -> GLenum {
+        match self {
+            IndexType::U8 => gl::UNSIGNED_BYTE,
+            IndexType::U16 => gl::UNSIGNED_SHORT,
+            IndexType::U32 => gl::UNSIGNED_INT,
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            IndexType::U8 => 1,
+            IndexType::U16 => 2,
+            IndexType::U32 => 4,
+        }
+    }
+
+    /// The index that starts a new primitive when restart is enabled, the
+    /// largest one the type can hold.
+    pub fn restart_index(self) -> u32 {
+        match self {
+            IndexType::U8 => u8::MAX as u32,
+            IndexType::U16 => u16::MAX as u32,
+            IndexType::U32 => u32::MAX,
+        }
+    }
+}
+
+/// A type usable as an index.
+pub trait Index: Pod {
+    const TYPE: IndexType;
+}
+
+impl Index for u8 {
+    const TYPE: IndexType = IndexType::U8;
+}
+
+impl Index for u16 {
+    const TYPE: IndexType = IndexType::U16;
+}
+
+impl Index for u32 {
+    const TYPE: IndexType = IndexType::U32;
+}
+
+/// A part of a mesh drawn with its own material.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submesh {
+    /// Elements to draw, indices for indexed meshes and vertices otherwise.
+    pub range: Range<u32>,
+    /// Which of the materials the mesh is drawn with applies to this part.
+    pub material: usize,
+}
+
+struct IndexBuffer {
+    buffer: Buffer,
+    index_type: IndexType,
+    count: u32,
+}
+
+/// Vertices, optional indices and the vertex array reading them, split into
+/// submeshes.
+///
+/// The index buffer is recorded in the vertex array, so drawing only needs
+/// the array bound.
+pub struct Mesh {
+    vao: VertexArray,
+    vertices: Buffer,
+    /// Buffers added by [`Mesh::with_attributes`].
+    attributes: Vec<Buffer>,
+    vertex_count: u32,
+    indices: Option<IndexBuffer>,
+    topology: Topology,
+    primitive_restart: bool,
+    submeshes: Vec<Submesh>,
+    bounds: Option<Aabb>,
+    morph_targets: Option<MorphTexture>,
+}
+
+impl Mesh {
+    /// Upload `vertices`, drawn in order.
+    pub fn new<V: Vertex>(gl: &gl::Gl, topology: Topology, vertices: &[V]) -> Self {
+        let vao = VertexArray::new(gl);
+        let vbo = vao.add_vertices(gl, vertices, BufferUsage::Static);
+        vao.unbind(gl);
+
+        Self::build(vao, vbo.into_untyped(), vertices.len(), None, topology)
+    }
+
+    /// Upload `vertices`, drawn through `indices`.
+    pub fn indexed<V: Vertex, I: Index>(
+        gl: &gl::Gl,
+        topology: Topology,
+        vertices: &[V],
+        indices: &[I],
+    ) -> Self {
+        let vao = VertexArray::new(gl);
+        let vbo = vao.add_vertices(gl, vertices, BufferUsage::Static);
+        let ibo = Buffer::new(gl, BufferTarget::Element, indices, BufferUsage::Static);
+        vao.unbind(gl);
+
+        let indices = IndexBuffer {
+            buffer: ibo.into_untyped(),
+            index_type: I::TYPE,
+            count: indices.len() as u32,
+        };
+        Self::build(
+            vao,
+            vbo.into_untyped(),
+            vertices.len(),
+            Some(indices),
+            topology,
+        )
+    }
+
+    fn build(
+        vao: VertexArray,
+        vertices: Buffer,
+        vertex_count: usize,
+        indices: Option<IndexBuffer>,
+        topology: Topology,
+    ) -> Self {
+        let mut mesh = Self {
+            vao,
+            vertices,
+            attributes: vec![],
+            vertex_count: vertex_count as u32,
+            indices,
+            topology,
+            primitive_restart: false,
+            submeshes: vec![],
+            bounds: None,
+            morph_targets: None,
+        };
+        mesh.submeshes = vec![Submesh {
+            range: 0..mesh.element_count(),
+            material: 0,
+        }];
+        mesh
+    }
+
+    /// Split the mesh, by default it is one submesh with material 0.
+    ///
+    /// Panics if a range goes past the elements of the mesh.
+    pub fn with_submeshes(mut self, submeshes: Vec<Submesh>) -> Self {
+        let count = self.element_count();
+        for submesh in submeshes.iter() {
+            assert!(
+                submesh.range.start <= submesh.range.end && submesh.range.end <= count,
+                "Submesh {:?} is out of the {} elements of the mesh.",
+                submesh.range,
+                count
+            );
+        }
+
+        self.submeshes = submeshes;
+        self
+    }
+
+    /// Treat [`IndexType::restart_index`] as the end of a strip, fan or loop.
+    ///
+    /// Panics for meshes without indices.
+    pub fn with_primitive_restart(mut self, restart: bool) -> Self {
+        assert!(
+            self.indices.is_some(),
+            "Primitive restart needs an indexed mesh."
+        );
+
+        self.primitive_restart = restart;
+        self
+    }
+
+    /// Add attributes from a separate buffer laid out by `layout`, one entry
+    /// per vertex.
+    ///
+    /// Panics if `data` does not match the vertex count.
+    pub fn with_attributes<T: Pod>(
+        mut self,
+        gl: &gl::Gl,
+        data: &[T],
+        layout: &VertexBufferLayout,
+    ) -> Self {
+        assert_eq!(
+            data.len() as u32,
+            self.vertex_count,
+            "Attributes need one entry per vertex."
+        );
+
+        self.vao.bind(gl);
+        let buffer = Buffer::new(gl, BufferTarget::Array, data, BufferUsage::Static);
+        self.vao.add_buffer(gl, &buffer, layout);
+        self.vao.unbind(gl);
+
+        self.attributes.push(buffer.into_untyped());
+        self
+    }
+
+    /// Upload the deltas of `targets` for shaders to blend by weight.
+    ///
+    /// Targets past [`MorphTexture::max_targets`] are dropped with an error
+    /// message. Panics unless every target has a position delta per vertex.
+    pub fn with_morph_targets(
+        mut self,
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        targets: &[MorphTarget],
+    ) -> Self {
+        if let Some(old) = self.morph_targets.take() {
+            old.drop(gl);
+        }
+        let max = MorphTexture::max_targets(caps, self.vertex_count);
+        if targets.len() > max {
+            eprintln!(
+                "Only {} of {} morph targets of {} vertices fit in a texture, dropping {}.",
+                max,
+                targets.len(),
+                self.vertex_count,
+                targets[max..]
+                    .iter()
+                    .map(|target| target.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        let targets = &targets[..targets.len().min(max)];
+        if !targets.is_empty() {
+            self.morph_targets = Some(MorphTexture::new(gl, caps, self.vertex_count, targets));
+        }
+        self
+    }
+
+    /// Record the bounds of the vertices in model space.
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Draw every submesh with whatever program is bound.
+    pub fn draw(&self, gl: &gl::Gl) {
+        self.vao.bind(gl);
+        for submesh in self.submeshes.iter() {
+            self.draw_range(gl, submesh.range.clone());
+        }
+        self.vao.unbind(gl);
+    }
+
+    /// Draw one submesh with whatever program is bound.
+    pub fn draw_submesh(&self, gl: &gl::Gl, index: usize) {
+        self.vao.bind(gl);
+        self.draw_range(gl, self.submeshes[index].range.clone());
+        self.vao.unbind(gl);
+    }
+
+    fn draw_range(&self, gl: &gl::Gl, range: Range<u32>) {
+        let mode = self.topology.gl_mode();
+        let count = (range.end - range.start) as GLsizei;
+        if count == 0 {
+            return;
+        }
+
+        match &self.indices {
+            Some(indices) => {
+                if self.primitive_restart {
+                    gl_call!(gl, Enable(gl::PRIMITIVE_RESTART_FIXED_INDEX));
+                }
+
+                let offset = range.start as usize * indices.index_type.size();
+                gl_call!(
+                    gl,
+                    DrawElements(
+                        mode,
+                        count,
+                        indices.index_type.gl_type(),
+                        offset as *const _
+                    )
+                );
+
+                if self.primitive_restart {
+                    gl_call!(gl, Disable(gl::PRIMITIVE_RESTART_FIXED_INDEX));
+                }
+            }
+            None => gl_call!(gl, DrawArrays(mode, range.start as i32, count)),
+        }
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Width of the indices, `None` for meshes drawn in vertex order.
+    pub fn index_type(&self) -> Option<IndexType> {
+        self.indices.as_ref().map(|indices| indices.index_type)
+    }
+
+    pub fn primitive_restart(&self) -> bool {
+        self.primitive_restart
+    }
+
+    pub fn submeshes(&self) -> &[Submesh] {
+        &self.submeshes
+    }
+
+    /// Model space bounds, if they were given.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    pub fn vertex_count(&self) -> u32 {
+        self.vertex_count
+    }
+
+    /// Number of indices, or vertices for meshes without indices.
+    pub fn element_count(&self) -> u32 {
+        self.indices
+            .as_ref()
+            .map_or(self.vertex_count, |indices| indices.count)
+    }
+
+    pub fn vertex_array(&self) -> &VertexArray {
+        &self.vao
+    }
+
+    /// The deltas given to [`Mesh::with_morph_targets`].
+    pub fn morph_targets(&self) -> Option<&MorphTexture> {
+        self.morph_targets.as_ref()
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.vao.drop(gl);
+        self.vertices.drop(gl);
+        for buffer in self.attributes.iter() {
+            buffer.drop(gl);
+        }
+        if let Some(indices) = &self.indices {
+            indices.buffer.drop(gl);
+        }
+        if let Some(morph_targets) = &self.morph_targets {
+            morph_targets.drop(gl);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    capabilities::Capabilities,
+    renderer::gl,
+    texture::{Texture, TextureFormat},
+};
+
+/// Texels per row of a [`MorphTexture`].
+pub const MORPH_TEXTURE_WIDTH: u32 = 1024;
+
+/// Offsets from a mesh's vertices that are blended in by weight, such as a
+/// facial expression or a shape key.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MorphTarget {
+    pub name: String,
+    /// One per vertex.
+    pub positions: Vec<[f32; 3]>,
+    /// One per vertex, or empty when the normals stay as they are.
+    pub normals: Vec<[f32; 3]>,
+}
+
+impl MorphTarget {
+    /// A target that leaves `vertex_count` vertices where they are.
+    pub fn zeros(name: &str, vertex_count: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            positions: vec![[0.; 3]; vertex_count],
+            normals: vec![],
+        }
+    }
+}
+
+/// The deltas of every morph target of a mesh in a float texture, fetched
+/// by vertex shaders with `gl_VertexID`.
+///
+/// Texel `2 * (target * vertex_count + vertex)` holds the position delta and
+/// the one after it the normal delta, in rows of [`MORPH_TEXTURE_WIDTH`].
+pub struct MorphTexture {
+    texture: Texture,
+    targets: u32,
+}
+
+impl MorphTexture {
+    /// How many targets of `vertex_count` vertices fit in the tallest texture
+    /// `caps` allows.
+    pub fn max_targets(caps: &Capabilities, vertex_count: u32) -> usize {
+        targets_that_fit(
+            caps.max_texture_size().max(0) as usize,
+            vertex_count as usize,
+        )
+    }
+
+    /// Panics unless every target has a position delta per vertex and
+    /// there are at most [`MorphTexture::max_targets`] of them.
+    pub fn new(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        vertex_count: u32,
+        targets: &[MorphTarget],
+    ) -> Self {
+        assert!(
+            targets.len() <= Self::max_targets(caps, vertex_count),
+            "{} morph targets of {} vertices do not fit in a texture.",
+            targets.len(),
+            vertex_count
+        );
+        let vertex_count = vertex_count as usize;
+        let texels = 2 * vertex_count * targets.len();
+        let rows = texels.div_ceil(MORPH_TEXTURE_WIDTH as usize).max(1);
+
+        let mut data = vec![[0f32; 4]; rows * MORPH_TEXTURE_WIDTH as usize];
+        for (index, target) in targets.iter().enumerate() {
+            assert_eq!(
+                target.positions.len(),
+                vertex_count,
+                "Morph target {} needs a position per vertex.",
+                target.name
+            );
+            assert!(
+                target.normals.is_empty() || target.normals.len() == vertex_count,
+                "Morph target {} needs a normal per vertex or none.",
+                target.name
+            );
+
+            let first = 2 * index * vertex_count;
+            for (vertex, [x, y, z]) in target.positions.iter().enumerate() {
+                data[first + 2 * vertex] = [*x, *y, *z, 0.];
+            }
+            for (vertex, [x, y, z]) in target.normals.iter().enumerate() {
+                data[first + 2 * vertex + 1] = [*x, *y, *z, 0.];
+            }
+        }
+
+        let texture = Texture::new(
+            gl,
+            caps,
+            MORPH_TEXTURE_WIDTH,
+            rows as u32,
+            TextureFormat::Rgba32f,
+        );
+        texture.update(
+            gl,
+            0,
+            0,
+            MORPH_TEXTURE_WIDTH,
+            rows as u32,
+            bytemuck::cast_slice(&data),
+        );
+
+        Self {
+            texture,
+            targets: targets.len() as u32,
+        }
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Number of targets.
+    pub fn targets(&self) -> u32 {
+        self.targets
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.texture.drop(gl);
+    }
+}
+
+/// Targets of `vertex_count` vertices that fit in `max_rows` rows of
+/// [`MORPH_TEXTURE_WIDTH`] texels, none if a row is already too wide.
+fn targets_that_fit(max_rows: usize, vertex_count: usize) -> usize {
+    let width = MORPH_TEXTURE_WIDTH as usize;
+    if max_rows < width {
+        0
+    } else if vertex_count == 0 {
+        usize::MAX
+    } else {
+        max_rows * width / (2 * vertex_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_fit_in_the_rows_allowed() {
+        // Two texels per vertex, 1024 per row.
+        assert_eq!(targets_that_fit(2048, 1024), 1024);
+        assert_eq!(targets_that_fit(2048, 3000), 349);
+        assert_eq!(targets_that_fit(2048, 1024 * 1024), 1);
+        assert_eq!(targets_that_fit(2048, 1024 * 1024 + 1), 0);
+        assert_eq!(targets_that_fit(2048, 0), usize::MAX);
+        assert_eq!(targets_that_fit(512, 10), 0);
+    }
+}
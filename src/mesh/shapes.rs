@@ -0,0 +1,453 @@
+//! Procedural meshes, centered on the origin with +Y up.
+//!
+//! Every shape has normals, tangents and UVs, and counter-clockwise front
+//! faces pointing outwards.
+
+use std::{
+    collections::HashMap,
+    f32::consts::{FRAC_PI_2, PI, TAU},
+};
+
+use nalgebra::{Point3, Vector2, Vector3};
+
+use super::{MeshData, MeshVertex};
+
+fn vertex(position: Point3<f32>, normal: Vector3<f32>, uv: [f32; 2]) -> MeshVertex {
+    MeshVertex {
+        position: position.into(),
+        uv,
+        normal: normal.into(),
+        tangent: [0.; 4],
+    }
+}
+
+fn finish(mut data: MeshData) -> MeshData {
+    data.compute_tangents();
+    data
+}
+
+/// A flat grid spanning `origin + u * [0, 1] + v * [0, 1]`, facing along
+/// `u × v`.
+fn grid(
+    origin: Point3<f32>,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+    u_segments: u32,
+    v_segments: u32,
+) -> MeshData {
+    let (u_segments, v_segments) = (u_segments.max(1), v_segments.max(1));
+    let normal = u.cross(&v).normalize();
+    let mut data = MeshData::default();
+
+    for j in 0..=v_segments {
+        let t = j as f32 / v_segments as f32;
+        for i in 0..=u_segments {
+            let s = i as f32 / u_segments as f32;
+            data.vertices
+                .push(vertex(origin + u * s + v * t, normal, [s, t]));
+        }
+    }
+
+    let row = u_segments + 1;
+    for j in 0..v_segments {
+        for i in 0..u_segments {
+            let a = j * row + i;
+            let (b, c, d) = (a + 1, a + row + 1, a + row);
+            data.indices.extend([a, b, c, a, c, d]);
+        }
+    }
+
+    data
+}
+
+/// A point of a profile revolved by [`lathe`].
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    /// The normal in the profile plane, outwards and up.
+    normal: Vector2<f32>,
+    v: f32,
+}
+
+/// Revolve `profile`, given from bottom to top, around the Y axis.
+fn lathe(profile: &[ProfilePoint], segments: u32) -> MeshData {
+    let segments = segments.max(3);
+    let mut data = MeshData::default();
+
+    for point in profile {
+        for i in 0..=segments {
+            let u = i as f32 / segments as f32;
+            let (sin, cos) = (u * TAU).sin_cos();
+            let position = Point3::new(point.radius * cos, point.y, -point.radius * sin);
+            let normal = Vector3::new(point.normal.x * cos, point.normal.y, -point.normal.x * sin);
+            data.vertices
+                .push(vertex(position, normal.normalize(), [u, point.v]));
+        }
+    }
+
+    let row = segments + 1;
+    for j in 0..profile.len() as u32 - 1 {
+        for i in 0..segments {
+            let a = j * row + i;
+            let (b, c, d) = (a + 1, a + row + 1, a + row);
+            for triangle in [[a, b, c], [a, c, d]] {
+                // Rows on the axis, like the poles of a sphere, collapse half
+                // of their triangles.
+                let [p, q, r] = triangle.map(|i| Point3::from(data.vertices[i as usize].position));
+                let collapsed = [(p, q), (q, r), (r, p)]
+                    .iter()
+                    .any(|(a, b)| nalgebra::distance_squared(a, b) < 1e-12);
+                if !collapsed {
+                    data.indices.extend(triangle);
+                }
+            }
+        }
+    }
+
+    data
+}
+
+/// A flat disc at height `y`, facing up or down.
+fn cap(radius: f32, y: f32, segments: u32, up: bool) -> MeshData {
+    let segments = segments.max(3);
+    let normal = if up { Vector3::y() } else { -Vector3::y() };
+    let mut data = MeshData::default();
+
+    data.vertices
+        .push(vertex(Point3::new(0., y, 0.), normal, [0.5, 0.5]));
+    for i in 0..=segments {
+        let (sin, cos) = (i as f32 / segments as f32 * TAU).sin_cos();
+        let position = Point3::new(radius * cos, y, -radius * sin);
+        let uv = [0.5 + cos / 2., 0.5 + if up { sin } else { -sin } / 2.];
+        data.vertices.push(vertex(position, normal, uv));
+    }
+
+    for i in 1..=segments {
+        if up {
+            data.indices.extend([0, i, i + 1]);
+        } else {
+            data.indices.extend([0, i + 1, i]);
+        }
+    }
+
+    data
+}
+
+/// A `width` by `depth` grid on the XZ plane facing +Y.
+pub fn plane(width: f32, depth: f32, x_segments: u32, z_segments: u32) -> MeshData {
+    let origin = Point3::new(-width / 2., 0., depth / 2.);
+    finish(grid(
+        origin,
+        Vector3::x() * width,
+        -Vector3::z() * depth,
+        x_segments,
+        z_segments,
+    ))
+}
+
+/// A cube with edges of `size`, each face split into `segments` squared.
+pub fn cube(size: f32, segments: u32) -> MeshData {
+    let h = size / 2.;
+    // Origin, u and v of each face, seen from the outside with v up.
+    let faces = [
+        (Point3::new(-h, -h, h), Vector3::x(), Vector3::y()),
+        (Point3::new(h, -h, -h), -Vector3::x(), Vector3::y()),
+        (Point3::new(h, -h, h), -Vector3::z(), Vector3::y()),
+        (Point3::new(-h, -h, -h), Vector3::z(), Vector3::y()),
+        (Point3::new(-h, h, h), Vector3::x(), -Vector3::z()),
+        (Point3::new(-h, -h, -h), Vector3::x(), Vector3::z()),
+    ];
+
+    let mut data = MeshData::default();
+    for (origin, u, v) in faces {
+        data.append(grid(origin, u * size, v * size, segments, segments));
+    }
+    finish(data)
+}
+
+/// A sphere of `segments` around and `rings` from pole to pole, with
+/// equirectangular UVs.
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(2);
+    let profile: Vec<_> = (0..=rings)
+        .map(|j| {
+            let v = j as f32 / rings as f32;
+            let (sin, cos) = (v * PI - FRAC_PI_2).sin_cos();
+            ProfilePoint {
+                radius: radius * cos,
+                y: radius * sin,
+                normal: Vector2::new(cos, sin),
+                v,
+            }
+        })
+        .collect();
+
+    finish(lathe(&profile, segments))
+}
+
+/// A sphere from an icosahedron split `subdivisions` times, with evenly
+/// sized triangles. Vertices on the UV seam are duplicated.
+pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1. + 5f32.sqrt()) / 2.;
+    let mut positions: Vec<Vector3<f32>> = [
+        [-1., t, 0.],
+        [1., t, 0.],
+        [-1., -t, 0.],
+        [1., -t, 0.],
+        [0., -1., t],
+        [0., 1., t],
+        [0., -1., -t],
+        [0., 1., -t],
+        [t, 0., -1.],
+        [t, 0., 1.],
+        [-t, 0., -1.],
+        [-t, 0., 1.],
+    ]
+    .iter()
+    .map(|p| Vector3::from(*p).normalize())
+    .collect();
+
+    #[rustfmt::skip]
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let p = (positions[a as usize] + positions[b as usize]).normalize();
+                positions.push(p);
+                positions.len() as u32 - 1
+            })
+        };
+
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let uv = |p: &Vector3<f32>| {
+        let u = 0.5 + (-p.z).atan2(p.x) / TAU;
+        let v = 0.5 + p.y.clamp(-1., 1.).asin() / PI;
+        [u, v]
+    };
+
+    let vertices = positions
+        .iter()
+        .map(|p| vertex(Point3::from(p * radius), *p, uv(p)))
+        .collect();
+    let mut data = MeshData::new(vertices, vec![]);
+
+    // Triangles across the seam get copies of their low-u vertices moved
+    // past 1, so the texture does not wrap backwards over them.
+    let mut wrapped = HashMap::new();
+    for triangle in triangles.iter_mut() {
+        let us = triangle.map(|i| data.vertices[i as usize].uv[0]);
+        let (min, max) = (
+            us.iter().copied().fold(1., f32::min),
+            us.iter().copied().fold(0., f32::max),
+        );
+        if max - min > 0.5 {
+            for (index, u) in triangle.iter_mut().zip(us) {
+                if u < 0.5 {
+                    *index = *wrapped.entry(*index).or_insert_with(|| {
+                        let mut copy = data.vertices[*index as usize];
+                        copy.uv[0] += 1.;
+                        data.vertices.push(copy);
+                        data.vertices.len() as u32 - 1
+                    });
+                }
+            }
+        }
+    }
+    data.indices = triangles.into_iter().flatten().collect();
+
+    finish(data)
+}
+
+/// A cylinder or cone frustum standing on the XZ plane's center.
+fn frustum(
+    bottom_radius: f32,
+    top_radius: f32,
+    height: f32,
+    segments: u32,
+    height_segments: u32,
+    caps: bool,
+) -> MeshData {
+    let height_segments = height_segments.max(1);
+    let normal = Vector2::new(height, bottom_radius - top_radius).normalize();
+    let profile: Vec<_> = (0..=height_segments)
+        .map(|j| {
+            let v = j as f32 / height_segments as f32;
+            ProfilePoint {
+                radius: bottom_radius + (top_radius - bottom_radius) * v,
+                y: height * (v - 0.5),
+                normal,
+                v,
+            }
+        })
+        .collect();
+
+    let mut data = lathe(&profile, segments);
+    if caps {
+        if bottom_radius > 0. {
+            data.append(cap(bottom_radius, -height / 2., segments, false));
+        }
+        if top_radius > 0. {
+            data.append(cap(top_radius, height / 2., segments, true));
+        }
+    }
+    finish(data)
+}
+
+/// A cylinder of `height` along Y, optionally closed by caps.
+pub fn cylinder(
+    radius: f32,
+    height: f32,
+    segments: u32,
+    height_segments: u32,
+    caps: bool,
+) -> MeshData {
+    frustum(radius, radius, height, segments, height_segments, caps)
+}
+
+/// A cone with its tip at `height / 2` on the Y axis, optionally closed by a
+/// base.
+pub fn cone(radius: f32, height: f32, segments: u32, height_segments: u32, cap: bool) -> MeshData {
+    frustum(radius, 0., height, segments, height_segments, cap)
+}
+
+/// A ring in the XZ plane, `major_radius` from the center to the middle of
+/// the tube of `minor_radius`.
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> MeshData {
+    let minor_segments = minor_segments.max(3);
+    let profile: Vec<_> = (0..=minor_segments)
+        .map(|j| {
+            let v = j as f32 / minor_segments as f32;
+            let (sin, cos) = (v * TAU).sin_cos();
+            ProfilePoint {
+                radius: major_radius + minor_radius * cos,
+                y: minor_radius * sin,
+                normal: Vector2::new(cos, sin),
+                v,
+            }
+        })
+        .collect();
+
+    finish(lathe(&profile, major_segments))
+}
+
+/// A cylinder of `height` with hemispheres of `radius` on both ends, the
+/// total height is `height + 2 * radius`. Each hemisphere has `rings` rings.
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(1);
+    let total = height + PI * radius;
+
+    let mut profile = vec![];
+    for (offset, range) in [(-height / 2., 0..=rings), (height / 2., rings..=2 * rings)] {
+        for j in range {
+            let angle = j as f32 / (2 * rings) as f32 * PI - FRAC_PI_2;
+            let (sin, cos) = angle.sin_cos();
+            // Arc length so far, the straight part sits between the halves.
+            let arc = (angle + FRAC_PI_2) * radius + if offset > 0. { height } else { 0. };
+            profile.push(ProfilePoint {
+                radius: radius * cos,
+                y: offset + radius * sin,
+                normal: Vector2::new(cos, sin),
+                v: arc / total,
+            });
+        }
+    }
+
+    finish(lathe(&profile, segments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shapes() -> Vec<(&'static str, MeshData)> {
+        vec![
+            ("plane", plane(2., 3., 4, 5)),
+            ("cube", cube(2., 3)),
+            ("uv_sphere", uv_sphere(1.5, 16, 8)),
+            ("icosphere", icosphere(1., 2)),
+            ("cylinder", cylinder(1., 2., 12, 3, true)),
+            ("cone", cone(1., 2., 12, 2, true)),
+            ("torus", torus(2., 0.5, 16, 8)),
+            ("capsule", capsule(0.5, 1., 12, 4)),
+        ]
+    }
+
+    #[test]
+    fn vertices_are_well_formed() {
+        for (name, data) in shapes() {
+            assert!(!data.indices.is_empty(), "{name} has no triangles");
+            assert_eq!(data.indices.len() % 3, 0, "{name}");
+            assert!(data
+                .indices
+                .iter()
+                .all(|&i| (i as usize) < data.vertices.len()));
+
+            for v in data.vertices.iter() {
+                let normal = Vector3::from(v.normal);
+                let tangent = Vector3::new(v.tangent[0], v.tangent[1], v.tangent[2]);
+                assert!((normal.norm() - 1.).abs() < 1e-4, "{name} normal {normal}");
+                assert!(
+                    (tangent.norm() - 1.).abs() < 1e-4,
+                    "{name} tangent {tangent}"
+                );
+                assert!(normal.dot(&tangent).abs() < 1e-4, "{name}");
+                assert!(v.tangent[3].abs() == 1., "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn triangles_face_along_their_normals() {
+        for (name, data) in shapes() {
+            for triangle in data.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| data.vertices[triangle[i] as usize]);
+                let face = (Vector3::from(b.position) - Vector3::from(a.position))
+                    .cross(&(Vector3::from(c.position) - Vector3::from(a.position)));
+                let normal =
+                    Vector3::from(a.normal) + Vector3::from(b.normal) + Vector3::from(c.normal);
+                assert!(
+                    face.dot(&normal) > 0.,
+                    "{name} triangle {triangle:?} faces inwards"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn aabbs_match_dimensions() {
+        let close = |a: Point3<f32>, b: [f32; 3]| (a - Point3::from(b)).norm() < 1e-4;
+
+        let aabb = cube(2., 2).aabb().unwrap();
+        assert!(close(aabb.min, [-1., -1., -1.]) && close(aabb.max, [1., 1., 1.]));
+
+        let aabb = uv_sphere(1.5, 16, 8).aabb().unwrap();
+        assert!(close(aabb.min, [-1.5, -1.5, -1.5]) && close(aabb.max, [1.5, 1.5, 1.5]));
+
+        let aabb = capsule(0.5, 1., 12, 4).aabb().unwrap();
+        assert!(close(aabb.min, [-0.5, -1., -0.5]) && close(aabb.max, [0.5, 1., 0.5]));
+
+        let aabb = torus(2., 0.5, 16, 8).aabb().unwrap();
+        assert!(close(aabb.min, [-2.5, -0.5, -2.5]) && close(aabb.max, [2.5, 0.5, 2.5]));
+    }
+}
@@ -0,0 +1,975 @@
+use std::{path::Path, rc::Rc};
+
+use ::gltf::{
+    animation::util::ReadOutputs,
+    camera::Projection,
+    khr_lights_punctual::Kind,
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+    Document, Gltf,
+};
+use base64::Engine;
+use nalgebra::{Matrix4, Quaternion, UnitQuaternion, Vector3, Vector4};
+
+use super::{
+    AlphaMode, ImageSource, Model, ModelError, ModelMaterial, ModelMesh, ModelNode, ModelSkin,
+    TextureRef,
+};
+use crate::{
+    animation::{Channel, Clip, Interpolation, Property},
+    camera::Camera,
+    mesh::{MeshData, MeshVertex, MorphTarget, Submesh, Topology},
+    scene::{Light, LightKind, MAX_JOINTS},
+    texture::{Filter, MipmapFilter, SamplerDesc, TextureOptions, Wrap},
+    transform::Transform,
+};
+
+/// Parse a `.gltf` or `.glb` file. Buffers and images that are neither
+/// embedded nor data URIs are read through `load`, image files are taken
+/// relative to `texture_dir`.
+///
+/// Strips and fans become lists. Lines and points of a mesh are split into
+/// meshes of their own, on extra child nodes.
+pub fn parse(
+    file: &str,
+    bytes: &[u8],
+    texture_dir: &Path,
+    mut load: impl FnMut(&str) -> Result<Vec<u8>, ModelError>,
+) -> Result<Model, ModelError> {
+    let error = |message: String| ModelError::Gltf(file.to_string(), message);
+    let Gltf { document, mut blob } = Gltf::from_slice(bytes).map_err(|e| error(e.to_string()))?;
+
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let data = match buffer.source() {
+            ::gltf::buffer::Source::Bin => blob
+                .take()
+                .ok_or_else(|| error("Missing binary chunk.".to_string()))?,
+            ::gltf::buffer::Source::Uri(uri) => read_uri(uri, &mut load).map_err(|e| match e {
+                ModelError::Gltf(_, message) => error(message),
+                e => e,
+            })?,
+        };
+        if data.len() < buffer.length() {
+            return Err(error(format!(
+                "Buffer {} holds {} bytes, not {}.",
+                buffer.index(),
+                data.len(),
+                buffer.length()
+            )));
+        }
+        buffers.push(data);
+    }
+
+    let images = document
+        .images()
+        .map(|image| {
+            let name = format!("{}#image{}", file, image.index());
+            match image.source() {
+                ::gltf::image::Source::View { view, .. } => {
+                    let start = view.offset();
+                    let bytes = &buffers[view.buffer().index()][start..start + view.length()];
+                    Ok(ImageSource::Embedded {
+                        name,
+                        bytes: Rc::from(bytes),
+                    })
+                }
+                ::gltf::image::Source::Uri { uri, .. } if uri.starts_with("data:") => {
+                    Ok(ImageSource::Embedded {
+                        name,
+                        bytes: Rc::from(decode_data_uri(uri).map_err(error)?),
+                    })
+                }
+                ::gltf::image::Source::Uri { uri, .. } => {
+                    let path = texture_dir.join(percent_decode(uri));
+                    Ok(ImageSource::File(path.to_string_lossy().into_owned()))
+                }
+            }
+        })
+        .collect::<Result<Vec<_>, ModelError>>()?;
+
+    let mut model = Model {
+        materials: document
+            .materials()
+            .map(|material| convert_material(&material, &images))
+            .collect(),
+        ..Model::default()
+    };
+
+    // The model meshes of each glTF mesh, triangles first.
+    let mut meshes = Vec::new();
+    for mesh in document.meshes() {
+        let name = mesh
+            .name()
+            .map_or_else(|| format!("mesh{}", mesh.index()), str::to_string);
+        let mut parts: Vec<ModelMesh> = vec![];
+
+        for primitive in mesh.primitives() {
+            let data = read_primitive(&primitive, &buffers).map_err(|message| {
+                error(format!(
+                    "Mesh {} primitive {}: {}",
+                    name,
+                    primitive.index(),
+                    message
+                ))
+            })?;
+            let material = match primitive.material().index() {
+                Some(index) => index,
+                None => default_material(&mut model.materials),
+            };
+
+            let part = match parts
+                .iter_mut()
+                .find(|part| part.data.topology == data.topology)
+            {
+                Some(part) => part,
+                None => {
+                    let suffix = match data.topology {
+                        Topology::Points => " points",
+                        Topology::Lines => " lines",
+                        _ => "",
+                    };
+                    parts.push(ModelMesh {
+                        name: format!("{}{}", name, suffix),
+                        data: MeshData {
+                            topology: data.topology,
+                            ..MeshData::default()
+                        },
+                        submeshes: vec![],
+                    });
+                    parts.last_mut().unwrap()
+                }
+            };
+            let start = part.data.indices.len() as u32;
+            part.data.append(data);
+            part.submeshes.push(Submesh {
+                range: start..part.data.indices.len() as u32,
+                material,
+            });
+        }
+
+        parts.sort_by_key(|part| part.data.topology != Topology::Triangles);
+        let first = model.meshes.len();
+        model.meshes.extend(parts);
+        meshes.push(first..model.meshes.len());
+    }
+
+    let mut extra_parts = vec![];
+    for node in document.nodes() {
+        let name = node
+            .name()
+            .map_or_else(|| format!("node{}", node.index()), str::to_string);
+        let mut model_node = ModelNode::new(&name);
+
+        let (translation, [x, y, z, w], scale) = node.transform().decomposed();
+        model_node.transform = Transform::new(
+            Vector3::from(translation),
+            UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+            Vector3::from(scale),
+        );
+        model_node.children = node.children().map(|child| child.index()).collect();
+        model_node.camera = node.camera().map(|camera| convert_camera(&camera));
+        model_node.light = node.light().map(|light| convert_light(&light));
+        model_node.skin = node.skin().map(|skin| skin.index());
+
+        if let Some(mesh) = node.mesh() {
+            let mut parts = meshes[mesh.index()].clone();
+            model_node.mesh = parts.next();
+            model_node.morph_weights = node
+                .weights()
+                .or(mesh.weights())
+                .map_or_else(Vec::new, <[f32]>::to_vec);
+            extra_parts.extend(parts.map(|part| (node.index(), part)));
+        }
+        model.nodes.push(model_node);
+    }
+    // Lines and points hang below the node, after all glTF nodes.
+    for (parent, part) in extra_parts {
+        let mut child = ModelNode::new(&model.meshes[part].name);
+        child.mesh = Some(part);
+        child.morph_weights = model.nodes[parent].morph_weights.clone();
+        let index = model.nodes.len();
+        model.nodes[parent].children.push(index);
+        model.nodes.push(child);
+    }
+
+    for skin in document.skins() {
+        let joints: Vec<_> = skin.joints().map(|joint| joint.index()).collect();
+        let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+        let inverse_bind: Vec<_> = match reader.read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(Matrix4::from).collect(),
+            None => vec![Matrix4::identity(); joints.len()],
+        };
+        if joints.len() > MAX_JOINTS {
+            return Err(error(format!(
+                "Skin {} has {} joints, at most {} are supported.",
+                skin.index(),
+                joints.len(),
+                MAX_JOINTS
+            )));
+        }
+        if inverse_bind.len() < joints.len() {
+            return Err(error(format!(
+                "Skin {} has {} joints but {} inverse bind matrices.",
+                skin.index(),
+                joints.len(),
+                inverse_bind.len()
+            )));
+        }
+        model.skins.push(ModelSkin {
+            inverse_bind: inverse_bind[..joints.len()].to_vec(),
+            joints,
+        });
+    }
+
+    for animation in document.animations() {
+        let name = animation
+            .name()
+            .map_or_else(|| format!("animation{}", animation.index()), str::to_string);
+        let channels = animation
+            .channels()
+            .map(|channel| read_channel(&channel, &buffers))
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|message| error(format!("Animation {}: {}", name, message)))?;
+        model.animations.push(Clip::new(&name, channels));
+    }
+
+    model.roots = roots(&document);
+    Ok(model)
+}
+
+/// A channel animating a node's transform or morph target weights.
+fn read_channel(
+    channel: &::gltf::animation::Channel,
+    buffers: &[Vec<u8>],
+) -> Result<Channel, String> {
+    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+    let times: Vec<f32> = reader
+        .read_inputs()
+        .ok_or_else(|| "Channel without key times.".to_string())?
+        .collect();
+    let property = match reader.read_outputs() {
+        Some(ReadOutputs::Translations(values)) => {
+            Property::Translation(values.map(Vector3::from).collect())
+        }
+        Some(ReadOutputs::Rotations(values)) => {
+            Property::Rotation(values.into_f32().map(Vector4::from).collect())
+        }
+        Some(ReadOutputs::Scales(values)) => Property::Scale(values.map(Vector3::from).collect()),
+        Some(ReadOutputs::MorphTargetWeights(values)) => {
+            Property::Weights(values.into_f32().collect())
+        }
+        None => return Err("Channel without key values.".to_string()),
+    };
+    let interpolation = match channel.sampler().interpolation() {
+        ::gltf::animation::Interpolation::Step => Interpolation::Step,
+        ::gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        ::gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    };
+
+    let per_key = if interpolation == Interpolation::CubicSpline {
+        3
+    } else {
+        1
+    };
+    let keys = times.len() * per_key;
+    let (values, matches) = match &property {
+        Property::Translation(values) | Property::Scale(values) => {
+            (values.len(), values.len() == keys)
+        }
+        Property::Rotation(values) => (values.len(), values.len() == keys),
+        // Every key holds a weight per morph target.
+        Property::Weights(values) => (values.len(), keys > 0 && values.len().is_multiple_of(keys)),
+    };
+    if times.is_empty() || values == 0 || !matches {
+        return Err(format!("{} key times but {} values.", times.len(), values));
+    }
+    let target = channel.target().node().index();
+    Ok(Channel::new(target, times, interpolation, property))
+}
+
+/// The default scene, else the first one, else every node without a parent.
+fn roots(document: &Document) -> Vec<usize> {
+    if let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        return scene.nodes().map(|node| node.index()).collect();
+    }
+    let mut has_parent = vec![false; document.nodes().count()];
+    for node in document.nodes() {
+        for child in node.children() {
+            has_parent[child.index()] = true;
+        }
+    }
+    (0..has_parent.len()).filter(|&i| !has_parent[i]).collect()
+}
+
+/// Bytes of a buffer URI, either a data URI or a file read through `load`.
+fn read_uri(
+    uri: &str,
+    load: &mut impl FnMut(&str) -> Result<Vec<u8>, ModelError>,
+) -> Result<Vec<u8>, ModelError> {
+    if uri.starts_with("data:") {
+        decode_data_uri(uri).map_err(|message| ModelError::Gltf(String::new(), message))
+    } else {
+        load(&percent_decode(uri))
+    }
+}
+
+/// The payload of a base64 `data:` URI.
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, String> {
+    let (header, data) = uri
+        .split_once(',')
+        .ok_or_else(|| "Data URI without payload.".to_string())?;
+    if !header.ends_with(";base64") {
+        return Err(format!("'{}' is not base64.", header));
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("Bad base64 data: {}", e))
+}
+
+/// Undo `%XX` escapes in a relative URI.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| uri.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Index of the glTF default material, added on first use.
+fn default_material(materials: &mut Vec<ModelMaterial>) -> usize {
+    if let Some(index) = materials.iter().position(|m| m.name == DEFAULT_MATERIAL) {
+        return index;
+    }
+    let mut material = ModelMaterial::new(DEFAULT_MATERIAL);
+    material.metallic = 1.;
+    material.double_sided = false;
+    materials.push(material);
+    materials.len() - 1
+}
+
+const DEFAULT_MATERIAL: &str = "default";
+
+fn convert_material(material: &::gltf::Material, images: &[ImageSource]) -> ModelMaterial {
+    let name = match (material.name(), material.index()) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(index)) => format!("material{}", index),
+        (None, None) => DEFAULT_MATERIAL.to_string(),
+    };
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    let texture = |texture: ::gltf::Texture, tex_coord: u32, srgb: bool| TextureRef {
+        source: images[texture.source().index()].clone(),
+        options: texture_options(&texture.sampler(), srgb),
+        tex_coord,
+    };
+
+    let mut result = ModelMaterial::new(&name);
+    result.diffuse = [r, g, b];
+    result.opacity = a;
+    result.emissive = material.emissive_factor();
+    result.metallic = pbr.metallic_factor();
+    result.roughness = pbr.roughness_factor();
+    result.double_sided = material.double_sided();
+    result.alpha_mode = match material.alpha_mode() {
+        ::gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+        ::gltf::material::AlphaMode::Mask => {
+            AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5))
+        }
+        ::gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+    };
+
+    result.diffuse_map = pbr
+        .base_color_texture()
+        .map(|info| texture(info.texture(), info.tex_coord(), true));
+    result.metallic_roughness_map = pbr
+        .metallic_roughness_texture()
+        .map(|info| texture(info.texture(), info.tex_coord(), false));
+    result.normal_map = material
+        .normal_texture()
+        .map(|info| texture(info.texture(), info.tex_coord(), false));
+    result.occlusion_map = material
+        .occlusion_texture()
+        .map(|info| texture(info.texture(), info.tex_coord(), false));
+    result.emissive_map = material
+        .emissive_texture()
+        .map(|info| texture(info.texture(), info.tex_coord(), true));
+    result
+}
+
+/// glTF samplers repeat by default, and images start at the top left so
+/// they are not flipped.
+fn texture_options(sampler: &::gltf::texture::Sampler, srgb: bool) -> TextureOptions {
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) => (Filter::Nearest, MipmapFilter::None),
+        Some(MinFilter::Linear) => (Filter::Linear, MipmapFilter::None),
+        Some(MinFilter::NearestMipmapNearest) => (Filter::Nearest, MipmapFilter::Nearest),
+        Some(MinFilter::LinearMipmapNearest) => (Filter::Linear, MipmapFilter::Nearest),
+        Some(MinFilter::NearestMipmapLinear) => (Filter::Nearest, MipmapFilter::Linear),
+        Some(MinFilter::LinearMipmapLinear) | None => (Filter::Linear, MipmapFilter::Linear),
+    };
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => Filter::Nearest,
+        Some(MagFilter::Linear) | None => Filter::Linear,
+    };
+    let wrap = |mode| match mode {
+        WrappingMode::ClampToEdge => Wrap::ClampToEdge,
+        WrappingMode::MirroredRepeat => Wrap::MirroredRepeat,
+        WrappingMode::Repeat => Wrap::Repeat,
+    };
+
+    TextureOptions {
+        sampler: SamplerDesc {
+            min_filter,
+            mag_filter,
+            mipmap_filter,
+            wrap_s: wrap(sampler.wrap_s()),
+            wrap_t: wrap(sampler.wrap_t()),
+        },
+        mipmaps: mipmap_filter != MipmapFilter::None,
+        flip_v: false,
+        srgb,
+    }
+}
+
+/// Read every attribute of a primitive into a list topology, with flat
+/// normals and tangents filled in where the file has none.
+fn read_primitive(primitive: &::gltf::Primitive, buffers: &[Vec<u8>]) -> Result<MeshData, String> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .ok_or_else(|| "No POSITION attribute.".to_string())?
+        .collect();
+    let count = positions.len();
+
+    let check = |name: &str, len: usize| {
+        if len == count {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} has {} entries for {} vertices.",
+                name, len, count
+            ))
+        }
+    };
+    let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+    let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(Iterator::collect);
+    let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0).map(|t| t.into_f32().collect());
+    let mut data = MeshData {
+        colors: reader
+            .read_colors(0)
+            .map_or_else(Vec::new, |c| c.into_rgba_f32().collect()),
+        uvs1: reader
+            .read_tex_coords(1)
+            .map_or_else(Vec::new, |t| t.into_f32().collect()),
+        joints: reader
+            .read_joints(0)
+            .map_or_else(Vec::new, |j| j.into_u16().collect()),
+        weights: reader
+            .read_weights(0)
+            .map_or_else(Vec::new, |w| w.into_f32().collect()),
+        ..MeshData::default()
+    };
+    for (name, len) in [
+        ("NORMAL", normals.as_ref().map(Vec::len)),
+        ("TANGENT", tangents.as_ref().map(Vec::len)),
+        ("TEXCOORD_0", uvs.as_ref().map(Vec::len)),
+        ("TEXCOORD_1", Some(data.uvs1.len()).filter(|&n| n > 0)),
+        ("COLOR_0", Some(data.colors.len()).filter(|&n| n > 0)),
+        ("JOINTS_0", Some(data.joints.len()).filter(|&n| n > 0)),
+        ("WEIGHTS_0", Some(data.weights.len()).filter(|&n| n > 0)),
+    ] {
+        if let Some(len) = len {
+            check(name, len)?;
+        }
+    }
+
+    // Targets without positions only move normals.
+    for (index, (positions, normals, _)) in reader.read_morph_targets().enumerate() {
+        let target = MorphTarget {
+            name: format!("target{}", index),
+            positions: positions.map_or_else(|| vec![[0.; 3]; count], Iterator::collect),
+            normals: normals.map_or_else(Vec::new, Iterator::collect),
+        };
+        check(
+            &format!("Target {} POSITION", index),
+            target.positions.len(),
+        )?;
+        if !target.normals.is_empty() {
+            check(&format!("Target {} NORMAL", index), target.normals.len())?;
+        }
+        data.morph_targets.push(target);
+    }
+
+    data.vertices = (0..count)
+        .map(|i| MeshVertex {
+            position: positions[i],
+            uv: uvs.as_ref().map_or([0.; 2], |uvs| uvs[i]),
+            normal: normals.as_ref().map_or([0.; 3], |normals| normals[i]),
+            tangent: tangents.as_ref().map_or([0.; 4], |tangents| tangents[i]),
+        })
+        .collect();
+
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..count as u32).collect(),
+    };
+    if let Some(&index) = indices.iter().find(|&&i| i as usize >= count) {
+        return Err(format!("Index {} out of {} vertices.", index, count));
+    }
+    (data.topology, data.indices) = to_list(primitive.mode(), &indices);
+
+    if data.topology == Topology::Triangles {
+        if normals.is_none() {
+            data = flat_shaded(data);
+        }
+        if tangents.is_none() {
+            data.compute_tangents();
+        }
+    }
+    Ok(data)
+}
+
+/// Turn strips, fans and loops into plain lists.
+fn to_list(mode: Mode, indices: &[u32]) -> (Topology, Vec<u32>) {
+    let n = indices.len();
+    match mode {
+        Mode::Points => (Topology::Points, indices.to_vec()),
+        Mode::Lines => (Topology::Lines, indices[..n - n % 2].to_vec()),
+        Mode::LineStrip | Mode::LineLoop => {
+            let mut lines: Vec<u32> = indices.windows(2).flatten().copied().collect();
+            if mode == Mode::LineLoop && n > 2 {
+                lines.extend([indices[n - 1], indices[0]]);
+            }
+            (Topology::Lines, lines)
+        }
+        Mode::Triangles => (Topology::Triangles, indices[..n - n % 3].to_vec()),
+        Mode::TriangleStrip => {
+            let triangles = (0..n.saturating_sub(2))
+                .flat_map(|i| {
+                    let odd = i % 2;
+                    [indices[i], indices[i + 1 + odd], indices[i + 2 - odd]]
+                })
+                .collect();
+            (Topology::Triangles, triangles)
+        }
+        Mode::TriangleFan => {
+            let triangles = (0..n.saturating_sub(2))
+                .flat_map(|i| [indices[i + 1], indices[i + 2], indices[0]])
+                .collect();
+            (Topology::Triangles, triangles)
+        }
+    }
+}
+
+/// Give every triangle its own vertices with the face normal, which is what
+/// glTF asks for when normals are missing.
+fn flat_shaded(data: MeshData) -> MeshData {
+    let mut flat = MeshData {
+        vertices: data
+            .indices
+            .iter()
+            .map(|&i| data.vertices[i as usize])
+            .collect(),
+        indices: (0..data.indices.len() as u32).collect(),
+        topology: Topology::Triangles,
+        colors: pick(&data.colors, &data.indices),
+        uvs1: pick(&data.uvs1, &data.indices),
+        joints: pick(&data.joints, &data.indices),
+        weights: pick(&data.weights, &data.indices),
+        morph_targets: data
+            .morph_targets
+            .iter()
+            .map(|target| MorphTarget {
+                name: target.name.clone(),
+                positions: pick(&target.positions, &data.indices),
+                normals: pick(&target.normals, &data.indices),
+            })
+            .collect(),
+    };
+    for triangle in flat.vertices.chunks_exact_mut(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| Vector3::from(triangle[i].position));
+        let normal = (b - a)
+            .cross(&(c - a))
+            .try_normalize(0.)
+            .unwrap_or_else(Vector3::z);
+        for vertex in triangle {
+            vertex.normal = normal.into();
+        }
+    }
+    flat
+}
+
+/// The aspect ratio is left to the viewport.
+fn convert_camera(camera: &::gltf::Camera) -> Camera {
+    match camera.projection() {
+        Projection::Perspective(p) => {
+            Camera::perspective(p.yfov(), p.znear(), p.zfar().unwrap_or(f32::INFINITY))
+        }
+        Projection::Orthographic(o) => Camera::orthographic(2. * o.ymag(), o.znear(), o.zfar()),
+    }
+}
+
+/// Lights without a range reach infinitely far, as the extension specifies.
+fn convert_light(light: &::gltf::khr_lights_punctual::Light) -> Light {
+    let range = light.range().unwrap_or(f32::INFINITY);
+    let kind = match light.kind() {
+        Kind::Directional => LightKind::Directional,
+        Kind::Point => LightKind::Point { range },
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => LightKind::Spot {
+            range,
+            inner_angle: inner_cone_angle,
+            outer_angle: outer_cone_angle,
+        },
+    };
+    Light::new(kind)
+        .with_color(light.color())
+        .with_intensity(light.intensity())
+}
+
+/// `values` in the order of `indices`, empty stays empty.
+fn pick<T: Copy>(values: &[T], indices: &[u32]) -> Vec<T> {
+    if values.is_empty() {
+        return vec![];
+    }
+    indices.iter().map(|&i| values[i as usize]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Projection;
+
+    fn load(name: &str) -> Model {
+        Model::load_gltf(name).unwrap()
+    }
+
+    #[test]
+    fn triangles_with_and_without_indices() {
+        for name in [
+            "MiniTriangle/MiniTriangle.gltf",
+            "MiniTriangleWithoutIndices/MiniTriangleWithoutIndices.gltf",
+        ] {
+            let model = load(name);
+            let data = &model.meshes[0].data;
+            assert_eq!(data.vertices.len(), 3, "{}", name);
+            assert_eq!(data.indices, vec![0, 1, 2], "{}", name);
+            // No normals in the file, so they face the front.
+            assert_eq!(data.vertices[0].normal, [0., 0., 1.], "{}", name);
+            assert_eq!(model.roots, vec![0]);
+            assert_eq!(model.materials[0].name, DEFAULT_MATERIAL);
+        }
+    }
+
+    #[test]
+    fn sparse_accessors_displace_positions() {
+        let model = load("MiniSimpleSparseAccessor/MiniSimpleSparseAccessor.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!(data.indices.len(), 36);
+        // Flat shading unwelds the grid.
+        let positions: Vec<_> = data.vertices.iter().map(|v| v.position).collect();
+        for point in [[1., 2., 0.], [3., 3., 0.], [5., 4., 0.]] {
+            assert!(positions.contains(&point), "{:?}", point);
+        }
+        assert!(!positions.contains(&[1., 1., 0.]));
+    }
+
+    #[test]
+    fn cameras_are_attached_to_nodes() {
+        let model = load("MiniCameras/MiniCameras.gltf");
+        assert_eq!(model.roots, vec![0, 1, 2]);
+        assert_eq!(model.nodes[0].mesh, Some(0));
+
+        let perspective = model.nodes[1].camera.as_ref().unwrap();
+        assert_eq!(
+            perspective.projection,
+            Projection::Perspective {
+                fovy: 0.7,
+                near: 0.01,
+                far: 100.
+            }
+        );
+        assert_eq!(
+            model.nodes[1].transform.translation,
+            Vector3::new(0.5, 0.5, 3.)
+        );
+        let orthographic = model.nodes[2].camera.as_ref().unwrap();
+        assert_eq!(
+            orthographic.projection,
+            Projection::Orthographic {
+                height: 2.,
+                near: 0.01,
+                far: 100.
+            }
+        );
+    }
+
+    #[test]
+    fn binary_box_keeps_hierarchy_and_material() {
+        let model = load("MiniBox/MiniBox.glb");
+        assert_eq!(model.roots, vec![0]);
+        assert_eq!(model.nodes[0].children, vec![1]);
+        assert_eq!(model.nodes[1].mesh, Some(0));
+        // The root matrix turns Z up into Y up.
+        let up = model.nodes[0]
+            .transform
+            .matrix()
+            .transform_vector(&Vector3::z());
+        assert!((up - Vector3::y()).norm() < 1e-6);
+
+        let data = &model.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (24, 36));
+        let material = &model.materials[model.meshes[0].submeshes[0].material];
+        assert_eq!(material.name, "Red");
+        assert_eq!(material.diffuse, [0.8, 0., 0.]);
+        assert_eq!(material.metallic, 0.);
+        assert!(!material.double_sided);
+    }
+
+    #[test]
+    fn external_images_use_the_sampler() {
+        let model = load("MiniBoxTextured/MiniBoxTextured.gltf");
+        let map = model.materials[0].diffuse_map.as_ref().unwrap();
+        assert_eq!(
+            map.source,
+            ImageSource::File("../models/MiniBoxTextured/Checker.png".to_string())
+        );
+        assert!(map.options.srgb && !map.options.flip_v && map.options.mipmaps);
+        assert_eq!(
+            map.options.sampler,
+            SamplerDesc {
+                min_filter: Filter::Nearest,
+                mag_filter: Filter::Linear,
+                mipmap_filter: MipmapFilter::Linear,
+                wrap_s: Wrap::Repeat,
+                wrap_t: Wrap::MirroredRepeat,
+            }
+        );
+        let vertex = &model.meshes[0].data.vertices[0];
+        assert_ne!(vertex.tangent, [0.; 4]);
+    }
+
+    #[test]
+    fn embedded_images_and_vertex_colors() {
+        let model = load("MiniBoxVertexColors/MiniBoxVertexColors.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!(data.colors.len(), data.vertices.len());
+
+        let material = &model.materials[0];
+        assert_eq!(material.alpha_mode, AlphaMode::Mask(0.25));
+        assert_eq!((material.metallic, material.roughness), (0.5, 0.25));
+        assert_eq!(material.emissive, [0.1, 0.2, 0.3]);
+        assert!(material.double_sided);
+        let map = material.diffuse_map.as_ref().unwrap();
+        let ImageSource::Embedded { name, bytes } = &map.source else {
+            panic!("{:?}", map.source);
+        };
+        assert_eq!(name, "MiniBoxVertexColors/MiniBoxVertexColors.gltf#image0");
+        assert!(bytes.starts_with(b"\x89PNG"));
+        // No sampler, so repeat with trilinear filtering.
+        assert_eq!(map.options.sampler.wrap_s, Wrap::Repeat);
+        assert_eq!(map.options.sampler.mipmap_filter, MipmapFilter::Linear);
+    }
+
+    #[test]
+    fn skins_and_animations() {
+        let model = load("MiniSimpleSkin/MiniSimpleSkin.gltf");
+        let data = &model.meshes[0].data;
+        // Flat shading unwelds the strip, the attributes follow along.
+        assert_eq!(data.joints.len(), data.vertices.len());
+        assert_eq!(data.weights.len(), data.vertices.len());
+        for (vertex, weights) in data.vertices.iter().zip(&data.weights) {
+            let w = vertex.position[1] / 2.;
+            assert_eq!(*weights, [1. - w, w, 0., 0.]);
+        }
+        assert!(data.joints.iter().all(|&joints| joints == [0, 1, 0, 0]));
+
+        assert_eq!(model.nodes[0].skin, Some(0));
+        let skin = &model.skins[0];
+        assert_eq!(skin.joints, vec![1, 2]);
+        assert_eq!(
+            skin.inverse_bind[0],
+            Matrix4::new_translation(&Vector3::new(-0.5, -1., 0.))
+        );
+
+        let clip = &model.animations[0];
+        assert_eq!(clip.duration, 2.);
+        let channel = &clip.channels[0];
+        assert_eq!(
+            (channel.target, channel.interpolation),
+            (2, Interpolation::Linear)
+        );
+        let mut pose = vec![Transform::IDENTITY; model.nodes.len()];
+        clip.sample(0.5, &mut pose);
+        assert!((pose[2].rotation.angle() - 0.6).abs() < 1e-5);
+    }
+
+    #[test]
+    fn morph_targets_and_weights() {
+        let model = load("MiniSimpleMorph/MiniSimpleMorph.gltf");
+        let targets = &model.meshes[0].data.morph_targets;
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].positions[2], [0., 1., 0.]);
+        assert!(targets[0].normals.is_empty());
+        assert_eq!(targets[1].positions[1], [0.5, 0., 0.]);
+        assert_eq!(targets[1].normals[1], [0., 0.5, -0.5]);
+        assert_eq!(model.nodes[0].morph_weights, vec![0.5, 0.5]);
+
+        let clip = &model.animations[0];
+        assert_eq!(clip.duration, 2.);
+        let mut weights = vec![vec![]; model.nodes.len()];
+        clip.sample_weights(1.5, &mut weights);
+        assert_eq!(weights[0], vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn punctual_lights() {
+        let model = load("PunctualLights/PunctualLights.gltf");
+        let light = |name: &str| {
+            let node = model.nodes.iter().find(|n| n.name == name).unwrap();
+            node.light.unwrap()
+        };
+        assert_eq!(light("Sun").kind, LightKind::Directional);
+        assert_eq!(light("Sun").color, [1., 0.9, 0.8]);
+        assert_eq!(light("Bulb").kind, LightKind::Point { range: 10. });
+        assert_eq!(
+            light("Spot").kind,
+            LightKind::Spot {
+                range: f32::INFINITY,
+                inner_angle: 0.2,
+                outer_angle: 0.6
+            }
+        );
+        assert_eq!(light("Spot").intensity, 50.);
+    }
+
+    #[test]
+    #[ignore = "needs the originals from res/models/khronos/fetch.sh"]
+    fn khronos_samples_load() {
+        // Neither triangle has normals, flat shading keeps their three vertices.
+        for name in [
+            "Triangle/Triangle.gltf",
+            "TriangleWithoutIndices/TriangleWithoutIndices.gltf",
+        ] {
+            let data = &load(&format!("khronos/{}", name)).meshes[0].data;
+            assert_eq!(data.vertices.len(), 3, "{}", name);
+            assert_eq!(data.indices, vec![0, 1, 2], "{}", name);
+        }
+
+        // 14 grid vertices in 12 triangles, unwelded, with vertices 8, 10 and
+        // 12 moved up by the sparse accessor.
+        let model = load("khronos/SimpleSparseAccessor/SimpleSparseAccessor.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (36, 36));
+        let positions: Vec<_> = data.vertices.iter().map(|v| v.position).collect();
+        for point in [[1., 2., 0.], [3., 3., 0.], [5., 4., 0.]] {
+            assert!(positions.contains(&point), "{:?}", point);
+        }
+        assert!(!positions.contains(&[1., 1., 0.]));
+
+        let model = load("khronos/Cameras/Cameras.gltf");
+        assert_eq!(model.meshes[0].data.indices.len(), 6);
+        let cameras: Vec<_> = model
+            .nodes
+            .iter()
+            .filter_map(|n| n.camera.as_ref())
+            .collect();
+        assert_eq!(cameras.len(), 2);
+        assert!(matches!(
+            cameras[0].projection,
+            Projection::Perspective { .. }
+        ));
+        assert!(matches!(
+            cameras[1].projection,
+            Projection::Orthographic { .. }
+        ));
+
+        let model = load("khronos/Box/Box.glb");
+        let data = &model.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (24, 36));
+        let material = &model.materials[model.meshes[0].submeshes[0].material];
+        assert_eq!(material.name, "Red");
+        assert_eq!(material.diffuse, [0.8, 0., 0.]);
+
+        let model = load("khronos/BoxTextured/BoxTextured.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (24, 36));
+        assert!(model.materials[0].diffuse_map.is_some());
+
+        let model = load("khronos/BoxVertexColors/BoxVertexColors.gltf");
+        let data = &model.meshes[0].data;
+        assert!(!data.colors.is_empty());
+        assert_eq!(data.colors.len(), data.vertices.len());
+
+        // Ten vertices in eight triangles, unwelded, bent by two joints.
+        let model = load("khronos/SimpleSkin/SimpleSkin.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!((data.vertices.len(), data.indices.len()), (24, 24));
+        assert_eq!(data.joints.len(), data.vertices.len());
+        assert_eq!(data.weights.len(), data.vertices.len());
+        assert_eq!(model.skins[0].joints.len(), 2);
+        assert_eq!(model.animations.len(), 1);
+        let channels = &model.animations[0].channels;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].target, 2);
+
+        let model = load("khronos/SimpleMorph/SimpleMorph.gltf");
+        let data = &model.meshes[0].data;
+        assert_eq!(data.vertices.len(), 3);
+        assert_eq!(data.morph_targets.len(), 2);
+        assert!(data
+            .morph_targets
+            .iter()
+            .all(|target| target.positions.len() == 3));
+        assert_eq!(model.nodes[0].morph_weights, vec![0.5, 0.5]);
+        assert_eq!(model.animations[0].channels.len(), 1);
+    }
+
+    #[test]
+    fn strips_fans_and_loops_become_lists() {
+        let indices = [0, 1, 2, 3];
+        assert_eq!(
+            to_list(Mode::TriangleStrip, &indices),
+            (Topology::Triangles, vec![0, 1, 2, 1, 3, 2])
+        );
+        assert_eq!(
+            to_list(Mode::TriangleFan, &indices),
+            (Topology::Triangles, vec![1, 2, 0, 2, 3, 0])
+        );
+        assert_eq!(
+            to_list(Mode::LineLoop, &indices[..3]),
+            (Topology::Lines, vec![0, 1, 1, 2, 2, 0])
+        );
+    }
+
+    #[test]
+    fn uris_are_decoded() {
+        assert_eq!(percent_decode("my%20file.bin"), "my file.bin");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(
+            decode_data_uri("data:application/octet-stream;base64,AAEC").unwrap(),
+            vec![0, 1, 2]
+        );
+    }
+}
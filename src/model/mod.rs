@@ -0,0 +1,463 @@
+mod gltf;
+mod mtl;
+mod obj;
+
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use nalgebra::Matrix4;
+
+use crate::{
+    animation::{AnimationPlayer, Clip},
+    camera::Camera,
+    capabilities::Capabilities,
+    mesh::{Mesh, MeshData, Submesh},
+    render_state::{BlendState, CullState, DepthState, RenderState},
+    renderer::gl,
+    resources::{Handle, ResourceManager},
+    scene::{Light, Material, Node, NodeId, Scene, Skin},
+    shaders::Shader,
+    texture::{Texture, TextureOptions},
+    transform::Transform,
+};
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(PathBuf, io::Error),
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    /// A glTF file that is malformed or breaks the specification.
+    Gltf(String, String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Io(path, err) => write!(f, "Can not read {}: {}", path.display(), err),
+            ModelError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{} line {}: {}", file, line, message),
+            ModelError::Gltf(file, message) => write!(f, "{}: {}", file, message),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// Where the pixels of a [`TextureRef`] come from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// A file name relative to `res/textures`.
+    File(String),
+    /// An encoded image held in memory, shared through the resource manager
+    /// under `name`.
+    Embedded { name: String, bytes: Rc<[u8]> },
+}
+
+/// A texture map of a [`ModelMaterial`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureRef {
+    pub source: ImageSource,
+    pub options: TextureOptions,
+    /// Which UV set the map uses, 0 for [`crate::mesh::MeshVertex::uv`] and 1
+    /// for [`MeshData::uvs1`].
+    pub tex_coord: u32,
+}
+
+impl TextureRef {
+    /// A file in `res/textures` with default options.
+    pub fn file(name: &str) -> Self {
+        Self {
+            source: ImageSource::File(name.to_string()),
+            options: TextureOptions::default(),
+            tex_coord: 0,
+        }
+    }
+
+    /// Load the texture through `resources`. Images that are missing or can
+    /// not be decoded are reported and skipped.
+    pub fn load(&self, gl: &gl::Gl, resources: &ResourceManager) -> Option<Handle<Texture>> {
+        let (name, texture) = match &self.source {
+            ImageSource::File(name) => (name, resources.try_load_texture(gl, name, &self.options)),
+            ImageSource::Embedded { name, bytes } => (
+                name,
+                resources.load_texture_bytes(gl, name, bytes, &self.options),
+            ),
+        };
+        texture
+            .map_err(|e| eprintln!("Can not load texture {}: {}", name, e))
+            .ok()
+    }
+}
+
+/// How the alpha of the base color is used.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AlphaMode {
+    /// Alpha is ignored.
+    #[default]
+    Opaque,
+    /// Fragments below the cutoff are discarded by the shader.
+    Mask(f32),
+    /// Blended over what is behind.
+    Blend,
+}
+
+/// Surface properties of an imported material.
+///
+/// OBJ files fill in the Phong terms, glTF files the metallic-roughness
+/// ones. Both use `diffuse` and `opacity` as the base color.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelMaterial {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub emissive: [f32; 3],
+    pub shininess: f32,
+    pub opacity: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    pub diffuse_map: Option<TextureRef>,
+    pub specular_map: Option<TextureRef>,
+    pub normal_map: Option<TextureRef>,
+    pub emissive_map: Option<TextureRef>,
+    pub opacity_map: Option<TextureRef>,
+    /// Roughness in green and metalness in blue.
+    pub metallic_roughness_map: Option<TextureRef>,
+    /// Ambient occlusion in red.
+    pub occlusion_map: Option<TextureRef>,
+}
+
+impl ModelMaterial {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambient: [0., 0., 0.],
+            diffuse: [1., 1., 1.],
+            specular: [0., 0., 0.],
+            emissive: [0., 0., 0.],
+            shininess: 1.,
+            opacity: 1.,
+            metallic: 0.,
+            roughness: 1.,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: true,
+            diffuse_map: None,
+            specular_map: None,
+            normal_map: None,
+            emissive_map: None,
+            opacity_map: None,
+            metallic_roughness_map: None,
+            occlusion_map: None,
+        }
+    }
+
+    /// Whether it needs blending.
+    pub fn is_transparent(&self) -> bool {
+        self.alpha_mode == AlphaMode::Blend
+    }
+
+    /// Depth tested, blended when transparent and culled unless double
+    /// sided. Transparent surfaces leave depth alone so they never hide each
+    /// other, drawn unsorted their blended colors still depend on the order.
+    pub fn render_state(&self) -> RenderState {
+        let mut state = RenderState::default();
+        if self.is_transparent() {
+            let depth = DepthState {
+                write: false,
+                ..DepthState::default()
+            };
+            state = state.with_depth(depth).with_blend(BlendState::alpha());
+        } else {
+            state = state.with_depth(DepthState::default());
+        }
+        if !self.double_sided {
+            state = state.with_cull(CullState::default());
+        }
+        state
+    }
+
+    /// A [`Material`] drawn by `shader`, with the diffuse color and map.
+    pub fn material(
+        &self,
+        gl: &gl::Gl,
+        resources: &ResourceManager,
+        shader: Handle<Shader>,
+    ) -> Material {
+        let [r, g, b] = self.diffuse;
+        let mut material = Material::new(shader)
+            .with_color([r, g, b, self.opacity])
+            .with_state(self.render_state());
+        if let Some(texture) = self
+            .diffuse_map
+            .as_ref()
+            .and_then(|map| map.load(gl, resources))
+        {
+            material = material.with_texture(texture);
+        }
+        material
+    }
+}
+
+/// A named part of a model, split into submeshes by material.
+#[derive(Debug, Clone)]
+pub struct ModelMesh {
+    pub name: String,
+    pub data: MeshData,
+    /// Material indices point into [`Model::materials`].
+    pub submeshes: Vec<Submesh>,
+}
+
+impl ModelMesh {
+    pub fn upload(&self, gl: &gl::Gl, caps: &Capabilities) -> Mesh {
+        self.data
+            .upload(gl, caps)
+            .with_submeshes(self.submeshes.clone())
+    }
+}
+
+/// A node of the model's hierarchy, see [`crate::scene::Node`].
+#[derive(Debug, Clone)]
+pub struct ModelNode {
+    pub name: String,
+    pub transform: Transform,
+    /// Index into [`Model::meshes`].
+    pub mesh: Option<usize>,
+    pub camera: Option<Camera>,
+    pub light: Option<Light>,
+    /// Index into [`Model::skins`], deforming the node's mesh.
+    pub skin: Option<usize>,
+    /// Initial weights of the mesh's morph targets.
+    pub morph_weights: Vec<f32>,
+    /// Indices into [`Model::nodes`].
+    pub children: Vec<usize>,
+}
+
+impl ModelNode {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            transform: Transform::IDENTITY,
+            mesh: None,
+            camera: None,
+            light: None,
+            skin: None,
+            morph_weights: vec![],
+            children: vec![],
+        }
+    }
+}
+
+/// Joints of a skinned mesh, see [`crate::scene::Skin`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSkin {
+    /// Indices into [`Model::nodes`].
+    pub joints: Vec<usize>,
+    pub inverse_bind: Vec<Matrix4<f32>>,
+}
+
+/// Meshes, materials and their hierarchy read from a model file, still on
+/// the CPU.
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    pub meshes: Vec<ModelMesh>,
+    pub materials: Vec<ModelMaterial>,
+    pub nodes: Vec<ModelNode>,
+    /// The top level nodes, indices into [`Model::nodes`].
+    pub roots: Vec<usize>,
+    pub skins: Vec<ModelSkin>,
+    /// Channels target indices into [`Model::nodes`].
+    pub animations: Vec<Clip>,
+}
+
+/// The scene nodes made by [`Model::add_to_scene`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInstance {
+    /// The node everything was added below.
+    pub root: NodeId,
+    /// The scene node of each of [`Model::nodes`], `None` for those outside
+    /// of the roots' hierarchy.
+    pub nodes: Vec<Option<NodeId>>,
+}
+
+impl Model {
+    /// Load a Wavefront OBJ file from `res/models`, along with the MTL files
+    /// it references. Texture maps are resolved next to the MTL file.
+    ///
+    /// A material library that can not be read is reported and replaced by
+    /// default materials.
+    pub fn load_obj(name: &str) -> Result<Self, ModelError> {
+        let dir = Path::new(name).parent().unwrap_or(Path::new(""));
+        let source = read(&Path::new(MODEL_DIR).join(name))?;
+
+        obj::parse(name, &source, |library| {
+            let path = Path::new(MODEL_DIR).join(dir).join(library);
+            let source = read(&path)?;
+            mtl::parse(library, &source, &texture_dir(dir))
+        })
+    }
+
+    /// Parse OBJ `source`. MTL libraries are looked up through `load_library`
+    /// and texture maps taken relative to `res/textures`.
+    pub fn parse_obj(
+        name: &str,
+        source: &str,
+        mut load_library: impl FnMut(&str) -> Result<String, ModelError>,
+    ) -> Result<Self, ModelError> {
+        obj::parse(name, source, |library| {
+            mtl::parse(library, &load_library(library)?, Path::new(""))
+        })
+    }
+
+    /// Load a `.gltf` or `.glb` file from `res/models`, with the buffers and
+    /// images it references next to it.
+    ///
+    /// The default scene, or else the first one, becomes [`Model::roots`].
+    pub fn load_gltf(name: &str) -> Result<Self, ModelError> {
+        let path = Path::new(MODEL_DIR).join(name);
+        let bytes = std::fs::read(&path).map_err(|e| ModelError::Io(path, e))?;
+        let dir = Path::new(name).parent().unwrap_or(Path::new(""));
+
+        gltf::parse(name, &bytes, &texture_dir(dir), |uri| {
+            let path = Path::new(MODEL_DIR).join(dir).join(uri);
+            std::fs::read(&path).map_err(|e| ModelError::Io(path, e))
+        })
+    }
+
+    /// Upload every mesh and add the hierarchy below a new node called
+    /// `name`, all drawn by `shader`. Meshes used by several nodes are
+    /// uploaded once.
+    pub fn add_to_scene(
+        &self,
+        gl: &gl::Gl,
+        resources: &ResourceManager,
+        scene: &mut Scene,
+        name: &str,
+        shader: Handle<Shader>,
+    ) -> ModelInstance {
+        let materials: Vec<_> = self
+            .materials
+            .iter()
+            .map(|material| material.material(gl, resources, shader.clone()))
+            .collect();
+        let mut meshes = HashMap::new();
+        let mut nodes = vec![None; self.nodes.len()];
+
+        let root = scene.add(Node::new(name));
+        let mut stack: Vec<_> = self.roots.iter().rev().map(|&node| (root, node)).collect();
+        while let Some((parent, index)) = stack.pop() {
+            let node = &self.nodes[index];
+            let mut scene_node = Node::new(&node.name).with_transform(node.transform);
+            if let Some(mesh) = node.mesh {
+                let handle = meshes.entry(mesh).or_insert_with(|| {
+                    resources.insert(self.meshes[mesh].upload(gl, resources.capabilities()))
+                });
+                scene_node = scene_node.with_materials(handle.clone(), materials.clone());
+            }
+            scene_node.camera = node.camera.clone();
+            scene_node.light = node.light;
+            scene_node.morph_weights = node.morph_weights.clone();
+
+            let id = scene.add_child(parent, scene_node);
+            nodes[index] = Some(id);
+            stack.extend(node.children.iter().rev().map(|&child| (id, child)));
+        }
+
+        // Joints can be anywhere in the hierarchy, so skins come last.
+        for (index, node) in self.nodes.iter().enumerate() {
+            let (Some(id), Some(skin)) = (nodes[index], node.skin) else {
+                continue;
+            };
+            let skin = &self.skins[skin];
+            let joints: Option<Vec<_>> = skin.joints.iter().map(|&joint| nodes[joint]).collect();
+            match joints {
+                Some(joints) => {
+                    scene.node_mut(id).skin = Some(Skin::new(joints, skin.inverse_bind.clone()))
+                }
+                None => eprintln!(
+                    "{}: skin of {} has joints outside of the scene.",
+                    name, node.name
+                ),
+            }
+        }
+
+        ModelInstance { root, nodes }
+    }
+
+    /// A player for [`Model::animations`] on the nodes of `instance`.
+    /// Channels on nodes that were not added to the scene are dropped.
+    pub fn animation_player(&self, scene: &Scene, instance: &ModelInstance) -> AnimationPlayer {
+        let mut targets = vec![];
+        let target_index: Vec<_> = instance
+            .nodes
+            .iter()
+            .map(|node| {
+                node.map(|id| {
+                    targets.push(id);
+                    targets.len() - 1
+                })
+            })
+            .collect();
+
+        let clips = self
+            .animations
+            .iter()
+            .map(|clip| {
+                let mut clip = clip.clone();
+                clip.channels.retain_mut(|channel| {
+                    match target_index.get(channel.target).copied().flatten() {
+                        Some(target) => {
+                            channel.target = target;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+                clip
+            })
+            .collect();
+        AnimationPlayer::new(scene, targets, clips)
+    }
+}
+
+const MODEL_DIR: &str = "res/models";
+
+fn read(path: &Path) -> Result<String, ModelError> {
+    std::fs::read_to_string(path).map_err(|e| ModelError::Io(path.to_path_buf(), e))
+}
+
+/// `dir` below `res/models` as seen from `res/textures`.
+fn texture_dir(dir: &Path) -> PathBuf {
+    Path::new("../models").join(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blended_materials_do_not_write_depth() {
+        let mut material = ModelMaterial::new("glass");
+        let opaque = material.render_state();
+        assert_eq!(opaque.depth(), Some(DepthState::default()));
+        assert_eq!(opaque.blend(), None);
+
+        material.alpha_mode = AlphaMode::Blend;
+        material.double_sided = true;
+        let blended = material.render_state();
+        assert_eq!(blended.blend(), Some(BlendState::alpha()));
+        assert!(!blended.depth().unwrap().write);
+        assert_eq!(blended.cull(), None);
+    }
+}
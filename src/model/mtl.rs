@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use super::{AlphaMode, ModelError, ModelMaterial, TextureRef};
+
+/// Parse an MTL material library, map names are joined to `texture_dir`.
+///
+/// Unknown statements, such as `illum`, are ignored.
+pub fn parse(
+    file: &str,
+    source: &str,
+    texture_dir: &Path,
+) -> Result<Vec<ModelMaterial>, ModelError> {
+    let mut materials: Vec<ModelMaterial> = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |message: String| ModelError::Parse {
+            file: file.to_string(),
+            line: index + 1,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap().trim();
+        let Some((keyword, rest)) = split_keyword(line) else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            materials.push(ModelMaterial::new(rest));
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            return Err(error(format!("'{}' before any newmtl", keyword)));
+        };
+
+        let floats = || -> Result<Vec<f32>, ModelError> {
+            rest.split_whitespace()
+                .map(|s| {
+                    s.parse()
+                        .map_err(|_| error(format!("'{}' is not a number", s)))
+                })
+                .collect()
+        };
+        let color = || -> Result<[f32; 3], ModelError> {
+            match floats()?[..] {
+                [r] => Ok([r, r, r]),
+                [r, g, b, ..] => Ok([r, g, b]),
+                _ => Err(error(format!("'{}' needs a color", keyword))),
+            }
+        };
+        let float = || -> Result<f32, ModelError> {
+            floats()?
+                .first()
+                .copied()
+                .ok_or_else(|| error(format!("'{}' needs a value", keyword)))
+        };
+        let map = || -> Result<Option<TextureRef>, ModelError> {
+            // Options like `-bm 0.5` come before the file name, which is
+            // taken to be the last argument.
+            let name = rest
+                .split_whitespace()
+                .last()
+                .ok_or_else(|| error(format!("'{}' needs a file name", keyword)))?;
+            let path = texture_dir.join(name.replace('\\', "/"));
+            Ok(Some(TextureRef::file(&path.to_string_lossy())))
+        };
+
+        match keyword {
+            "Ka" => material.ambient = color()?,
+            "Kd" => material.diffuse = color()?,
+            "Ks" => material.specular = color()?,
+            "Ke" => material.emissive = color()?,
+            "Ns" => material.shininess = float()?,
+            "d" => material.opacity = float()?,
+            "Tr" => material.opacity = 1. - float()?,
+            "map_Kd" => material.diffuse_map = map()?,
+            "map_Ks" => material.specular_map = map()?,
+            "map_Ke" => material.emissive_map = map()?,
+            "map_d" => material.opacity_map = map()?,
+            "map_Bump" | "map_bump" | "bump" | "norm" => material.normal_map = map()?,
+            _ => {}
+        }
+    }
+
+    for material in materials.iter_mut() {
+        if material.opacity < 1. || material.opacity_map.is_some() {
+            material.alpha_mode = AlphaMode::Blend;
+        }
+    }
+    Ok(materials)
+}
+
+/// The first word of `line` and the trimmed rest, `None` for blank lines.
+pub fn split_keyword(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    match line.split_once(char::is_whitespace) {
+        Some((keyword, rest)) => Some((keyword, rest.trim())),
+        None => Some((line, "")),
+    }
+}
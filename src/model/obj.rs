@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use nalgebra::{Point2, Point3, Vector3};
+
+use super::{mtl::split_keyword, Model, ModelError, ModelMaterial, ModelMesh, ModelNode};
+use crate::mesh::{MeshData, MeshVertex, Submesh};
+
+/// Where the normal of a vertex comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum NormalSource {
+    /// A `vn` index.
+    Given(usize),
+    /// Averaged over the faces of a smoothing group sharing the position.
+    Smooth(u32),
+    /// The normal of a face outside any smoothing group.
+    Flat(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct VertexKey {
+    position: usize,
+    uv: Option<usize>,
+    normal: NormalSource,
+}
+
+/// The mesh being filled since the last `o` or `g`.
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    data: MeshData,
+    submeshes: Vec<Submesh>,
+    lookup: HashMap<VertexKey, u32>,
+    /// Summed face normals per position and smoothing group.
+    smooth: HashMap<(usize, u32), Vector3<f32>>,
+}
+
+impl MeshBuilder {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn finish(mut self) -> Option<ModelMesh> {
+        if self.data.indices.is_empty() {
+            return None;
+        }
+
+        for (key, &index) in self.lookup.iter() {
+            if let NormalSource::Smooth(group) = key.normal {
+                let normal = self.smooth[&(key.position, group)];
+                self.data.vertices[index as usize].normal = unit_or_up(normal).into();
+            }
+        }
+        self.data.compute_tangents();
+
+        Some(ModelMesh {
+            name: self.name,
+            data: self.data,
+            submeshes: self.submeshes,
+        })
+    }
+}
+
+struct Parser<F> {
+    load_library: F,
+    positions: Vec<Point3<f32>>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<Vector3<f32>>,
+    materials: Vec<ModelMaterial>,
+    material: Option<usize>,
+    smoothing: u32,
+    faces: usize,
+    mesh: MeshBuilder,
+    meshes: Vec<ModelMesh>,
+}
+
+/// Parse a Wavefront OBJ file, reading material libraries through
+/// `load_library`.
+///
+/// Polygons are triangulated by ear clipping. Faces without normals get the
+/// average of the faces in their smoothing group, or their own when they are
+/// in none. Objects and groups become separate meshes, `usemtl` starts a new
+/// submesh.
+pub fn parse<F>(file: &str, source: &str, load_library: F) -> Result<Model, ModelError>
+where
+    F: FnMut(&str) -> Result<Vec<ModelMaterial>, ModelError>,
+{
+    let mut parser = Parser {
+        load_library,
+        positions: vec![],
+        uvs: vec![],
+        normals: vec![],
+        materials: vec![],
+        material: None,
+        smoothing: 0,
+        faces: 0,
+        mesh: MeshBuilder::new(""),
+        meshes: vec![],
+    };
+
+    for (index, line) in source.lines().enumerate() {
+        parser.line(line).map_err(|message| ModelError::Parse {
+            file: file.to_string(),
+            line: index + 1,
+            message,
+        })?;
+    }
+
+    parser.start_mesh("");
+
+    // Every object at the top level, placed where its vertices are.
+    let nodes: Vec<_> = parser
+        .meshes
+        .iter()
+        .enumerate()
+        .map(|(index, mesh)| ModelNode {
+            mesh: Some(index),
+            ..ModelNode::new(&mesh.name)
+        })
+        .collect();
+    Ok(Model {
+        roots: (0..nodes.len()).collect(),
+        nodes,
+        meshes: parser.meshes,
+        materials: parser.materials,
+        ..Model::default()
+    })
+}
+
+impl<F> Parser<F>
+where
+    F: FnMut(&str) -> Result<Vec<ModelMaterial>, ModelError>,
+{
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.split('#').next().unwrap();
+        let Some((keyword, rest)) = split_keyword(line) else {
+            return Ok(());
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = floats(rest.split_whitespace())?;
+                self.positions.push(Point3::new(x, y, z));
+            }
+            "vt" => {
+                // The optional third coordinate is for 3D textures.
+                let [u, v] = floats(rest.split_whitespace().take(2))?;
+                self.uvs.push([u, v]);
+            }
+            "vn" => {
+                let [x, y, z] = floats(rest.split_whitespace())?;
+                self.normals.push(Vector3::new(x, y, z));
+            }
+            "f" => self.face(rest)?,
+            "o" | "g" => self.start_mesh(rest),
+            "s" => {
+                self.smoothing = match rest {
+                    "off" => 0,
+                    _ => rest
+                        .parse()
+                        .map_err(|_| format!("'{}' is not a smoothing group", rest))?,
+                }
+            }
+            "usemtl" => self.material = Some(self.material_index(rest)),
+            "mtllib" => {
+                for library in rest.split_whitespace() {
+                    match (self.load_library)(library) {
+                        Ok(materials) => self.materials.extend(materials),
+                        Err(e) => eprintln!("{}, using default materials.", e),
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Finish the current mesh and start one called `name`.
+    fn start_mesh(&mut self, name: &str) {
+        let mesh = std::mem::replace(&mut self.mesh, MeshBuilder::new(name));
+        self.meshes.extend(mesh.finish());
+    }
+
+    /// The material called `name`, added with default properties if no
+    /// library defines it.
+    fn material_index(&mut self, name: &str) -> usize {
+        match self.materials.iter().position(|m| m.name == name) {
+            Some(index) => index,
+            None => {
+                self.materials.push(ModelMaterial::new(name));
+                self.materials.len() - 1
+            }
+        }
+    }
+
+    fn face(&mut self, rest: &str) -> Result<(), String> {
+        let corners = rest
+            .split_whitespace()
+            .map(|corner| self.corner(corner))
+            .collect::<Result<Vec<_>, _>>()?;
+        if corners.len() < 3 {
+            return Err(format!("a face needs 3 vertices, not {}", corners.len()));
+        }
+
+        let points: Vec<_> = corners.iter().map(|c| self.positions[c.0]).collect();
+        let normal = newell_normal(&points);
+        let face = self.faces;
+        self.faces += 1;
+
+        let material = match self.material {
+            Some(material) => material,
+            None => self.material_index("default"),
+        };
+        let mesh = &mut self.mesh;
+        if mesh.submeshes.last().map(|s| s.material) != Some(material) {
+            let start = mesh.data.indices.len() as u32;
+            mesh.submeshes.push(Submesh {
+                range: start..start,
+                material,
+            });
+        }
+
+        let mut indices = Vec::with_capacity(corners.len());
+        for &(position, uv, given) in corners.iter() {
+            let source = match given {
+                Some(normal) => NormalSource::Given(normal),
+                None if self.smoothing != 0 => NormalSource::Smooth(self.smoothing),
+                None => NormalSource::Flat(face),
+            };
+            if let NormalSource::Smooth(group) = source {
+                *mesh
+                    .smooth
+                    .entry((position, group))
+                    .or_insert_with(Vector3::zeros) += normal;
+            }
+
+            let key = VertexKey {
+                position,
+                uv,
+                normal: source,
+            };
+            let index = *mesh.lookup.entry(key).or_insert_with(|| {
+                let normal = match source {
+                    NormalSource::Given(i) => unit_or_up(self.normals[i]),
+                    // Smooth normals are filled in when the mesh is done.
+                    _ => unit_or_up(normal),
+                };
+                mesh.data.vertices.push(MeshVertex {
+                    position: self.positions[position].into(),
+                    uv: uv.map_or([0., 0.], |i| self.uvs[i]),
+                    normal: normal.into(),
+                    tangent: [0.; 4],
+                });
+                mesh.data.vertices.len() as u32 - 1
+            });
+            indices.push(index);
+        }
+
+        for [a, b, c] in triangulate(&points, &normal) {
+            mesh.data
+                .indices
+                .extend([indices[a], indices[b], indices[c]]);
+        }
+        mesh.submeshes.last_mut().unwrap().range.end = mesh.data.indices.len() as u32;
+        Ok(())
+    }
+
+    /// Position, UV and normal indices of a `v/vt/vn` corner, zero based.
+    fn corner(&self, corner: &str) -> Result<(usize, Option<usize>, Option<usize>), String> {
+        let mut parts = corner.split('/');
+        let position = parts.next().unwrap_or("");
+        let uv = parts.next().filter(|s| !s.is_empty());
+        let normal = parts.next().filter(|s| !s.is_empty());
+
+        Ok((
+            resolve(position, self.positions.len())?,
+            uv.map(|uv| resolve(uv, self.uvs.len())).transpose()?,
+            normal
+                .map(|normal| resolve(normal, self.normals.len()))
+                .transpose()?,
+        ))
+    }
+}
+
+/// Turn a one based, or negative and relative, OBJ index into a zero based one.
+fn resolve(index: &str, len: usize) -> Result<usize, String> {
+    let parsed: i64 = index
+        .parse()
+        .map_err(|_| format!("'{}' is not an index", index))?;
+    let resolved = if parsed < 0 {
+        len as i64 + parsed
+    } else {
+        parsed - 1
+    };
+
+    if (0..len as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(format!("index {} is out of range", index))
+    }
+}
+
+fn floats<'a, const N: usize>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> Result<[f32; N], String> {
+    let values = values
+        .into_iter()
+        .map(|s| s.parse().map_err(|_| format!("'{}' is not a number", s)))
+        .collect::<Result<Vec<f32>, _>>()?;
+    values
+        .get(..N)
+        .and_then(|values| values.try_into().ok())
+        .ok_or_else(|| format!("expected {} numbers", N))
+}
+
+fn unit_or_up(normal: Vector3<f32>) -> Vector3<f32> {
+    normal
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::y)
+}
+
+/// Normal of a polygon, also right for non planar and concave ones. Its
+/// length is twice the area.
+fn newell_normal(points: &[Point3<f32>]) -> Vector3<f32> {
+    let mut normal = Vector3::zeros();
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        normal += Vector3::new(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    normal
+}
+
+/// Split a counter-clockwise polygon around `normal` into triangles of
+/// corner indices, by ear clipping in the plane the polygon faces most.
+/// Degenerate leftovers are fanned.
+fn triangulate(points: &[Point3<f32>], normal: &Vector3<f32>) -> Vec<[usize; 3]> {
+    if points.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Drop the dominant axis, ordering the other two so the polygon stays
+    // counter-clockwise.
+    let axis = normal.iamax();
+    let (u, v) = match axis {
+        0 => (1, 2),
+        1 => (2, 0),
+        _ => (0, 1),
+    };
+    let (u, v) = if normal[axis] < 0. { (v, u) } else { (u, v) };
+    let flat: Vec<_> = points.iter().map(|p| Point2::new(p[u], p[v])).collect();
+
+    let cross = |a: usize, b: usize, c: usize| (flat[b] - flat[a]).perp(&(flat[c] - flat[a]));
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            cross(a, b, c) > 0.
+                && remaining.iter().all(|&p| {
+                    [a, b, c].contains(&p)
+                        || cross(a, b, p) < 0.
+                        || cross(b, c, p) < 0.
+                        || cross(c, a, p) < 0.
+                })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TextureRef;
+
+    fn parse_str(source: &str) -> Model {
+        Model::parse_obj("test.obj", source, |_| Ok(String::new())).unwrap()
+    }
+
+    fn normal(mesh: &ModelMesh, index: u32) -> Vector3<f32> {
+        Vector3::from(mesh.data.vertices[index as usize].normal)
+    }
+
+    #[test]
+    fn quads_are_triangulated_and_shared_vertices_reused() {
+        let model = parse_str(
+            "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+             vn 0 0 1\n\
+             f 1//1 2//1 3//1 4//1\nf 2//1 5//1 6//1 3//1\n",
+        );
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.data.indices.len(), 12);
+        assert_eq!(mesh.data.vertices.len(), 6);
+        assert_eq!(
+            mesh.submeshes,
+            vec![Submesh {
+                range: 0..12,
+                material: 0
+            }]
+        );
+        assert_eq!(model.materials[0].name, "default");
+    }
+
+    #[test]
+    fn separate_indices_make_distinct_vertices() {
+        // One position with two UVs, as on a texture seam.
+        let model = parse_str(
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvt 0.5 0.5\n\
+             s 1\nf 1/1 2/2 3/3\nf 1/4 2/2 3/3\n",
+        );
+
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.data.vertices.len(), 4);
+        let uvs: Vec<_> = mesh
+            .data
+            .indices
+            .iter()
+            .map(|&i| mesh.data.vertices[i as usize].uv)
+            .collect();
+        assert_eq!(uvs[0], [0., 0.]);
+        assert_eq!(uvs[3], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn missing_normals_follow_smoothing_groups() {
+        // Two faces folded along the edge 2-3.
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 1 0 -1\n";
+
+        let flat = parse_str(&format!("{}s off\nf 1 2 3\nf 2 4 3\n", source));
+        let mesh = &flat.meshes[0];
+        assert_eq!(mesh.data.vertices.len(), 6);
+        assert!((normal(mesh, 0) - Vector3::z()).norm() < 1e-6);
+        assert!((normal(mesh, 3) - Vector3::x()).norm() < 1e-6);
+
+        let smooth = parse_str(&format!("{}s 1\nf 1 2 3\nf 2 4 3\n", source));
+        let mesh = &smooth.meshes[0];
+        assert_eq!(mesh.data.vertices.len(), 4);
+        let shared = Vector3::new(1., 0., 1.).normalize();
+        assert!((normal(mesh, 1) - shared).norm() < 1e-6);
+        assert!((normal(mesh, 0) - Vector3::z()).norm() < 1e-6);
+    }
+
+    #[test]
+    fn concave_polygons_stay_inside() {
+        // An L shape, fanning from the first corner would cover the notch.
+        let model =
+            parse_str("v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nf 1 2 3 4 5 6\n");
+
+        let mesh = &model.meshes[0];
+        let positions: Vec<_> = mesh
+            .data
+            .vertices
+            .iter()
+            .map(|v| Point3::from(v.position))
+            .collect();
+        let mut area = 0.;
+        for triangle in mesh.data.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let twice = (b - a).cross(&(c - a)).z;
+            assert!(twice > 0., "triangle {:?} is flipped", triangle);
+            area += twice / 2.;
+        }
+        assert!((area - 3.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn materials_split_objects_into_submeshes() {
+        let model = Model::parse_obj(
+            "test.obj",
+            "mtllib test.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv -1 -1 0\n\
+             o first\nusemtl red\nf 1 2 3\nusemtl blue\nf 1 3 -1\nf -4 -3 -2\n\
+             o second\nusemtl red\nf 1 2 3\n",
+            |library| {
+                assert_eq!(library, "test.mtl");
+                Ok("newmtl blue\nKd 0 0 1\nd 0.5\nmap_Kd -bm 1 tex\\blue.png\n\
+                    newmtl red\nKd 1 0 0\nNs 32\n"
+                    .to_string())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(model.materials.len(), 2);
+        let blue = &model.materials[0];
+        assert_eq!(blue.diffuse, [0., 0., 1.]);
+        assert!(blue.is_transparent());
+        assert_eq!(blue.diffuse_map, Some(TextureRef::file("tex/blue.png")));
+        assert_eq!(model.materials[1].shininess, 32.);
+
+        assert_eq!(model.meshes.len(), 2);
+        assert_eq!(model.roots, vec![0, 1]);
+        assert_eq!(model.nodes[1].mesh, Some(1));
+        assert_eq!(model.meshes[0].name, "first");
+        assert_eq!(
+            model.meshes[0].submeshes,
+            vec![
+                Submesh {
+                    range: 0..3,
+                    material: 1
+                },
+                Submesh {
+                    range: 3..9,
+                    material: 0
+                },
+            ]
+        );
+        assert_eq!(
+            model.meshes[1].submeshes,
+            vec![Submesh {
+                range: 0..3,
+                material: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error =
+            Model::parse_obj("bad.obj", "v 0 0 0\nf 1 2 3\n", |_| Ok(String::new())).unwrap_err();
+        assert_eq!(error.to_string(), "bad.obj line 2: index 2 is out of range");
+    }
+}
@@ -0,0 +1,808 @@
+use crate::{
+    gl_call,
+    renderer::gl::{
+        self,
+        types::{GLbitfield, GLenum, GLuint},
+    },
+};
+use std::cell::{Cell, RefCell};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Zero => gl::ZERO,
+            Self::One => gl::ONE,
+            Self::SrcColor => gl::SRC_COLOR,
+            Self::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            Self::DstColor => gl::DST_COLOR,
+            Self::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            Self::SrcAlpha => gl::SRC_ALPHA,
+            Self::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            Self::DstAlpha => gl::DST_ALPHA,
+            Self::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            Self::ConstantColor => gl::CONSTANT_COLOR,
+            Self::OneMinusConstantColor => gl::ONE_MINUS_CONSTANT_COLOR,
+            Self::ConstantAlpha => gl::CONSTANT_ALPHA,
+            Self::OneMinusConstantAlpha => gl::ONE_MINUS_CONSTANT_ALPHA,
+            Self::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendEquation {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Add => gl::FUNC_ADD,
+            Self::Subtract => gl::FUNC_SUBTRACT,
+            Self::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+            Self::Min => gl::MIN,
+            Self::Max => gl::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    pub color_equation: BlendEquation,
+    pub alpha_equation: BlendEquation,
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl BlendState {
+    /// The same factors for color and alpha, added together.
+    pub fn new(src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            color_equation: BlendEquation::Add,
+            alpha_equation: BlendEquation::Add,
+            src_color: src,
+            dst_color: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+
+    /// Classic transparency, `src * a + dst * (1 - a)`.
+    pub fn alpha() -> Self {
+        Self::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /// For colors already multiplied by their alpha.
+    pub fn premultiplied_alpha() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    pub fn additive() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::One)
+    }
+}
+
+impl Default for BlendState {
+    /// What gl starts out with, `src * 1 + dst * 0`.
+    fn default() -> Self {
+        Self::new(BlendFactor::One, BlendFactor::Zero)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareFunc {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl CompareFunc {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Never => gl::NEVER,
+            Self::Less => gl::LESS,
+            Self::Equal => gl::EQUAL,
+            Self::LessEqual => gl::LEQUAL,
+            Self::Greater => gl::GREATER,
+            Self::NotEqual => gl::NOTEQUAL,
+            Self::GreaterEqual => gl::GEQUAL,
+            Self::Always => gl::ALWAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    pub func: CompareFunc,
+    pub write: bool,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            func: CompareFunc::Less,
+            write: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+impl Face {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Front => gl::FRONT,
+            Self::Back => gl::BACK,
+            Self::FrontAndBack => gl::FRONT_AND_BACK,
+        }
+    }
+}
+
+/// Which vertex order makes a triangle front facing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl Winding {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::CounterClockwise => gl::CCW,
+            Self::Clockwise => gl::CW,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CullState {
+    pub face: Face,
+    pub front: Winding,
+}
+
+impl Default for CullState {
+    fn default() -> Self {
+        Self {
+            face: Face::Back,
+            front: Winding::CounterClockwise,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilOp {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::Keep => gl::KEEP,
+            Self::Zero => gl::ZERO,
+            Self::Replace => gl::REPLACE,
+            Self::Increment => gl::INCR,
+            Self::IncrementWrap => gl::INCR_WRAP,
+            Self::Decrement => gl::DECR,
+            Self::DecrementWrap => gl::DECR_WRAP,
+            Self::Invert => gl::INVERT,
+        }
+    }
+}
+
+/// Stencil test and update, for both faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilState {
+    pub func: CompareFunc,
+    pub reference: i32,
+    pub read_mask: GLuint,
+    pub write_mask: GLuint,
+    pub stencil_fail: StencilOp,
+    pub depth_fail: StencilOp,
+    pub pass: StencilOp,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            func: CompareFunc::Always,
+            reference: 0,
+            read_mask: !0,
+            write_mask: !0,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+/// A rectangle in window pixels, from the bottom-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ScissorRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units: f32,
+}
+
+/// Fixed function state for a draw, `None` parts are disabled.
+///
+/// With depth or stencil testing disabled every bit of those buffers is
+/// writable, so clears reach them whatever the last draw wrote.
+///
+/// Descriptions are plain values built once and handed to
+/// [`StateTracker::apply`] before drawing:
+///
+/// ```ignore
+/// let state = RenderState::default()
+///     .with_blend(BlendState::alpha())
+///     .with_depth(DepthState::default());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+    blend: Option<BlendState>,
+    depth: Option<DepthState>,
+    cull: Option<CullState>,
+    stencil: Option<StencilState>,
+    scissor: Option<ScissorRect>,
+    color_mask: [bool; 4],
+    polygon_offset: Option<PolygonOffset>,
+}
+
+impl Default for RenderState {
+    /// Everything disabled and all channels written, like a fresh context.
+    fn default() -> Self {
+        Self {
+            blend: None,
+            depth: None,
+            cull: None,
+            stencil: None,
+            scissor: None,
+            color_mask: [true; 4],
+            polygon_offset: None,
+        }
+    }
+}
+
+impl RenderState {
+    pub fn with_blend(mut self, blend: BlendState) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    pub fn with_depth(mut self, depth: DepthState) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn with_cull(mut self, cull: CullState) -> Self {
+        self.cull = Some(cull);
+        self
+    }
+
+    pub fn with_stencil(mut self, stencil: StencilState) -> Self {
+        self.stencil = Some(stencil);
+        self
+    }
+
+    pub fn with_scissor(mut self, scissor: ScissorRect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    /// Which of red, green, blue and alpha are written.
+    pub fn with_color_mask(mut self, color_mask: [bool; 4]) -> Self {
+        self.color_mask = color_mask;
+        self
+    }
+
+    pub fn with_polygon_offset(mut self, polygon_offset: PolygonOffset) -> Self {
+        self.polygon_offset = Some(polygon_offset);
+        self
+    }
+
+    pub fn blend(&self) -> Option<BlendState> {
+        self.blend
+    }
+
+    pub fn depth(&self) -> Option<DepthState> {
+        self.depth
+    }
+
+    pub fn cull(&self) -> Option<CullState> {
+        self.cull
+    }
+
+    pub fn stencil(&self) -> Option<StencilState> {
+        self.stencil
+    }
+
+    pub fn scissor(&self) -> Option<ScissorRect> {
+        self.scissor
+    }
+
+    pub fn color_mask(&self) -> [bool; 4] {
+        self.color_mask
+    }
+
+    pub fn polygon_offset(&self) -> Option<PolygonOffset> {
+        self.polygon_offset
+    }
+}
+
+/// State changes made and skipped by a [`StateTracker`], one per enable or
+/// parameter group such as the blend function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateStats {
+    pub issued: u64,
+    pub avoided: u64,
+}
+
+/// What the context currently has, `None` where it is unknown. Parameters of
+/// disabled features are remembered too, so re-enabling with the same ones
+/// costs a single call. The depth and stencil write masks also apply to
+/// clears and are tracked apart from their tests.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct GlState {
+    blend_enabled: Option<bool>,
+    blend: Option<BlendState>,
+    depth_enabled: Option<bool>,
+    depth_func: Option<CompareFunc>,
+    depth_write: Option<bool>,
+    cull_enabled: Option<bool>,
+    cull: Option<CullState>,
+    stencil_enabled: Option<bool>,
+    /// With the write mask left at `!0`, see `stencil_write_mask`.
+    stencil: Option<StencilState>,
+    stencil_write_mask: Option<GLuint>,
+    scissor_enabled: Option<bool>,
+    scissor: Option<ScissorRect>,
+    color_mask: Option<[bool; 4]>,
+    polygon_offset_enabled: Option<bool>,
+    polygon_offset: Option<PolygonOffset>,
+}
+
+/// One group of gl calls made by a [`StateTracker`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StateChange {
+    Capability(GLenum, bool),
+    Blend(BlendState),
+    DepthFunc(CompareFunc),
+    DepthMask(bool),
+    Cull(CullState),
+    /// Function and operations, the write mask is a [`StateChange::StencilMask`].
+    Stencil(StencilState),
+    StencilMask(GLuint),
+    Scissor(ScissorRect),
+    ColorMask([bool; 4]),
+    PolygonOffset(PolygonOffset),
+}
+
+impl StateChange {
+    fn issue(&self, gl: &gl::Gl) {
+        match *self {
+            Self::Capability(cap, true) => gl_call!(gl, Enable(cap)),
+            Self::Capability(cap, false) => gl_call!(gl, Disable(cap)),
+            Self::Blend(blend) => {
+                gl_call!(
+                    gl,
+                    BlendEquationSeparate(
+                        blend.color_equation.gl_enum(),
+                        blend.alpha_equation.gl_enum()
+                    )
+                );
+                gl_call!(
+                    gl,
+                    BlendFuncSeparate(
+                        blend.src_color.gl_enum(),
+                        blend.dst_color.gl_enum(),
+                        blend.src_alpha.gl_enum(),
+                        blend.dst_alpha.gl_enum()
+                    )
+                );
+            }
+            Self::DepthFunc(func) => gl_call!(gl, DepthFunc(func.gl_enum())),
+            Self::DepthMask(write) => gl_call!(gl, DepthMask(write as u8)),
+            Self::Cull(cull) => {
+                gl_call!(gl, CullFace(cull.face.gl_enum()));
+                gl_call!(gl, FrontFace(cull.front.gl_enum()));
+            }
+            Self::Stencil(stencil) => {
+                gl_call!(
+                    gl,
+                    StencilFunc(stencil.func.gl_enum(), stencil.reference, stencil.read_mask)
+                );
+                gl_call!(
+                    gl,
+                    StencilOp(
+                        stencil.stencil_fail.gl_enum(),
+                        stencil.depth_fail.gl_enum(),
+                        stencil.pass.gl_enum()
+                    )
+                );
+            }
+            Self::StencilMask(mask) => gl_call!(gl, StencilMask(mask)),
+            Self::Scissor(rect) => {
+                gl_call!(gl, Scissor(rect.x, rect.y, rect.width, rect.height));
+            }
+            Self::ColorMask(mask) => {
+                gl_call!(
+                    gl,
+                    ColorMask(mask[0] as u8, mask[1] as u8, mask[2] as u8, mask[3] as u8)
+                );
+            }
+            Self::PolygonOffset(offset) => {
+                gl_call!(gl, PolygonOffset(offset.factor, offset.units));
+            }
+        }
+    }
+}
+
+/// Shadow of the fixed function state, only issuing the gl calls for what
+/// differs between the last applied [`RenderState`] and the next one.
+///
+/// It assumes nothing else changes that state behind its back, call
+/// [`StateTracker::invalidate`] after code that does.
+#[derive(Default)]
+pub struct StateTracker {
+    current: RefCell<GlState>,
+    issued: Cell<u64>,
+    avoided: Cell<u64>,
+}
+
+impl StateTracker {
+    pub fn apply(&self, gl: &gl::Gl, state: &RenderState) {
+        self.apply_with(state, |change| change.issue(gl));
+    }
+
+    /// Diff `state` against the shadowed one, handing what differs to `issue`.
+    fn apply_with(&self, state: &RenderState, mut issue: impl FnMut(StateChange)) {
+        let mut cur = self.current.borrow_mut();
+        let mut capability = |current: &mut Option<bool>, cap: GLenum, enabled: bool| {
+            self.parameter(
+                current,
+                enabled,
+                StateChange::Capability(cap, enabled),
+                &mut issue,
+            );
+        };
+
+        capability(&mut cur.blend_enabled, gl::BLEND, state.blend.is_some());
+        capability(
+            &mut cur.depth_enabled,
+            gl::DEPTH_TEST,
+            state.depth.is_some(),
+        );
+        capability(&mut cur.cull_enabled, gl::CULL_FACE, state.cull.is_some());
+        capability(
+            &mut cur.stencil_enabled,
+            gl::STENCIL_TEST,
+            state.stencil.is_some(),
+        );
+        capability(
+            &mut cur.scissor_enabled,
+            gl::SCISSOR_TEST,
+            state.scissor.is_some(),
+        );
+        capability(
+            &mut cur.polygon_offset_enabled,
+            gl::POLYGON_OFFSET_FILL,
+            state.polygon_offset.is_some(),
+        );
+
+        // Parameters of disabled features are left as they are.
+        if let Some(blend) = state.blend {
+            self.parameter(&mut cur.blend, blend, StateChange::Blend(blend), &mut issue);
+        }
+        if let Some(depth) = state.depth {
+            let change = StateChange::DepthFunc(depth.func);
+            self.parameter(&mut cur.depth_func, depth.func, change, &mut issue);
+        }
+        // The masks are reset with their tests off for clears to work.
+        let write = state.depth.is_none_or(|depth| depth.write);
+        self.parameter(
+            &mut cur.depth_write,
+            write,
+            StateChange::DepthMask(write),
+            &mut issue,
+        );
+        if let Some(cull) = state.cull {
+            self.parameter(&mut cur.cull, cull, StateChange::Cull(cull), &mut issue);
+        }
+        if let Some(stencil) = state.stencil {
+            let test = StencilState {
+                write_mask: !0,
+                ..stencil
+            };
+            self.parameter(
+                &mut cur.stencil,
+                test,
+                StateChange::Stencil(test),
+                &mut issue,
+            );
+        }
+        let mask = state.stencil.map_or(!0, |stencil| stencil.write_mask);
+        self.parameter(
+            &mut cur.stencil_write_mask,
+            mask,
+            StateChange::StencilMask(mask),
+            &mut issue,
+        );
+        if let Some(rect) = state.scissor {
+            self.parameter(
+                &mut cur.scissor,
+                rect,
+                StateChange::Scissor(rect),
+                &mut issue,
+            );
+        }
+        let mask = state.color_mask;
+        self.parameter(
+            &mut cur.color_mask,
+            mask,
+            StateChange::ColorMask(mask),
+            &mut issue,
+        );
+        if let Some(offset) = state.polygon_offset {
+            let change = StateChange::PolygonOffset(offset);
+            self.parameter(&mut cur.polygon_offset, offset, change, &mut issue);
+        }
+    }
+
+    /// Clear `buffers` of the bound framebuffer, such as
+    /// `gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT`, with every channel and
+    /// bit writable whatever the last applied state masked.
+    pub fn clear(&self, gl: &gl::Gl, buffers: GLbitfield) {
+        self.unmask_with(|change| change.issue(gl));
+        gl_call!(gl, Clear(buffers));
+    }
+
+    /// Hand the changes that make every channel and bit writable to `issue`.
+    fn unmask_with(&self, mut issue: impl FnMut(StateChange)) {
+        let mut cur = self.current.borrow_mut();
+        let all = [true; 4];
+        let change = StateChange::ColorMask(all);
+        self.parameter(&mut cur.color_mask, all, change, &mut issue);
+        let change = StateChange::DepthMask(true);
+        self.parameter(&mut cur.depth_write, true, change, &mut issue);
+        let change = StateChange::StencilMask(!0);
+        self.parameter(&mut cur.stencil_write_mask, !0, change, &mut issue);
+    }
+
+    fn parameter<T: PartialEq>(
+        &self,
+        current: &mut Option<T>,
+        wanted: T,
+        change: StateChange,
+        issue: &mut impl FnMut(StateChange),
+    ) {
+        if current.as_ref() == Some(&wanted) {
+            self.avoided.set(self.avoided.get() + 1);
+            return;
+        }
+
+        issue(change);
+        *current = Some(wanted);
+        self.issued.set(self.issued.get() + 1);
+    }
+
+    /// Forget the shadowed state, the next [`StateTracker::apply`] sets
+    /// everything it uses again.
+    pub fn invalidate(&self) {
+        *self.current.borrow_mut() = GlState::default();
+    }
+
+    pub fn stats(&self) -> StateStats {
+        StateStats {
+            issued: self.issued.get(),
+            avoided: self.avoided.get(),
+        }
+    }
+
+    pub fn reset_stats(&self) {
+        self.issued.set(0);
+        self.avoided.set(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(tracker: &StateTracker, state: &RenderState) -> Vec<StateChange> {
+        let mut changes = vec![];
+        tracker.apply_with(state, |change| changes.push(change));
+        changes
+    }
+
+    #[test]
+    fn unknown_state_is_set_once() {
+        let tracker = StateTracker::default();
+        let state = RenderState::default().with_depth(DepthState::default());
+
+        // Six capabilities, the depth function and the three write masks.
+        assert_eq!(apply(&tracker, &state).len(), 10);
+        assert_eq!(
+            tracker.stats(),
+            StateStats {
+                issued: 10,
+                avoided: 0
+            }
+        );
+
+        assert!(apply(&tracker, &state).is_empty());
+        assert_eq!(
+            tracker.stats(),
+            StateStats {
+                issued: 10,
+                avoided: 10
+            }
+        );
+    }
+
+    #[test]
+    fn only_differences_are_issued() {
+        let tracker = StateTracker::default();
+        let opaque = RenderState::default().with_depth(DepthState::default());
+        let blended = opaque.with_blend(BlendState::alpha());
+        apply(&tracker, &opaque);
+        tracker.reset_stats();
+
+        assert_eq!(
+            apply(&tracker, &blended),
+            [
+                StateChange::Capability(gl::BLEND, true),
+                StateChange::Blend(BlendState::alpha()),
+            ]
+        );
+        // Disabling keeps the blend function, re-enabling reuses it.
+        assert_eq!(
+            apply(&tracker, &opaque),
+            [StateChange::Capability(gl::BLEND, false)]
+        );
+        assert_eq!(
+            apply(&tracker, &blended),
+            [StateChange::Capability(gl::BLEND, true)]
+        );
+        assert_eq!(
+            tracker.stats(),
+            StateStats {
+                issued: 4,
+                avoided: 28
+            }
+        );
+    }
+
+    #[test]
+    fn disabled_parameters_stay_unknown() {
+        let tracker = StateTracker::default();
+        apply(&tracker, &RenderState::default());
+
+        // Nothing set the cull parameters yet, default ones included.
+        let culled = RenderState::default().with_cull(CullState::default());
+        assert_eq!(
+            apply(&tracker, &culled),
+            [
+                StateChange::Capability(gl::CULL_FACE, true),
+                StateChange::Cull(CullState::default()),
+            ]
+        );
+
+        let scissor = ScissorRect::default();
+        let changes = apply(&tracker, &culled.with_scissor(scissor));
+        assert!(changes.contains(&StateChange::Scissor(scissor)));
+    }
+
+    #[test]
+    fn invalidate_forgets_everything() {
+        let tracker = StateTracker::default();
+        let state = RenderState::default().with_cull(CullState::default());
+        apply(&tracker, &state);
+
+        tracker.invalidate();
+        let changes = apply(&tracker, &state);
+        assert_eq!(changes.len(), 10);
+        assert!(changes.contains(&StateChange::Cull(CullState::default())));
+    }
+
+    #[test]
+    fn write_masks_are_restored_with_their_tests_off() {
+        let tracker = StateTracker::default();
+        let read_only = DepthState {
+            write: false,
+            ..DepthState::default()
+        };
+        let stencil = StencilState {
+            write_mask: 0x0f,
+            ..StencilState::default()
+        };
+        let masked = RenderState::default()
+            .with_depth(read_only)
+            .with_stencil(stencil);
+        let changes = apply(&tracker, &masked);
+        assert!(changes.contains(&StateChange::DepthMask(false)));
+        assert!(changes.contains(&StateChange::StencilMask(0x0f)));
+
+        // Clears after this draw reach every bit again.
+        let changes = apply(&tracker, &RenderState::default());
+        assert!(changes.contains(&StateChange::DepthMask(true)));
+        assert!(changes.contains(&StateChange::StencilMask(!0)));
+        assert_eq!(tracker.current.borrow().depth_write, Some(true));
+        assert_eq!(tracker.current.borrow().stencil_write_mask, Some(!0));
+
+        // Testing again only needs the capabilities, the rest is known.
+        let written = RenderState::default()
+            .with_depth(DepthState::default())
+            .with_stencil(StencilState::default());
+        assert_eq!(
+            apply(&tracker, &written),
+            [
+                StateChange::Capability(gl::DEPTH_TEST, true),
+                StateChange::Capability(gl::STENCIL_TEST, true),
+            ]
+        );
+
+        // Clearing with masks still set from a draw unmasks first.
+        apply(
+            &tracker,
+            &masked.with_color_mask([true, false, true, false]),
+        );
+        let mut changes = vec![];
+        tracker.unmask_with(|change| changes.push(change));
+        assert_eq!(
+            changes,
+            [
+                StateChange::ColorMask([true; 4]),
+                StateChange::DepthMask(true),
+                StateChange::StencilMask(!0),
+            ]
+        );
+    }
+}
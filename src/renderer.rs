@@ -1,9 +1,20 @@
 use crate::{
+    camera::Camera,
+    capabilities::Capabilities,
+    clock::FrameClock,
     gl_call,
-    samples::{Sample, SampleProps},
+    input::InputState,
+    render_state::StateTracker,
+    resources::ResourceManager,
+    samples::{Sample, SampleContext, SampleEntry},
+    scene::{self, Scene},
 };
 use glutin::display::GlDisplay;
-use std::ffi::{CStr, CString};
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, CString},
+};
+use winit::event::{DeviceEvent, WindowEvent};
 
 pub mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
@@ -13,11 +24,23 @@ pub mod gl {
 
 pub struct Renderer {
     gl: gl::Gl,
-    sample: SampleProps,
+    capabilities: Capabilities,
+    resources: ResourceManager,
+    state: StateTracker,
+    clock: RefCell<FrameClock>,
+    input: InputState,
+    sample: RefCell<Box<dyn Sample>>,
+    sample_entry: Cell<&'static SampleEntry>,
+    animating: Cell<bool>,
 }
 
 impl Renderer {
-    pub fn new<T: GlDisplay>(display: &T, sample: Sample) -> Self {
+    pub fn new<T: GlDisplay>(
+        display: &T,
+        sample_entry: &'static SampleEntry,
+        clock: FrameClock,
+        input: InputState,
+    ) -> Self {
         let gl = gl::Gl::load_with(|symbol| {
             let symbol = CString::new(symbol).unwrap();
             display.get_proc_address(symbol.as_c_str()).cast()
@@ -34,31 +57,164 @@ impl Renderer {
             println!("Shaders version on {}", shaders_version.to_string_lossy());
         }
 
-        let sample = unsafe { sample.create(&gl) };
+        let capabilities = Capabilities::query(&gl);
+
+        // The viewport starts out covering the whole window.
+        let mut viewport = [0; 4];
+        unsafe { gl.GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()) };
+        let surface_size = (viewport[2] as u32, viewport[3] as u32);
+
+        let resources = ResourceManager::new(&gl, capabilities.clone(), surface_size);
+
+        let state = StateTracker::default();
+
+        let sample = (sample_entry.create)(&SampleContext {
+            gl: &gl,
+            resources: &resources,
+            state: &state,
+            time: clock.time(),
+            input: &input,
+        });
 
-        Self { gl, sample }
+        Self {
+            gl,
+            capabilities,
+            resources,
+            state,
+            clock: RefCell::new(clock),
+            input,
+            sample: RefCell::new(sample),
+            sample_entry: Cell::new(sample_entry),
+            animating: Cell::new(true),
+        }
+    }
+
+    fn context(&self) -> SampleContext<'_> {
+        SampleContext {
+            gl: &self.gl,
+            resources: &self.resources,
+            state: &self.state,
+            time: self.clock.borrow().time(),
+            input: &self.input,
+        }
     }
 
+    /// Advance time by one frame, update the sample and render it.
     pub fn draw(&self) {
-        self.sample.draw(&self.gl);
+        self.resources.streamer().update(&self.gl);
+
+        let mut sample = self.sample.borrow_mut();
+        self.clock.borrow_mut().tick();
+        loop {
+            let mut clock = self.clock.borrow_mut();
+            if !clock.step_fixed() {
+                break;
+            }
+            let step = clock.fixed_step() as f32;
+            drop(clock);
+
+            sample.fixed_update(&self.context(), step);
+        }
+
+        let ctx = self.context();
+        self.animating.set(sample.update(&ctx));
+        sample.render(&ctx);
+
+        self.input.end_frame();
+        self.resources.collect(&self.gl);
     }
 
+    /// Returns `true` when a redraw is needed, either for the sample's animation,
+    /// to let it see pending input or to keep streaming textures in.
     pub fn snapshot(&self) -> bool {
-        self.sample.snapshot(&self.gl)
+        self.animating.get() || self.input.is_active() || self.resources.streamer().is_busy()
+    }
+
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// The time source driving [`Renderer::draw`], e.g. to pause it.
+    pub fn clock(&self) -> &RefCell<FrameClock> {
+        &self.clock
+    }
+
+    /// Forward a window event to the sample.
+    pub fn event(&self, event: &WindowEvent) {
+        self.input.handle_event(event);
+        self.sample.borrow_mut().event(&self.context(), event);
+    }
+
+    /// Draw `scene` as seen by `camera` into the bound framebuffer.
+    pub fn render_scene(&self, scene: &Scene, camera: &Camera) {
+        scene::render_scene(&self.gl, &self.resources, &self.state, scene, camera);
+    }
+
+    /// Raw device input, such as mouse motion while the cursor is grabbed.
+    pub fn device_event(&self, event: &DeviceEvent) {
+        self.input.handle_device_event(event);
+    }
+
+    /// Tear the running sample down and start `entry` in its place.
+    pub fn switch_sample(&self, entry: &'static SampleEntry) {
+        let ctx = self.context();
+        let mut sample = self.sample.borrow_mut();
+        sample.destroy(&ctx);
+        self.input.set_cursor_grab(false);
+
+        // Resources the old sample shares with the new one are picked up
+        // again before they are collected.
+        let old = std::mem::replace(&mut *sample, (entry.create)(&ctx));
+        drop(old);
+        self.sample_entry.set(entry);
+
+        let (width, height) = self.resources.surface_size();
+        sample.resize(&ctx, width, height);
+        self.animating.set(true);
+        self.resources.collect(&self.gl);
+    }
+
+    /// The registry entry of the running sample.
+    pub fn sample_entry(&self) -> &'static SampleEntry {
+        self.sample_entry.get()
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn resources(&self) -> &ResourceManager {
+        &self.resources
+    }
+
+    /// The fixed function state cache, see [`StateTracker::stats`] for how
+    /// many changes it saved.
+    pub fn state(&self) -> &StateTracker {
+        &self.state
     }
 
     pub fn resize(&self, width: i32, height: i32) {
         unsafe {
             self.gl.Viewport(0, 0, width, height);
         }
+        let (width, height) = (width.max(0) as u32, height.max(0) as u32);
+        self.resources.resize(&self.gl, width, height);
+        self.sample
+            .borrow_mut()
+            .resize(&self.context(), width, height);
     }
 }
 
 impl Drop for Renderer {
     fn drop(&mut self) {
-        unsafe {
-            self.sample.drop(&self.gl);
-        }
+        self.sample.get_mut().destroy(&SampleContext {
+            gl: &self.gl,
+            resources: &self.resources,
+            state: &self.state,
+            time: self.clock.get_mut().time(),
+            input: &self.input,
+        });
+        self.resources.drop(&self.gl);
     }
 }
 
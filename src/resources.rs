@@ -0,0 +1,531 @@
+use crate::{
+    buffer::Buffer,
+    capabilities::Capabilities,
+    framebuffer::{Framebuffer, FramebufferDesc, FramebufferError},
+    mesh::Mesh,
+    renderer::gl,
+    shaders::{Shader, ShaderPrelude},
+    texture::{
+        SamplerCache, StreamedTexture, Texture, TextureError, TextureOptions, TextureStreamer,
+        DEFAULT_UPLOAD_BUDGET,
+    },
+};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SlotId {
+    index: u32,
+    generation: u32,
+}
+
+/// What a resource was loaded from, so the same load can be shared.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ResourceKey {
+    Texture(String, TextureOptions),
+    Shader(String, String, ShaderPrelude),
+    Streamed(String),
+}
+
+/// Shared part of all clones of one handle, queues the slot for release when
+/// the last clone goes away.
+struct HandleRef {
+    id: SlotId,
+    released: Weak<RefCell<Vec<SlotId>>>,
+}
+
+impl Drop for HandleRef {
+    fn drop(&mut self) {
+        if let Some(released) = self.released.upgrade() {
+            released.borrow_mut().push(self.id);
+        }
+    }
+}
+
+/// Ref-counted, generational handle to a gl object owned by the [`ResourceManager`].
+///
+/// Cloning a handle adds a reference. When the last one drops the object is
+/// deleted at the next [`ResourceManager::collect`], which the `Renderer` runs
+/// after every frame on the gl thread.
+pub struct Handle<T> {
+    inner: Rc<HandleRef>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    /// Number of live clones of this handle.
+    pub fn ref_count(&self) -> usize {
+        Rc::strong_count(&self.inner)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Handle<{}>({}v{})",
+            std::any::type_name::<T>()
+                .rsplit("::")
+                .next()
+                .unwrap_or_default(),
+            self.inner.id.index,
+            self.inner.id.generation
+        )
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+    handle: Weak<HandleRef>,
+    key: Option<ResourceKey>,
+}
+
+/// Storage of one resource type inside the [`ResourceManager`].
+pub struct Pool<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    keys: HashMap<ResourceKey, u32>,
+    released: Rc<RefCell<Vec<SlotId>>>,
+}
+
+impl<T: Resource> Pool<T> {
+    fn new() -> Self {
+        Self {
+            slots: vec![],
+            free: vec![],
+            keys: HashMap::new(),
+            released: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    fn insert(&mut self, value: T, key: Option<ResourceKey>) -> Handle<T> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: None,
+                    handle: Weak::new(),
+                    key: None,
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+
+        if let Some(key) = key.clone() {
+            self.keys.insert(key, index);
+        }
+
+        let slot = &mut self.slots[index as usize];
+        slot.value = Some(value);
+        slot.key = key;
+        self.handle_for(index)
+    }
+
+    /// A handle to a live slot, reusing the existing `HandleRef` if there is one.
+    fn handle_for(&mut self, index: u32) -> Handle<T> {
+        let slot = &mut self.slots[index as usize];
+
+        let inner = slot.handle.upgrade().unwrap_or_else(|| {
+            // All previous handles are gone but the slot was not collected
+            // yet, it is simply revived.
+            let inner = Rc::new(HandleRef {
+                id: SlotId {
+                    index,
+                    generation: slot.generation,
+                },
+                released: Rc::downgrade(&self.released),
+            });
+            slot.handle = Rc::downgrade(&inner);
+            inner
+        });
+
+        Handle {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    fn find(&mut self, key: &ResourceKey) -> Option<Handle<T>> {
+        let index = *self.keys.get(key)?;
+        Some(self.handle_for(index))
+    }
+
+    fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        let id = handle.inner.id;
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    fn collect(&mut self, gl: &gl::Gl) -> usize {
+        let released = std::mem::take(&mut *self.released.borrow_mut());
+
+        let mut count = 0;
+        for id in released {
+            let Some(slot) = self.slots.get_mut(id.index as usize) else {
+                continue;
+            };
+            // Skip slots that were revived or already reused.
+            if slot.generation != id.generation || slot.handle.upgrade().is_some() {
+                continue;
+            }
+
+            if let Some(value) = slot.value.take() {
+                value.delete(gl);
+                count += 1;
+            }
+            if let Some(key) = slot.key.take() {
+                self.keys.remove(&key);
+            }
+            slot.generation += 1;
+            self.free.push(id.index);
+        }
+
+        count
+    }
+
+    fn clear(&mut self, gl: &gl::Gl) {
+        for slot in self.slots.iter_mut() {
+            if let Some(value) = slot.value.take() {
+                value.delete(gl);
+            }
+            slot.key = None;
+            slot.generation += 1;
+        }
+        self.keys.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.value.is_some())
+            .count()
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+/// A gl object type that can live in the [`ResourceManager`].
+pub trait Resource: Sized + 'static {
+    fn delete(&self, gl: &gl::Gl);
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>>;
+}
+
+impl Resource for Texture {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.textures
+    }
+}
+
+impl Resource for Shader {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.shaders
+    }
+}
+
+impl Resource for Buffer {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.buffers
+    }
+}
+
+impl Resource for Framebuffer {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.framebuffers
+    }
+}
+
+impl Resource for Mesh {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.meshes
+    }
+}
+
+impl Resource for StreamedTexture {
+    fn delete(&self, gl: &gl::Gl) {
+        self.drop(gl);
+    }
+
+    fn pool(resources: &ResourceManager) -> &RefCell<Pool<Self>> {
+        &resources.streamed
+    }
+}
+
+/// Owner of every gl object used by the samples.
+///
+/// Loads are deduplicated by their source and options, everything is handed
+/// out through [`Handle`]s and deleted on the gl thread, either by
+/// [`ResourceManager::collect`] once unreferenced or all at once by
+/// [`ResourceManager::drop`] when the `Renderer` goes away.
+pub struct ResourceManager {
+    textures: RefCell<Pool<Texture>>,
+    shaders: RefCell<Pool<Shader>>,
+    buffers: RefCell<Pool<Buffer>>,
+    framebuffers: RefCell<Pool<Framebuffer>>,
+    meshes: RefCell<Pool<Mesh>>,
+    streamed: RefCell<Pool<StreamedTexture>>,
+    samplers: SamplerCache,
+    streamer: TextureStreamer,
+    capabilities: Capabilities,
+    surface_size: Cell<(u32, u32)>,
+}
+
+impl ResourceManager {
+    /// `surface_size` is the initial window size, kept up to date by
+    /// [`ResourceManager::resize`].
+    pub fn new(gl: &gl::Gl, capabilities: Capabilities, surface_size: (u32, u32)) -> Self {
+        Self {
+            textures: RefCell::new(Pool::new()),
+            shaders: RefCell::new(Pool::new()),
+            buffers: RefCell::new(Pool::new()),
+            framebuffers: RefCell::new(Pool::new()),
+            meshes: RefCell::new(Pool::new()),
+            streamed: RefCell::new(Pool::new()),
+            samplers: SamplerCache::default(),
+            streamer: TextureStreamer::new(gl, DEFAULT_UPLOAD_BUDGET),
+            capabilities,
+            surface_size: Cell::new(surface_size),
+        }
+    }
+
+    /// Load a texture from `res/textures`, or share the one already loaded
+    /// with the same options.
+    pub fn load_texture(
+        &self,
+        gl: &gl::Gl,
+        name: &str,
+        options: &TextureOptions,
+    ) -> Handle<Texture> {
+        let key = ResourceKey::Texture(name.to_string(), *options);
+        let mut pool = self.textures.borrow_mut();
+        if let Some(handle) = pool.find(&key) {
+            return handle;
+        }
+
+        let texture = Texture::load_with(gl, &self.capabilities, name, options);
+        pool.insert(texture, Some(key))
+    }
+
+    /// Like [`ResourceManager::load_texture`], returning missing and broken
+    /// files as errors.
+    pub fn try_load_texture(
+        &self,
+        gl: &gl::Gl,
+        name: &str,
+        options: &TextureOptions,
+    ) -> Result<Handle<Texture>, TextureError> {
+        let key = ResourceKey::Texture(name.to_string(), *options);
+        let mut pool = self.textures.borrow_mut();
+        if let Some(handle) = pool.find(&key) {
+            return Ok(handle);
+        }
+
+        let texture = Texture::try_load_with(gl, &self.capabilities, name, options)?;
+        Ok(pool.insert(texture, Some(key)))
+    }
+
+    /// Decode an image held in memory, or share the one already decoded
+    /// under the same `name` and options. Names should not collide with
+    /// files in `res/textures`, embedded images use `model#image`.
+    pub fn load_texture_bytes(
+        &self,
+        gl: &gl::Gl,
+        name: &str,
+        bytes: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Handle<Texture>, TextureError> {
+        let key = ResourceKey::Texture(name.to_string(), *options);
+        let mut pool = self.textures.borrow_mut();
+        if let Some(handle) = pool.find(&key) {
+            return Ok(handle);
+        }
+
+        Ok(pool.insert(Texture::decode(gl, bytes, options)?, Some(key)))
+    }
+
+    /// Stream a texture from `res/textures` in the background, see
+    /// [`TextureStreamer::load`], or share the one already streaming.
+    pub fn stream_texture(&self, name: &str) -> Handle<StreamedTexture> {
+        let key = ResourceKey::Streamed(name.to_string());
+        let mut pool = self.streamed.borrow_mut();
+        if let Some(handle) = pool.find(&key) {
+            return handle;
+        }
+
+        pool.insert(self.streamer.load(name), Some(key))
+    }
+
+    /// Load a shader program from `res/shaders`, or share the one already
+    /// linked from the same sources.
+    pub fn load_shader(
+        &self,
+        gl: &gl::Gl,
+        vertex_name: &str,
+        fragment_name: &str,
+    ) -> Handle<Shader> {
+        self.load_shader_with_prelude(gl, vertex_name, fragment_name, &ShaderPrelude::default())
+    }
+
+    /// Like [`ResourceManager::load_shader`], see [`Shader::load_with_prelude`].
+    /// Programs are only shared between loads with the same prelude.
+    pub fn load_shader_with_prelude(
+        &self,
+        gl: &gl::Gl,
+        vertex_name: &str,
+        fragment_name: &str,
+        prelude: &ShaderPrelude,
+    ) -> Handle<Shader> {
+        let key = ResourceKey::Shader(
+            vertex_name.to_string(),
+            fragment_name.to_string(),
+            prelude.clone(),
+        );
+        let mut pool = self.shaders.borrow_mut();
+        if let Some(handle) = pool.find(&key) {
+            return handle;
+        }
+
+        let shader = Shader::load_with_prelude(gl, vertex_name, fragment_name, prelude);
+        pool.insert(shader, Some(key))
+    }
+
+    /// Create a framebuffer, those of [`crate::framebuffer::FramebufferSize::Surface`]
+    /// are resized along with the window.
+    pub fn create_framebuffer(
+        &self,
+        gl: &gl::Gl,
+        desc: FramebufferDesc,
+    ) -> Result<Handle<Framebuffer>, FramebufferError> {
+        let framebuffer = Framebuffer::new(gl, &self.capabilities, desc, self.surface_size())?;
+        Ok(self.insert(framebuffer))
+    }
+
+    /// Follow a new window size, reallocating surface sized framebuffers.
+    pub fn resize(&self, gl: &gl::Gl, width: u32, height: u32) {
+        self.surface_size.set((width, height));
+
+        for framebuffer in self.framebuffers.borrow_mut().values_mut() {
+            if framebuffer.follows_surface() {
+                if let Err(e) = framebuffer.resize(gl, &self.capabilities, width, height) {
+                    eprintln!("Resizing framebuffer {} failed: {}", framebuffer.id(), e);
+                }
+            }
+        }
+    }
+
+    /// Current window size in pixels.
+    pub fn surface_size(&self) -> (u32, u32) {
+        self.surface_size.get()
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Take ownership of an object created elsewhere.
+    pub fn insert<T: Resource>(&self, value: T) -> Handle<T> {
+        T::pool(self).borrow_mut().insert(value, None)
+    }
+
+    /// Borrow the object behind `handle`.
+    ///
+    /// Panics if the manager was already torn down.
+    pub fn get<T: Resource>(&self, handle: &Handle<T>) -> Ref<'_, T> {
+        self.try_get(handle)
+            .unwrap_or_else(|| panic!("Stale resource handle {:?}.", handle))
+    }
+
+    pub fn try_get<T: Resource>(&self, handle: &Handle<T>) -> Option<Ref<'_, T>> {
+        Ref::filter_map(T::pool(self).borrow(), |pool| pool.get(handle)).ok()
+    }
+
+    /// Number of live objects of type `T`.
+    pub fn count<T: Resource>(&self) -> usize {
+        T::pool(self).borrow().len()
+    }
+
+    pub fn samplers(&self) -> &SamplerCache {
+        &self.samplers
+    }
+
+    pub fn streamer(&self) -> &TextureStreamer {
+        &self.streamer
+    }
+
+    /// Delete every object whose last handle was dropped, returns how many.
+    pub fn collect(&self, gl: &gl::Gl) -> usize {
+        self.textures.borrow_mut().collect(gl)
+            + self.shaders.borrow_mut().collect(gl)
+            + self.buffers.borrow_mut().collect(gl)
+            + self.framebuffers.borrow_mut().collect(gl)
+            + self.meshes.borrow_mut().collect(gl)
+            + self.streamed.borrow_mut().collect(gl)
+    }
+
+    /// Delete everything, whether handles are still alive or not.
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.textures.borrow_mut().clear(gl);
+        self.shaders.borrow_mut().clear(gl);
+        self.buffers.borrow_mut().clear(gl);
+        self.framebuffers.borrow_mut().clear(gl);
+        self.meshes.borrow_mut().clear(gl);
+        self.streamed.borrow_mut().clear(gl);
+        self.samplers.drop(gl);
+        self.streamer.drop(gl);
+    }
+}
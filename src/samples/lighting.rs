@@ -0,0 +1,260 @@
+use super::{Sample, SampleContext, SampleEntry};
+use crate::{
+    camera::{Camera, CameraController, OrbitController},
+    gl_call,
+    lighting::{BlinnPhongMaterial, LightBuffer},
+    mesh::shapes,
+    render_state::{CullState, DepthState, RenderState},
+    scene::{Attenuation, Light, LightKind, Material, Node, NodeId, Scene},
+    texture::{SamplerDesc, Texture, TextureOptions, Wrap},
+    transform::Transform,
+};
+use image::{DynamicImage, Rgba, RgbaImage};
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+pub const ENTRY: SampleEntry = SampleEntry::new::<Lighting>("lighting").with_order(60);
+
+/// Light that reaches every surface, however it faces.
+const AMBIENT: [f32; 3] = [0.03, 0.03, 0.04];
+
+/// How fast the lamp moves, in units per second.
+const LAMP_SPEED: f32 = 2.;
+const LAMP_COLOR: [f32; 3] = [1., 0.85, 0.6];
+
+/// Tiles across the floor and texels across each tile.
+const TILES: u32 = 8;
+const TILE_SIZE: u32 = 32;
+/// Fractions of a tile taken by the grout and the bevel inside it.
+const GROUT: f32 = 0.04;
+const BEVEL: f32 = 0.12;
+
+/// Floor maps of beveled tiles: the diffuse colors, shiny tiles in matte
+/// grout and the normals tilting up the bevels.
+fn tile_maps() -> [RgbaImage; 3] {
+    let size = TILES * TILE_SIZE;
+    let mut diffuse = RgbaImage::new(size, size);
+    let mut specular = RgbaImage::new(size, size);
+    let mut normal = RgbaImage::new(size, size);
+
+    for (x, y, texel) in diffuse.enumerate_pixels_mut() {
+        let (tile_x, tile_y) = (x / TILE_SIZE, y / TILE_SIZE);
+        let local = |t: u32| ((t % TILE_SIZE) as f32 + 0.5) / TILE_SIZE as f32;
+        let (u, v) = (local(x), local(y));
+
+        // Distance into the tile from its nearest edge on either axis.
+        let edge_u = u.min(1. - u);
+        let edge_v = v.min(1. - v);
+        if edge_u.min(edge_v) < GROUT {
+            *texel = Rgba([90, 88, 84, 255]);
+            specular.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            normal.put_pixel(x, y, Rgba([128, 128, 255, 255]));
+            continue;
+        }
+
+        *texel = if (tile_x + tile_y) % 2 == 0 {
+            Rgba([200, 196, 186, 255])
+        } else {
+            Rgba([60, 90, 120, 255])
+        };
+        specular.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+
+        let slope = |t: f32, edge: f32| {
+            if edge < BEVEL {
+                (1. - (edge - GROUT) / (BEVEL - GROUT)) * if t < 0.5 { -1. } else { 1. }
+            } else {
+                0.
+            }
+        };
+        let tilt = Vector3::new(slope(u, edge_u), slope(v, edge_v), 1.).normalize();
+        let [r, g, b] = tilt.map(|c| ((c * 0.5 + 0.5) * 255.) as u8).into();
+        normal.put_pixel(x, y, Rgba([r, g, b, 255]));
+    }
+
+    [diffuse, specular, normal]
+}
+
+/// A torus on a tiled floor under a directional light, a spot light and a
+/// lamp moved with `light_*` actions. `shading_toggle` switches between
+/// lighting per vertex and per pixel, where the floor's normal map shows.
+pub struct Lighting {
+    lights: LightBuffer,
+    per_vertex: bool,
+
+    scene: Scene,
+    lamp: NodeId,
+    torus: NodeId,
+    /// Nodes drawn with a lit material.
+    lit: Vec<NodeId>,
+
+    camera: Camera,
+    controller: OrbitController,
+}
+
+impl Sample for Lighting {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        let caps = resources.capabilities();
+        let lights = LightBuffer::new(gl, caps);
+        let shader = resources.load_shader_with_prelude(gl, "lit", "lit", &lights.prelude());
+        lights.attach(gl, &resources.get(&shader));
+        let state = RenderState::default()
+            .with_depth(DepthState::default())
+            .with_cull(CullState::default());
+        let lit = |lighting: BlinnPhongMaterial| {
+            Material::new(shader.clone())
+                .with_lighting(lighting)
+                .with_state(state)
+        };
+
+        let options = TextureOptions {
+            sampler: SamplerDesc::trilinear().with_wrap(Wrap::Repeat),
+            mipmaps: true,
+            flip_v: false,
+            srgb: false,
+        };
+        let [diffuse, specular, normal] = tile_maps().map(|map| {
+            let texture = Texture::from_image(gl, DynamicImage::ImageRgba8(map), &options);
+            resources.insert(texture)
+        });
+
+        let mut scene = Scene::new();
+        let floor = scene.add(
+            Node::new("floor").with_mesh(
+                resources.insert(shapes::plane(8., 8., 1, 1).upload(gl, caps)),
+                lit(BlinnPhongMaterial::new([1., 1., 1.])
+                    .with_specular([0.6, 0.6, 0.6], 48.)
+                    .with_diffuse_map(diffuse)
+                    .with_specular_map(specular)
+                    .with_normal_map(normal)),
+            ),
+        );
+        let torus_mesh = resources.insert(shapes::torus(0.8, 0.3, 48, 24).upload(gl, caps));
+        let torus_material =
+            lit(BlinnPhongMaterial::new([0.8, 0.25, 0.15]).with_specular([1., 1., 1.], 96.));
+        let torus = scene.add(
+            Node::new("torus")
+                .with_transform(Transform::from_translation(Vector3::new(0., 1.1, 0.)))
+                .with_mesh(torus_mesh, torus_material),
+        );
+
+        // Lights shine down their node's -Z axis.
+        let facing =
+            |direction: Vector3<f32>| UnitQuaternion::face_towards(&-direction, &Vector3::y());
+        let sun = facing(Vector3::new(-0.4, -1., -0.3));
+        scene.add(
+            Node::new("sun")
+                .with_transform(Transform::from_rotation(sun))
+                .with_light(
+                    Light::new(LightKind::Directional)
+                        .with_color([0.6, 0.65, 0.8])
+                        .with_intensity(0.4),
+                ),
+        );
+        scene.add(
+            Node::new("spot")
+                .with_transform(
+                    Transform::from_translation(Vector3::new(-3., 4., 2.))
+                        .with_rotation(facing(Vector3::new(3., -3., -2.))),
+                )
+                .with_light(
+                    Light::new(LightKind::Spot {
+                        range: 12.,
+                        inner_angle: 0.25,
+                        outer_angle: 0.35,
+                    })
+                    .with_color([0.5, 0.7, 1.])
+                    .with_intensity(2.)
+                    .with_attenuation(Attenuation::NONE),
+                ),
+        );
+        let lamp = scene.add(
+            Node::new("lamp")
+                .with_transform(Transform::from_translation(Vector3::new(1.5, 1.5, 1.5)))
+                .with_light(
+                    Light::new(LightKind::Point { range: 8. })
+                        .with_color(LAMP_COLOR)
+                        .with_intensity(4.)
+                        .with_attenuation(Attenuation::new(1., 0.5, 0.5)),
+                ),
+        );
+        // The lamp's light does not reach the marker, which glows instead.
+        let marker = scene.add_child(
+            lamp,
+            Node::new("marker").with_mesh(
+                resources.insert(shapes::uv_sphere(0.08, 12, 8).upload(gl, caps)),
+                lit(BlinnPhongMaterial::new([0., 0., 0.]).with_emissive(LAMP_COLOR)),
+            ),
+        );
+
+        let mut camera = Camera::perspective(0.8, 0.1, 100.);
+        let (width, height) = resources.surface_size();
+        camera.resize(width, height);
+        let mut controller = OrbitController::new(Point3::new(0., 0.8, 0.), 7.);
+        controller.pitch = 0.45;
+        controller.apply(&mut camera);
+
+        Self {
+            lights,
+            per_vertex: false,
+            scene,
+            lamp,
+            torus,
+            lit: vec![floor, torus, marker],
+            camera,
+            controller,
+        }
+    }
+
+    fn update(&mut self, ctx: &SampleContext) -> bool {
+        let input = ctx.input;
+        let delta = ctx.time.delta;
+
+        let motion = Vector3::new(
+            input.axis("light_left", "light_right"),
+            input.axis("light_down", "light_up"),
+            input.axis("light_forward", "light_back"),
+        );
+        self.scene.update_local(self.lamp, |local| {
+            local.translation += motion * LAMP_SPEED * delta;
+        });
+        if input.action_pressed("shading_toggle") {
+            self.per_vertex = !self.per_vertex;
+            for id in &self.lit {
+                for material in &mut self.scene.node_mut(*id).materials {
+                    if let Some(lighting) = &mut material.lighting {
+                        lighting.per_vertex = self.per_vertex;
+                    }
+                }
+            }
+        }
+
+        let spin = UnitQuaternion::from_euler_angles(0.9, ctx.time.total as f32 * 0.4, 0.);
+        self.scene
+            .update_local(self.torus, |local| local.rotation = spin);
+
+        self.controller.update(&mut self.camera, input, delta);
+
+        true
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let gl = ctx.gl;
+
+        gl_call!(gl, ClearColor(0.02, 0.02, 0.03, 0.9));
+        ctx.state
+            .clear(gl, gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        self.lights.update(gl, AMBIENT, &self.scene.lights());
+        self.lights.bind(gl);
+        ctx.render_scene(&self.scene, &self.camera);
+    }
+
+    fn resize(&mut self, _ctx: &SampleContext, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+
+    fn destroy(&mut self, ctx: &SampleContext) {
+        self.lights.drop(ctx.gl);
+    }
+}
@@ -1,198 +1,115 @@
-mod simple_square;
-mod simple_triangle;
-mod textured_square;
-
-use crate::{gl_call, renderer::gl, shaders::Shader, texture::Texture};
-use std::{cell::Cell, ptr::null};
-
-pub enum Sample {
-    SimpleTriangle,
-    SimpleSquare,
-    TexturedSquare,
+use crate::{
+    camera::Camera,
+    clock::FrameTime,
+    input::InputState,
+    render_state::StateTracker,
+    renderer::gl,
+    resources::ResourceManager,
+    scene::{self, Scene},
+    vertex_array::Vertex,
+};
+use bytemuck::{Pod, Zeroable};
+use std::sync::LazyLock;
+use winit::event::WindowEvent;
+
+// The build script declares every other file in this directory as a sample
+// module, each provides its registry entry as `ENTRY`.
+include!(concat!(env!("OUT_DIR"), "/samples.rs"));
+
+/// Every sample, in the order they are cycled through, see
+/// [`SampleEntry::with_order`].
+pub static SAMPLES: LazyLock<Vec<SampleEntry>> = LazyLock::new(|| {
+    let mut samples = ENTRIES.to_vec();
+    // Stable, so equal orders stay sorted by module name.
+    samples.sort_by_key(|entry| entry.order);
+    samples
+});
+
+/// Everything a sample needs from the `Renderer`.
+pub struct SampleContext<'a> {
+    pub gl: &'a gl::Gl,
+    pub resources: &'a ResourceManager,
+    pub state: &'a StateTracker,
+    /// Timing of the frame being drawn.
+    pub time: FrameTime,
+    /// Input gathered since the previous frame.
+    pub input: &'a InputState,
 }
 
-pub enum SampleProps {
-    SimpleTriangle {
-        shader: Shader,
-        vao: u32,
-        vbo: u32,
-    },
-    SimpleSquare {
-        shader: Shader,
-        vao: u32,
-        vbo: u32,
-        ibo: u32,
-
-        r: Cell<f32>,
-        inc: Cell<f32>,
-    },
-    TexturedSquare {
-        shader: Shader,
-        vao: u32,
-        vbo: u32,
-        ibo: u32,
-        texture: Texture,
-    },
-}
-
-impl Sample {
-    pub unsafe fn create(&self, gl: &gl::Gl) -> SampleProps {
-        gl_call!(gl, Enable(gl::BLEND));
-        gl_call!(gl, BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA));
-
-        match self {
-            Self::SimpleTriangle => simple_triangle::create_sample(gl),
-            Self::SimpleSquare => simple_square::create_sample(gl),
-            Self::TexturedSquare => textured_square::create_sample(gl),
-        }
+impl SampleContext<'_> {
+    /// Draw `scene` as seen by `camera`, see `Renderer::render_scene`.
+    pub fn render_scene(&self, scene: &Scene, camera: &Camera) {
+        scene::render_scene(self.gl, self.resources, self.state, scene, camera);
     }
 }
 
-impl SampleProps {
-    pub fn draw(&self, gl: &gl::Gl) {
-        self.bind(gl);
+/// A self-contained demo.
+///
+/// Objects behind `Handle`s are released by the `ResourceManager`, anything
+/// else has to be deleted in [`Sample::destroy`].
+pub trait Sample {
+    fn create(ctx: &SampleContext) -> Self
+    where
+        Self: Sized;
+
+    /// Advance simulations by exactly `step` seconds, called zero or more
+    /// times per frame before [`Sample::update`].
+    fn fixed_update(&mut self, _ctx: &SampleContext, _step: f32) {}
+
+    /// Advance animations by `ctx.time`, called once per frame before
+    /// [`Sample::render`]. Returns `true` while the next frame will differ.
+    fn update(&mut self, _ctx: &SampleContext) -> bool {
+        false
+    }
 
-        match self {
-            Self::SimpleTriangle { .. } => {
-                gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
-                gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+    fn render(&mut self, ctx: &SampleContext);
 
-                gl_call!(gl, DrawArrays(gl::TRIANGLES, 0, 3));
-            }
+    /// The window was resized to `width`x`height` pixels.
+    fn resize(&mut self, _ctx: &SampleContext, _width: u32, _height: u32) {}
 
-            Self::SimpleSquare { .. } => {
-                gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
-                gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+    fn event(&mut self, _ctx: &SampleContext, _event: &WindowEvent) {}
 
-                gl_call!(gl, DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, null()));
-            }
+    fn destroy(&mut self, ctx: &SampleContext);
+}
 
-            Self::TexturedSquare { .. } => {
-                gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
-                gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+/// A sample as listed in [`SAMPLES`].
+#[derive(Clone, Copy)]
+pub struct SampleEntry {
+    pub name: &'static str,
+    pub create: fn(&SampleContext) -> Box<dyn Sample>,
+    pub order: u32,
+}
 
-                gl_call!(gl, DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, null()));
-            }
+impl SampleEntry {
+    pub const fn new<S: Sample + 'static>(name: &'static str) -> Self {
+        Self {
+            name,
+            create: create_boxed::<S>,
+            order: u32::MAX,
         }
-
-        self.unbind(gl);
     }
 
-    fn bind(&self, gl: &gl::Gl) {
-        match self {
-            Self::SimpleTriangle { shader, vao, .. } => {
-                shader.bind(gl);
-
-                gl_call!(gl, BindVertexArray(*vao));
-            }
-            Self::SimpleSquare {
-                shader, vao, ibo, ..
-            } => {
-                shader.bind(gl);
-
-                gl_call!(gl, BindVertexArray(*vao));
-                gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, *ibo));
-            }
-            Self::TexturedSquare {
-                shader,
-                vao,
-                ibo,
-                texture,
-                ..
-            } => {
-                shader.bind(gl);
-
-                let slot = 0;
-                texture.bind(gl, slot);
-                shader.set_uniform_1i(gl, "u_texture\0", slot);
-
-                gl_call!(gl, BindVertexArray(*vao));
-                gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, *ibo));
-            }
-        }
+    /// Where the sample goes in [`SAMPLES`], lowest first. Samples without
+    /// one come last.
+    pub const fn with_order(mut self, order: u32) -> Self {
+        self.order = order;
+        self
     }
+}
 
-    fn unbind(&self, gl: &gl::Gl) {
-        match self {
-            Self::SimpleTriangle { shader, .. } => {
-                shader.unbind(gl);
-
-                gl_call!(gl, BindVertexArray(0));
-            }
-            Self::SimpleSquare { shader, .. } => {
-                shader.unbind(gl);
-
-                gl_call!(gl, BindVertexArray(0));
-                gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
-            }
-            Self::TexturedSquare {
-                shader, texture, ..
-            } => {
-                shader.unbind(gl);
-                texture.unbind(gl);
-
-                gl_call!(gl, BindVertexArray(0));
-                gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
-            }
-        }
-    }
+fn create_boxed<S: Sample + 'static>(ctx: &SampleContext) -> Box<dyn Sample> {
+    Box::new(S::create(ctx))
+}
 
-    pub fn snapshot(&self, gl: &gl::Gl) -> bool {
-        match self {
-            Self::SimpleSquare { shader, r, inc, .. } => {
-                let rc = r.get();
-                shader.set_uniform_4f(gl, "u_color\0", rc, 0.2, 0.75, 0.9);
-
-                if rc > 1. {
-                    inc.set(-0.05)
-                } else if rc < 0. {
-                    inc.set(0.05)
-                }
-                r.set(rc + inc.get());
-
-                true
-            }
-            _ => false,
-        }
-    }
+/// Look a sample up by its name.
+pub fn find(name: &str) -> Option<&'static SampleEntry> {
+    SAMPLES.iter().find(|entry| entry.name == name)
+}
 
-    pub unsafe fn drop(&mut self, gl: &gl::Gl) {
-        match self {
-            Self::SimpleTriangle { shader, vao, vbo } => {
-                shader.drop(gl);
-
-                gl.DeleteBuffers(1, vbo);
-                gl.DeleteVertexArrays(1, vao);
-            }
-
-            Self::SimpleSquare {
-                shader,
-                vao,
-                vbo,
-                ibo,
-                ..
-            } => {
-                shader.drop(gl);
-
-                gl.DeleteBuffers(1, vbo);
-                gl.DeleteBuffers(1, ibo);
-                gl.DeleteVertexArrays(1, vao);
-            }
-            Self::TexturedSquare {
-                shader,
-                vao,
-                vbo,
-                ibo,
-                texture,
-                ..
-            } => {
-                shader.drop(gl);
-                texture.drop(gl);
-
-                gl.DeleteBuffers(1, vbo);
-                gl.DeleteBuffers(1, ibo);
-                gl.DeleteVertexArrays(1, vao);
-            }
-        }
-    }
+/// A vertex with only a 2d position.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+struct Vertex2D {
+    #[location(0)]
+    pos: [f32; 2],
 }
@@ -0,0 +1,143 @@
+use super::{Sample, SampleContext, SampleEntry};
+use crate::{
+    animation::{AnimationPlayer, Channel, Clip, Interpolation, Property},
+    camera::{Camera, CameraController, OrbitController},
+    gl_call,
+    mesh::{shapes, MeshData, MorphTarget},
+    render_state::{CullState, DepthState, RenderState},
+    scene::{Material, Node, Scene},
+    transform::Transform,
+};
+use nalgebra::{Point3, Vector3};
+
+pub const ENTRY: SampleEntry = SampleEntry::new::<MorphTargets>("morph_targets").with_order(50);
+
+/// How the squash target scales the sphere.
+const SQUASH: Vector3<f32> = Vector3::new(1.25, 0.6, 1.25);
+
+const COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.4, 0.3, 1.],
+    [0.4, 0.8, 0.4, 1.],
+    [0.3, 0.5, 0.9, 1.],
+];
+
+/// Weights of the squash and peak targets over one loop, a second apart.
+const KEYS: [[f32; 2]; 4] = [[0., 0.], [1., 0.], [0., 0.], [0., 1.]];
+
+/// A sphere with two morph targets, squashed flat and with a peak on top.
+fn blob() -> MeshData {
+    let mut data = shapes::uv_sphere(0.6, 32, 24);
+
+    let mut squash = MorphTarget::zeros("squash", 0);
+    let mut peak = MorphTarget::zeros("peak", 0);
+    for vertex in &data.vertices {
+        let position = Vector3::from(vertex.position);
+        let normal = Vector3::from(vertex.normal);
+
+        let squashed = position.component_mul(&SQUASH);
+        squash.positions.push((squashed - position).into());
+        let squashed_normal = normal.component_div(&SQUASH).normalize();
+        squash.normals.push((squashed_normal - normal).into());
+
+        let height = normal.y.max(0.).powi(6);
+        peak.positions.push((normal * 0.5 * height).into());
+    }
+
+    data.morph_targets = vec![squash, peak];
+    data
+}
+
+/// The loop of [`KEYS`] for node `index`, started `offset` keys in.
+fn weights(index: usize, offset: usize) -> Channel {
+    let mut values = vec![];
+    for key in 0..=KEYS.len() {
+        values.extend(KEYS[(key + offset) % KEYS.len()]);
+    }
+    let times = (0..=KEYS.len()).map(|key| key as f32).collect();
+    Channel::new(
+        index,
+        times,
+        Interpolation::Linear,
+        Property::Weights(values),
+    )
+}
+
+/// Three instances of one mesh, each blending between its two morph targets
+/// with weights of its own.
+pub struct MorphTargets {
+    scene: Scene,
+    player: AnimationPlayer,
+    camera: Camera,
+    controller: OrbitController,
+}
+
+impl Sample for MorphTargets {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        let shader = resources.load_shader(gl, "morph", "shaded");
+        let mesh = resources.insert(blob().upload(gl, resources.capabilities()));
+
+        let mut scene = Scene::new();
+        let mut nodes = vec![];
+        for (index, color) in COLORS.into_iter().enumerate() {
+            let material = Material::new(shader.clone()).with_color(color).with_state(
+                RenderState::default()
+                    .with_depth(DepthState::default())
+                    .with_cull(CullState::default()),
+            );
+            let x = (index as f32 - 1.) * 1.6;
+            let node = Node::new(&format!("blob{}", index))
+                .with_transform(Transform::from_translation(Vector3::new(x, 0., 0.)))
+                .with_mesh(mesh.clone(), material)
+                .with_morph_weights(vec![0., 0.]);
+            nodes.push(scene.add(node));
+        }
+
+        let channels = (0..nodes.len())
+            .map(|index| weights(index, index))
+            .collect();
+        let mut player = AnimationPlayer::new(&scene, nodes, vec![Clip::new("morph", channels)]);
+        player.play(0, 0.);
+
+        let mut camera = Camera::perspective(0.8, 0.1, 100.);
+        let (width, height) = resources.surface_size();
+        camera.resize(width, height);
+        let mut controller = OrbitController::new(Point3::origin(), 5.);
+        controller.pitch = 0.3;
+        controller.apply(&mut camera);
+
+        Self {
+            scene,
+            player,
+            camera,
+            controller,
+        }
+    }
+
+    fn update(&mut self, ctx: &SampleContext) -> bool {
+        self.player.update(&mut self.scene, ctx.time.delta);
+        self.controller
+            .update(&mut self.camera, ctx.input, ctx.time.delta);
+
+        true
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let gl = ctx.gl;
+
+        gl_call!(gl, ClearColor(0.1, 0.1, 0.12, 0.9));
+        ctx.state
+            .clear(gl, gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        ctx.render_scene(&self.scene, &self.camera);
+    }
+
+    fn resize(&mut self, _ctx: &SampleContext, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // The mesh and shader are released along with the scene.
+    }
+}
@@ -1,89 +1,109 @@
-use std::cell::Cell;
-
-use super::SampleProps;
+use super::{Sample, SampleContext, SampleEntry, Vertex2D};
 use crate::{
+    animation::{Binding, Curve, Easing, LoopMode, Timeline, Track, UniformCurve},
     gl_call,
-    shaders::Shader,
+    mesh::{Mesh, Topology},
+    render_state::RenderState,
+    resources::Handle,
+    shaders::{MatrixUniforms, Shader},
 };
-use gl::types::{GLsizei, GLsizeiptr};
+
+pub const ENTRY: SampleEntry = SampleEntry::new::<SimpleSquare>("simple_square").with_order(20);
 
 #[rustfmt::skip]
-const VERTEX_DATA: [f32; 8] = [
-    -0.5, -0.5,  // 0
-     0.5, -0.5,  // 1
-     0.5,  0.5,  // 2
-    -0.5,  0.5,  // 3
+const VERTICES: [Vertex2D; 4] = [
+    Vertex2D { pos: [-0.5, -0.5] },
+    Vertex2D { pos: [ 0.5, -0.5] },
+    Vertex2D { pos: [ 0.5,  0.5] },
+    Vertex2D { pos: [-0.5,  0.5] },
 ];
 
 #[rustfmt::skip]
-const INDICES: [u32; 6] = [
+const INDICES: [u8; 6] = [
     0, 1, 2, 
     2, 3, 0
 ];
 
-pub unsafe fn create_sample(gl: &super::gl::Gl) -> SampleProps {
-    // Create shaders:
-    let shader = Shader::load(gl, "basic", "basic_uniform");
-
-    // Create vertex array object:
-    let mut vao = std::mem::zeroed();
-    gl_call!(gl, GenVertexArrays(1, &mut vao));
-    gl_call!(gl, BindVertexArray(vao));
-
-    // Create vertex buffer object:
-    let mut vbo = std::mem::zeroed();
-    gl_call!(gl, GenBuffers(1, &mut vbo));
-    // In OpenGl, `bind` means `select`.
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, vbo));
-    gl_call!(
-        gl,
-        BufferData(
-            gl::ARRAY_BUFFER,
-            (VERTEX_DATA.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
-            VERTEX_DATA.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        )
-    );
-
-    gl_call!(gl, EnableVertexAttribArray(0));
-    gl_call!(
-        gl,
-        VertexAttribPointer(
-            0, // index
-            2, // size
-            gl::FLOAT, // type
-            gl::FALSE, // normalized
-            (std::mem::size_of::<f32>() * 2) as GLsizei, // stride
-            (std::mem::size_of::<f32>() * 0) as *const _, // offset
-        )
-    );
-
-    // Create index buffer object:
-    let mut ibo = std::mem::zeroed();
-    gl_call!(gl, GenBuffers(1, &mut ibo));
-    gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo));
-    gl_call!(
-        gl,
-        BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            (INDICES.len() * std::mem::size_of::<u32>()) as GLsizeiptr,
-            INDICES.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        )
-    );
-
-    // Unbind things:
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
-    gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
-    gl_call!(gl, BindVertexArray(0));
-    gl_call!(gl, UseProgram(0));
-
-    SampleProps::SimpleSquare {
-        shader,
-        vao,
-        vbo,
-        ibo,
-        r: Cell::new(0.),
-        inc: Cell::new(0.05),
+/// How fast the red channel sweeps from 0 to 1 and back, per second.
+const PULSE_SPEED: f32 = 3.0;
+
+pub struct SimpleSquare {
+    shader: Handle<Shader>,
+    mesh: Handle<Mesh>,
+    pulse: Timeline,
+}
+
+impl Sample for SimpleSquare {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        // Create shaders:
+        let shader = resources.load_shader(gl, "basic", "basic_uniform");
+
+        // Create the mesh, its vertex layout comes from `Vertex2D` and the
+        // few indices fit in bytes:
+        let mesh = Mesh::indexed(gl, Topology::Triangles, &VERTICES, &INDICES);
+
+        // Unbind things:
+        gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
+        gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
+        gl_call!(gl, UseProgram(0));
+
+        // Ping-pong the red channel between 0 and 1:
+        let color = Curve::tween(
+            [0., 0.2, 0.75, 0.9],
+            [1., 0.2, 0.75, 0.9],
+            1. / PULSE_SPEED,
+            Easing::Linear,
+        );
+        let pulse = Timeline::new().with_track(
+            Track::new(Binding::uniform(
+                shader.clone(),
+                "u_color",
+                UniformCurve::Vec4(color),
+            ))
+            .with_loop_mode(LoopMode::PingPong),
+        );
+
+        Self {
+            shader,
+            mesh: resources.insert(mesh),
+            pulse,
+        }
+    }
+
+    fn update(&mut self, ctx: &SampleContext) -> bool {
+        // Driven by time rather than frame count.
+        self.pulse.update(&ctx.time);
+        self.pulse.apply_uniforms(ctx.gl, ctx.resources);
+
+        true
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let SampleContext {
+            gl,
+            resources,
+            state,
+            ..
+        } = *ctx;
+
+        state.apply(gl, &RenderState::default());
+
+        gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
+        gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+
+        let shader = resources.get(&self.shader);
+        shader.bind(gl);
+        // Drawn straight in clip space.
+        shader.set_matrices(gl, &MatrixUniforms::default());
+
+        resources.get(&self.mesh).draw(gl);
+
+        shader.unbind(gl);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // The mesh and shader are released by the `ResourceManager`.
     }
 }
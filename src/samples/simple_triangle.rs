@@ -1,55 +1,70 @@
-use super::SampleProps;
-use crate::{gl_call, shaders::Shader};
-use gl::types::{GLsizei, GLsizeiptr};
+use super::{Sample, SampleContext, SampleEntry, Vertex2D};
+use crate::{
+    gl_call,
+    mesh::{Mesh, Topology},
+    render_state::RenderState,
+    resources::Handle,
+    shaders::{MatrixUniforms, Shader},
+};
+
+pub const ENTRY: SampleEntry = SampleEntry::new::<SimpleTriangle>("simple_triangle").with_order(10);
 
 #[rustfmt::skip]
-const VERTEX_DATA: [f32; 6] = [
-    -0.5, -0.5,
-     0.0,  0.5,
-     0.5, -0.5,
+const VERTICES: [Vertex2D; 3] = [
+    Vertex2D { pos: [-0.5, -0.5] },
+    Vertex2D { pos: [ 0.0,  0.5] },
+    Vertex2D { pos: [ 0.5, -0.5] },
 ];
 
-pub unsafe fn create_sample(gl: &super::gl::Gl) -> SampleProps {
-    // Create shaders:
-    let shader = Shader::load(gl, "basic", "basic");
-
-    // Create vertex array object:
-    let mut vao = std::mem::zeroed();
-    gl_call!(gl, GenVertexArrays(1, &mut vao));
-    gl_call!(gl, BindVertexArray(vao));
-
-    // Create vertex buffer object:
-    let mut vbo = std::mem::zeroed();
-    gl_call!(gl, GenBuffers(1, &mut vbo));
-    // In OpenGl, `bind` means `select`.
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, vbo));
-    gl_call!(
-        gl,
-        BufferData(
-            gl::ARRAY_BUFFER,
-            (VERTEX_DATA.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
-            VERTEX_DATA.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        )
-    );
-
-    gl_call!(gl, EnableVertexAttribArray(0));
-    gl_call!(
-        gl,
-        VertexAttribPointer(
-            0, // index
-            2, // size
-            gl::FLOAT, // type
-            gl::FALSE, // normalized
-            (std::mem::size_of::<f32>() * 2) as GLsizei, // stride
-            (std::mem::size_of::<f32>() * 0) as *const _, // offset
-        )
-    );
-
-    // Unbind things:
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
-    gl_call!(gl, BindVertexArray(0));
-    gl_call!(gl, UseProgram(0));
-
-    SampleProps::SimpleTriangle { shader, vao, vbo }
+pub struct SimpleTriangle {
+    shader: Handle<Shader>,
+    mesh: Handle<Mesh>,
+}
+
+impl Sample for SimpleTriangle {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        // Create shaders:
+        let shader = resources.load_shader(gl, "basic", "basic");
+
+        // Create the mesh, its vertex layout comes from `Vertex2D`:
+        let mesh = Mesh::new(gl, Topology::Triangles, &VERTICES);
+
+        // Unbind things:
+        gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
+        gl_call!(gl, UseProgram(0));
+
+        Self {
+            shader,
+            mesh: resources.insert(mesh),
+        }
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let SampleContext {
+            gl,
+            resources,
+            state,
+            ..
+        } = *ctx;
+
+        state.apply(gl, &RenderState::default());
+
+        gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
+        gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+
+        let shader = resources.get(&self.shader);
+        shader.bind(gl);
+        // Drawn straight in clip space.
+        shader.set_matrices(gl, &MatrixUniforms::default());
+
+        resources.get(&self.mesh).draw(gl);
+
+        shader.unbind(gl);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // The mesh and shader are released by the `ResourceManager`.
+    }
 }
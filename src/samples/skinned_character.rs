@@ -0,0 +1,301 @@
+use super::{Sample, SampleContext, SampleEntry};
+use crate::{
+    animation::{AnimationPlayer, Channel, Clip, Interpolation, Property},
+    camera::{Camera, CameraController, OrbitController},
+    gl_call,
+    mesh::{shapes, MeshData},
+    render_state::{CullState, DepthState, RenderState},
+    scene::{Material, Node, Scene, Skin},
+    transform::Transform,
+};
+use nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3, Vector4};
+
+pub const ENTRY: SampleEntry =
+    SampleEntry::new::<SkinnedCharacter>("skinned_character").with_order(40);
+
+/// Seconds each clip plays before fading into the other.
+const CLIP_TIME: f64 = 4.;
+const FADE_TIME: f32 = 0.5;
+
+/// The skeleton: name, parent and bind position relative to the parent. The
+/// figure stands on the origin and faces +Z.
+const JOINTS: [(&str, Option<usize>, [f32; 3]); 11] = [
+    ("hips", None, [0., 1., 0.]),
+    ("spine", Some(HIPS), [0., 0.25, 0.]),
+    ("head", Some(SPINE), [0., 0.4, 0.]),
+    ("left_shoulder", Some(SPINE), [0.3, 0.3, 0.]),
+    ("left_elbow", Some(LEFT_SHOULDER), [0., -0.35, 0.]),
+    ("right_shoulder", Some(SPINE), [-0.3, 0.3, 0.]),
+    ("right_elbow", Some(RIGHT_SHOULDER), [0., -0.35, 0.]),
+    ("left_hip", Some(HIPS), [0.12, 0., 0.]),
+    ("left_knee", Some(LEFT_HIP), [0., -0.5, 0.]),
+    ("right_hip", Some(HIPS), [-0.12, 0., 0.]),
+    ("right_knee", Some(RIGHT_HIP), [0., -0.5, 0.]),
+];
+
+const HIPS: usize = 0;
+const SPINE: usize = 1;
+const HEAD: usize = 2;
+const LEFT_SHOULDER: usize = 3;
+const LEFT_ELBOW: usize = 4;
+const RIGHT_SHOULDER: usize = 5;
+const RIGHT_ELBOW: usize = 6;
+const LEFT_HIP: usize = 7;
+const LEFT_KNEE: usize = 8;
+const RIGHT_HIP: usize = 9;
+const RIGHT_KNEE: usize = 10;
+
+/// Move `data` by `offset` and bind each vertex through `bind`, which maps
+/// the vertex height to joints and weights.
+fn part(
+    mut data: MeshData,
+    offset: Vector3<f32>,
+    bind: impl Fn(f32) -> ([u16; 4], [f32; 4]),
+) -> MeshData {
+    for vertex in &mut data.vertices {
+        let position = Vector3::from(vertex.position) + offset;
+        vertex.position = position.into();
+
+        let (joints, weights) = bind(position.y);
+        data.joints.push(joints);
+        data.weights.push(weights);
+    }
+    data
+}
+
+/// An arm or leg hanging down from `top`, bending smoothly between `upper`
+/// and `lower` halfway along.
+fn limb(top: Vector3<f32>, length: f32, radius: f32, upper: usize, lower: usize) -> MeshData {
+    let capsule = shapes::capsule(radius, length - 2. * radius, 12, 4);
+    let center = top - Vector3::y() * (length / 2.);
+    part(capsule, center, |y| {
+        let along = (top.y - y) / length;
+        let w = smoothstep(0.4, 0.6, along);
+        ([upper as u16, lower as u16, 0, 0], [1. - w, w, 0., 0.])
+    })
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+/// Bind pose positions of every joint.
+fn bind_positions() -> Vec<Vector3<f32>> {
+    let mut positions: Vec<Vector3<f32>> = vec![];
+    for (_, parent, offset) in JOINTS {
+        let base = parent.map_or_else(Vector3::zeros, |parent| positions[parent]);
+        positions.push(base + Vector3::from(offset));
+    }
+    positions
+}
+
+/// The figure's mesh in the bind pose, every part weighted to its joints.
+fn body(joints: &[Vector3<f32>]) -> MeshData {
+    let torso = part(
+        shapes::capsule(0.18, 0.3, 16, 4),
+        Vector3::new(0., 1.3, 0.),
+        |y| {
+            let w = smoothstep(1.05, 1.4, y);
+            ([HIPS as u16, SPINE as u16, 0, 0], [1. - w, w, 0., 0.])
+        },
+    );
+    let head = part(
+        shapes::uv_sphere(0.15, 16, 12),
+        joints[HEAD] + Vector3::new(0., 0.15, 0.),
+        |_| ([HEAD as u16, 0, 0, 0], [1., 0., 0., 0.]),
+    );
+
+    let mut data = torso;
+    data.append(head);
+    for (shoulder, elbow) in [(LEFT_SHOULDER, LEFT_ELBOW), (RIGHT_SHOULDER, RIGHT_ELBOW)] {
+        data.append(limb(joints[shoulder], 0.7, 0.06, shoulder, elbow));
+    }
+    for (hip, knee) in [(LEFT_HIP, LEFT_KNEE), (RIGHT_HIP, RIGHT_KNEE)] {
+        data.append(limb(joints[hip], 1., 0.08, hip, knee));
+    }
+    data
+}
+
+fn rotation(axis: Unit<Vector3<f32>>, angle: f32) -> Vector4<f32> {
+    UnitQuaternion::from_axis_angle(&axis, angle).coords
+}
+
+/// Rotations of `joint` about `axis`, by `angles` at `times`.
+fn turn(
+    joint: usize,
+    axis: Unit<Vector3<f32>>,
+    times: &[f32],
+    angles: &[f32],
+    interpolation: Interpolation,
+) -> Channel {
+    let values = match interpolation {
+        // Flat tangents ease in and out of every key.
+        Interpolation::CubicSpline => angles
+            .iter()
+            .flat_map(|&angle| [Vector4::zeros(), rotation(axis, angle), Vector4::zeros()])
+            .collect(),
+        _ => angles.iter().map(|&angle| rotation(axis, angle)).collect(),
+    };
+    Channel::new(
+        joint,
+        times.to_vec(),
+        interpolation,
+        Property::Rotation(values),
+    )
+}
+
+/// A one second walk cycle, legs and arms swinging in opposition.
+fn walk() -> Clip {
+    let times = [0., 0.25, 0.5, 0.75, 1.];
+    let swing = [-0.5, 0., 0.5, 0., -0.5];
+    let opposite = swing.map(|angle| -angle);
+    let x = Vector3::x_axis();
+    let linear = Interpolation::Linear;
+
+    Clip::new(
+        "walk",
+        vec![
+            turn(LEFT_HIP, x, &times, &swing, linear),
+            turn(RIGHT_HIP, x, &times, &opposite, linear),
+            turn(LEFT_KNEE, x, &times, &[0.1, 0.3, 0.7, 0.3, 0.1], linear),
+            turn(RIGHT_KNEE, x, &times, &[0.7, 0.3, 0.1, 0.3, 0.7], linear),
+            turn(LEFT_SHOULDER, x, &times, &opposite.map(|a| a * 0.8), linear),
+            turn(RIGHT_SHOULDER, x, &times, &swing.map(|a| a * 0.8), linear),
+            turn(LEFT_ELBOW, x, &[0.], &[-0.4], linear),
+            turn(RIGHT_ELBOW, x, &[0.], &[-0.4], linear),
+            Channel::new(
+                HIPS,
+                times.to_vec(),
+                linear,
+                Property::Translation(
+                    [1., 1.04, 1., 1.04, 1.]
+                        .iter()
+                        .map(|&y| Vector3::new(0., y, 0.))
+                        .collect(),
+                ),
+            ),
+        ],
+    )
+}
+
+/// Waving the right hand, eased by splines, with the head tilting in steps.
+fn wave() -> Clip {
+    let z = Vector3::z_axis();
+    let times = [0., 0.5, 1., 1.5, 2.];
+
+    Clip::new(
+        "wave",
+        vec![
+            turn(RIGHT_SHOULDER, z, &[0.], &[-2.5], Interpolation::Linear),
+            turn(
+                RIGHT_ELBOW,
+                z,
+                &times,
+                &[0.4, -0.4, 0.4, -0.4, 0.4],
+                Interpolation::CubicSpline,
+            ),
+            turn(
+                HEAD,
+                z,
+                &times,
+                &[0.15, -0.15, 0.15, -0.15, 0.15],
+                Interpolation::Step,
+            ),
+        ],
+    )
+}
+
+/// A figure built from capsules and bound to an 11 joint skeleton, switching
+/// between a walk and a wave with a cross-fade.
+pub struct SkinnedCharacter {
+    scene: Scene,
+    player: AnimationPlayer,
+    camera: Camera,
+    controller: OrbitController,
+}
+
+impl Sample for SkinnedCharacter {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        let shader = resources.load_shader(gl, "skinned", "shaded");
+        let positions = bind_positions();
+        let mesh = resources.insert(body(&positions).upload(gl, resources.capabilities()));
+
+        let mut scene = Scene::new();
+        let root = scene.add(Node::new("character"));
+        let mut joints = vec![];
+        for (name, parent, offset) in JOINTS {
+            let node = Node::new(name).with_transform(Transform::from_translation(offset.into()));
+            let parent = parent.map_or(root, |parent| joints[parent]);
+            joints.push(scene.add_child(parent, node));
+        }
+
+        let inverse_bind = positions
+            .iter()
+            .map(|position| Matrix4::new_translation(&-position))
+            .collect();
+        let material = Material::new(shader)
+            .with_color([0.85, 0.55, 0.35, 1.])
+            .with_state(
+                RenderState::default()
+                    .with_depth(DepthState::default())
+                    .with_cull(CullState::default()),
+            );
+        scene.add_child(
+            root,
+            Node::new("body")
+                .with_mesh(mesh, material)
+                .with_skin(Skin::new(joints.clone(), inverse_bind)),
+        );
+
+        let mut player = AnimationPlayer::new(&scene, joints, vec![walk(), wave()]);
+        player.play(0, 0.);
+
+        let mut camera = Camera::perspective(0.8, 0.1, 100.);
+        let (width, height) = resources.surface_size();
+        camera.resize(width, height);
+        let mut controller = OrbitController::new(Point3::new(0., 1., 0.), 4.);
+        controller.pitch = 0.2;
+        controller.apply(&mut camera);
+
+        Self {
+            scene,
+            player,
+            camera,
+            controller,
+        }
+    }
+
+    fn update(&mut self, ctx: &SampleContext) -> bool {
+        let clip = (ctx.time.total / CLIP_TIME) as usize % self.player.clips().len();
+        if self.player.current() != Some(clip) {
+            self.player.play(clip, FADE_TIME);
+        }
+        self.player.update(&mut self.scene, ctx.time.delta);
+
+        self.controller
+            .update(&mut self.camera, ctx.input, ctx.time.delta);
+
+        true
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let gl = ctx.gl;
+
+        gl_call!(gl, ClearColor(0.1, 0.1, 0.12, 0.9));
+        ctx.state
+            .clear(gl, gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+        ctx.render_scene(&self.scene, &self.camera);
+    }
+
+    fn resize(&mut self, _ctx: &SampleContext, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // The mesh and shader are released along with the scene.
+    }
+}
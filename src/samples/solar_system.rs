@@ -0,0 +1,130 @@
+use super::{Sample, SampleContext, SampleEntry, Vertex2D};
+use crate::{
+    camera::{Camera, CameraController, PanZoomController},
+    gl_call,
+    mesh::{Mesh, Topology},
+    scene::{Material, Node, NodeId, Scene},
+    transform::Transform,
+};
+use nalgebra::{UnitQuaternion, Vector3};
+use std::f32::consts::TAU;
+
+pub const ENTRY: SampleEntry = SampleEntry::new::<SolarSystem>("solar_system").with_order(70);
+
+/// Orbits and spins, in turns per second.
+const EARTH_ORBIT: f32 = 0.1;
+const EARTH_SPIN: f32 = 0.5;
+const MOON_ORBIT: f32 = 0.4;
+
+/// A unit disc as a triangle fan around its center, the rim closes on its
+/// first vertex.
+fn disc(segments: u32) -> Vec<Vertex2D> {
+    let mut vertices = vec![Vertex2D { pos: [0., 0.] }];
+    for i in 0..=segments {
+        let angle = i as f32 / segments as f32 * TAU;
+        vertices.push(Vertex2D {
+            pos: [angle.cos(), angle.sin()],
+        });
+    }
+    vertices
+}
+
+fn spin(turns: f32) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_axis_angle(&Vector3::z_axis(), turns * TAU)
+}
+
+/// A sun, an earth and a moon placed by a scene graph: the moon only knows
+/// its orbit around the earth, which carries it around the sun.
+pub struct SolarSystem {
+    scene: Scene,
+    camera: Camera,
+    controller: PanZoomController,
+
+    earth_orbit: NodeId,
+    earth: NodeId,
+    moon_orbit: NodeId,
+}
+
+impl Sample for SolarSystem {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        let shader = resources.load_shader(gl, "basic", "basic_uniform");
+        let mesh = resources.insert(Mesh::new(gl, Topology::TriangleFan, &disc(48)));
+
+        let body = |name: &str, radius: f32, color: [f32; 4]| {
+            Node::new(name)
+                .with_transform(Transform::from_scale(Vector3::new(radius, radius, 1.)))
+                .with_mesh(
+                    mesh.clone(),
+                    Material::new(shader.clone()).with_color(color),
+                )
+        };
+
+        let mut scene = Scene::new();
+        scene.add(body("sun", 0.25, [1., 0.8, 0.2, 1.]));
+
+        let earth_orbit = scene.add(Node::new("earth_orbit"));
+        let earth = scene.add_child(
+            earth_orbit,
+            Node::new("earth")
+                .with_transform(Transform::from_translation(Vector3::new(0.7, 0., 0.))),
+        );
+        scene.add_child(earth, body("earth_body", 0.08, [0.2, 0.4, 0.9, 1.]));
+
+        let moon_orbit = scene.add_child(earth, Node::new("moon_orbit"));
+        let moon = scene.add_child(
+            moon_orbit,
+            Node::new("moon")
+                .with_transform(Transform::from_translation(Vector3::new(0.16, 0., 0.))),
+        );
+        scene.add_child(moon, body("moon_body", 0.025, [0.7, 0.7, 0.7, 1.]));
+
+        let mut camera = Camera::orthographic(2., -1., 1.);
+        let (width, height) = resources.surface_size();
+        camera.resize(width, height);
+
+        Self {
+            scene,
+            camera,
+            controller: PanZoomController::default(),
+            earth_orbit,
+            earth,
+            moon_orbit,
+        }
+    }
+
+    fn update(&mut self, ctx: &SampleContext) -> bool {
+        let t = ctx.time.total as f32;
+        self.scene.update_local(self.earth_orbit, |local| {
+            local.rotation = spin(t * EARTH_ORBIT)
+        });
+        self.scene
+            .update_local(self.earth, |local| local.rotation = spin(t * EARTH_SPIN));
+        self.scene.update_local(self.moon_orbit, |local| {
+            local.rotation = spin(t * MOON_ORBIT)
+        });
+
+        self.controller
+            .update(&mut self.camera, ctx.input, ctx.time.delta);
+
+        true
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let gl = ctx.gl;
+
+        gl_call!(gl, ClearColor(0.02, 0.02, 0.05, 0.9));
+        ctx.state.clear(gl, gl::COLOR_BUFFER_BIT);
+
+        ctx.render_scene(&self.scene, &self.camera);
+    }
+
+    fn resize(&mut self, _ctx: &SampleContext, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // The meshes and shader are released along with the scene.
+    }
+}
@@ -1,108 +1,114 @@
-use super::SampleProps;
+use super::{Sample, SampleContext, SampleEntry};
 use crate::{
+    camera::Camera,
     gl_call,
-    shaders::Shader,
-    texture::Texture,
+    mesh::{Mesh, Topology},
+    render_state::{BlendState, RenderState},
+    resources::Handle,
+    shaders::{MatrixUniforms, Shader},
+    texture::StreamedTexture,
+    vertex_array::Vertex,
 };
-use gl::types::{GLsizei, GLsizeiptr};
+use bytemuck::{Pod, Zeroable};
 use nalgebra::Matrix4;
 
+pub const ENTRY: SampleEntry = SampleEntry::new::<TexturedSquare>("textured_square").with_order(30);
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+struct TexVertex {
+    #[location(0)]
+    pos: [f32; 2],
+    #[location(1)]
+    uv: [f32; 2],
+}
+
 #[rustfmt::skip]
-const VERTEX_DATA: [f32; 16] = [
-    -0.5, -0.5, 0.0, 0.0,  // 0
-     0.5, -0.5, 1.0, 0.0,  // 1
-     0.5,  0.5, 1.0, 1.0,  // 2
-    -0.5,  0.5, 0.0, 1.0,  // 3
+const VERTICES: [TexVertex; 4] = [
+    TexVertex { pos: [-0.5, -0.5], uv: [0.0, 0.0] },
+    TexVertex { pos: [ 0.5, -0.5], uv: [1.0, 0.0] },
+    TexVertex { pos: [ 0.5,  0.5], uv: [1.0, 1.0] },
+    TexVertex { pos: [-0.5,  0.5], uv: [0.0, 1.0] },
 ];
 
 #[rustfmt::skip]
-const INDICES: [u32; 6] = [
+const INDICES: [u16; 6] = [
     0, 1, 2, 
     2, 3, 0
 ];
 
-pub unsafe fn create_sample(gl: &super::gl::Gl) -> SampleProps {
-    // Create shaders:
-    let shader = Shader::load(gl, "basic_texture", "basic_texture");
-
-    // Create vertex array object:
-    let mut vao = std::mem::zeroed();
-    gl_call!(gl, GenVertexArrays(1, &mut vao));
-    gl_call!(gl, BindVertexArray(vao));
-
-    // Create vertex buffer object:
-    let mut vbo = std::mem::zeroed();
-    gl_call!(gl, GenBuffers(1, &mut vbo));
-    // In OpenGl, `bind` means `select`.
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, vbo));
-    gl_call!(
-        gl,
-        BufferData(
-            gl::ARRAY_BUFFER,
-            (VERTEX_DATA.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
-            VERTEX_DATA.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        )
-    );
-
-    gl_call!(gl, EnableVertexAttribArray(0));
-    gl_call!(
-        gl,
-        VertexAttribPointer(
-            0, // index
-            2, // size
-            gl::FLOAT, // type
-            gl::FALSE, // normalized
-            (std::mem::size_of::<f32>() * 4) as GLsizei, // stride
-            (std::mem::size_of::<f32>() * 0) as *const _, // offset
-        )
-    );
-
-    gl_call!(gl, EnableVertexAttribArray(1));
-    gl_call!(
-        gl,
-        VertexAttribPointer(
-            1, // index
-            2, // size
-            gl::FLOAT, // type
-            gl::FALSE, // normalized
-            (std::mem::size_of::<f32>() * 4) as GLsizei, // stride
-            (std::mem::size_of::<f32>() * 2) as *const _, // offset
-        )
-    );
-
-    // Create index buffer object:
-    let mut ibo = std::mem::zeroed();
-    gl_call!(gl, GenBuffers(1, &mut ibo));
-    gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ibo));
-    gl_call!(
-        gl,
-        BufferData(
-            gl::ELEMENT_ARRAY_BUFFER,
-            (INDICES.len() * std::mem::size_of::<u32>()) as GLsizeiptr,
-            INDICES.as_ptr() as *const _,
-            gl::STATIC_DRAW,
-        )
-    );
-
-    // Create the texture:
-    let texture = Texture::load(gl, "texture01.jpeg");
-
-    // Set up the project matrix:
-    let proj = Matrix4::new_orthographic(-1.6, 1.6, -1.175, 1.175, -1.0, 1.0);
-    shader.set_uniform_mat_4f(gl, "u_proj\0", &proj);
-
-    // Unbind things:
-    gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
-    gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
-    gl_call!(gl, BindVertexArray(0));
-    gl_call!(gl, UseProgram(0));
-
-    SampleProps::TexturedSquare {
-        shader,
-        vao,
-        vbo,
-        ibo,
-        texture,
+pub struct TexturedSquare {
+    shader: Handle<Shader>,
+    mesh: Handle<Mesh>,
+    texture: Handle<StreamedTexture>,
+    camera: Camera,
+}
+
+impl Sample for TexturedSquare {
+    fn create(ctx: &SampleContext) -> Self {
+        let SampleContext { gl, resources, .. } = *ctx;
+
+        // Create shaders:
+        let shader = resources.load_shader(gl, "basic_texture", "basic_texture");
+
+        // Create the mesh, its vertex layout comes from `TexVertex`:
+        let mesh = Mesh::indexed(gl, Topology::Triangles, &VERTICES, &INDICES);
+
+        // Create the texture, it is decoded in the background and shows a
+        // placeholder until then:
+        let texture = resources.stream_texture("texture01.jpeg");
+
+        // Set up the camera, the projection follows the window size:
+        let mut camera = Camera::orthographic(2.35, -1.0, 1.0);
+        let (width, height) = resources.surface_size();
+        camera.resize(width, height);
+
+        // Unbind things:
+        gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, 0));
+        gl_call!(gl, BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0));
+        gl_call!(gl, UseProgram(0));
+
+        Self {
+            shader,
+            mesh: resources.insert(mesh),
+            texture,
+            camera,
+        }
+    }
+
+    fn resize(&mut self, _ctx: &SampleContext, width: u32, height: u32) {
+        self.camera.resize(width, height);
+    }
+
+    fn render(&mut self, ctx: &SampleContext) {
+        let SampleContext {
+            gl,
+            resources,
+            state,
+            ..
+        } = *ctx;
+
+        state.apply(gl, &RenderState::default().with_blend(BlendState::alpha()));
+
+        gl_call!(gl, ClearColor(0.1, 0.1, 0.1, 0.9));
+        gl_call!(gl, Clear(gl::COLOR_BUFFER_BIT));
+
+        let shader = resources.get(&self.shader);
+        shader.bind(gl);
+        shader.set_matrices(gl, &MatrixUniforms::new(&self.camera, Matrix4::identity()));
+
+        let slot = 0;
+        let texture = resources.get(&self.texture);
+        texture.bind(gl, slot, None);
+        shader.set_uniform_1i(gl, c"u_texture", slot);
+
+        resources.get(&self.mesh).draw(gl);
+
+        shader.unbind(gl);
+        texture.unbind(gl);
+    }
+
+    fn destroy(&mut self, _ctx: &SampleContext) {
+        // Everything is deleted by the `ResourceManager`.
     }
 }
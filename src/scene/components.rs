@@ -0,0 +1,177 @@
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use crate::{
+    lighting::BlinnPhongMaterial, render_state::RenderState, resources::Handle, shaders::Shader,
+    texture::Texture,
+};
+
+/// How a mesh is shaded.
+///
+/// Besides the standard matrices, `color` goes to `u_color` and `texture` to
+/// `u_texture` on slot 0 when the program has them. `lighting` sets the
+/// uniforms and maps of lit programs, see [`BlinnPhongMaterial::apply`].
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub shader: Handle<Shader>,
+    pub color: [f32; 4],
+    pub texture: Option<Handle<Texture>>,
+    pub lighting: Option<BlinnPhongMaterial>,
+    pub state: RenderState,
+}
+
+impl Material {
+    pub fn new(shader: Handle<Shader>) -> Self {
+        Self {
+            shader,
+            color: [1., 1., 1., 1.],
+            texture: None,
+            lighting: None,
+            state: RenderState::default(),
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Handle<Texture>) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_lighting(mut self, lighting: BlinnPhongMaterial) -> Self {
+        self.lighting = Some(lighting);
+        self
+    }
+
+    pub fn with_state(mut self, state: RenderState) -> Self {
+        self.state = state;
+        self
+    }
+}
+
+/// Joints deforming a node's mesh, see [`crate::scene::Scene::joint_matrices`].
+///
+/// Vertices are bound to joints by index through their joint and weight
+/// attributes. A skin has at most [`super::MAX_JOINTS`] joints, as many as
+/// the skinning shaders hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skin {
+    pub joints: Vec<super::NodeId>,
+    /// From the mesh's space to each joint's space in the bind pose.
+    pub inverse_bind: Vec<Matrix4<f32>>,
+}
+
+impl Skin {
+    pub fn new(joints: Vec<super::NodeId>, inverse_bind: Vec<Matrix4<f32>>) -> Self {
+        assert_eq!(
+            joints.len(),
+            inverse_bind.len(),
+            "Every joint needs an inverse bind matrix."
+        );
+        assert!(
+            joints.len() <= super::MAX_JOINTS,
+            "A skin has at most {} joints, not {}.",
+            super::MAX_JOINTS,
+            joints.len()
+        );
+        Self {
+            joints,
+            inverse_bind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    /// Shines along the node's -Z axis from infinitely far away.
+    Directional,
+    /// Shines in all directions from the node, fading out at `range`.
+    Point { range: f32 },
+    /// A cone along the node's -Z axis, angles in radians from its axis.
+    Spot {
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    },
+}
+
+/// How point and spot lights fade with distance `d`, by
+/// `1 / (constant + linear * d + quadratic * d * d)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    /// Full intensity at any distance.
+    pub const NONE: Self = Self::new(1., 0., 0.);
+    /// The physical falloff, kept finite close to the light.
+    pub const INVERSE_SQUARE: Self = Self::new(1., 0., 1.);
+
+    pub const fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// Strength at `distance`, relative to the light's intensity.
+    pub fn factor(&self, distance: f32) -> f32 {
+        1. / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::INVERSE_SQUARE
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Ignored by directional lights. Point and spot lights also fade out
+    /// towards their range.
+    pub attenuation: Attenuation,
+}
+
+impl Light {
+    pub fn new(kind: LightKind) -> Self {
+        Self {
+            kind,
+            color: [1., 1., 1.],
+            intensity: 1.,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+}
+
+/// A light placed in world space by its node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneLight {
+    pub light: Light,
+    pub position: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
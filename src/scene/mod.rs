@@ -0,0 +1,621 @@
+mod components;
+
+pub use components::{Attenuation, Light, LightKind, Material, SceneLight, Skin};
+
+use std::cell::Cell;
+
+use nalgebra::{Matrix3, Matrix4, Point3, UnitQuaternion, Vector3};
+
+use crate::{
+    camera::Camera,
+    gl_call,
+    mesh::Mesh,
+    render_state::StateTracker,
+    renderer::gl,
+    resources::{Handle, ResourceManager},
+    shaders::MatrixUniforms,
+    transform::Transform,
+};
+
+/// Index of a node in its [`Scene`]. Ids of removed nodes are not reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+/// A point in the hierarchy with optional components.
+///
+/// Its placement is relative to the parent and only changed through the
+/// [`Scene`], which keeps the cached world matrices up to date.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    /// Hidden nodes are skipped along with their children.
+    pub visible: bool,
+    pub mesh: Option<Handle<Mesh>>,
+    /// Indexed by [`crate::mesh::Submesh::material`].
+    pub materials: Vec<Material>,
+    pub light: Option<Light>,
+    pub camera: Option<Camera>,
+    pub skin: Option<Skin>,
+    /// How far the mesh is blended towards each of its morph targets.
+    pub morph_weights: Vec<f32>,
+
+    local: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Cell<Matrix4<f32>>,
+    dirty: Cell<bool>,
+}
+
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            mesh: None,
+            materials: vec![],
+            light: None,
+            camera: None,
+            skin: None,
+            morph_weights: vec![],
+            local: Transform::IDENTITY,
+            parent: None,
+            children: vec![],
+            world: Cell::new(Matrix4::identity()),
+            dirty: Cell::new(true),
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.local = transform;
+        self
+    }
+
+    /// Draw `mesh` with `material` for all of its submeshes.
+    pub fn with_mesh(self, mesh: Handle<Mesh>, material: Material) -> Self {
+        self.with_materials(mesh, vec![material])
+    }
+
+    /// Draw `mesh` with one material per submesh material index.
+    pub fn with_materials(mut self, mesh: Handle<Mesh>, materials: Vec<Material>) -> Self {
+        self.mesh = Some(mesh);
+        self.materials = materials;
+        self
+    }
+
+    pub fn with_light(mut self, light: Light) -> Self {
+        self.light = Some(light);
+        self
+    }
+
+    pub fn with_camera(mut self, camera: Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    pub fn with_skin(mut self, skin: Skin) -> Self {
+        self.skin = Some(skin);
+        self
+    }
+
+    pub fn with_morph_weights(mut self, weights: Vec<f32>) -> Self {
+        self.morph_weights = weights;
+        self
+    }
+
+    /// Placement relative to the parent.
+    pub fn local(&self) -> &Transform {
+        &self.local
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// A hierarchy of nodes whose world matrices are their parent's times their
+/// own local transform, cached until a transform above them changes.
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    nodes: Vec<Option<Node>>,
+    roots: Vec<NodeId>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `node` at the top level.
+    pub fn add(&mut self, node: Node) -> NodeId {
+        let id = self.insert(node);
+        self.roots.push(id);
+        id
+    }
+
+    /// Add `node` below `parent`.
+    pub fn add_child(&mut self, parent: NodeId, node: Node) -> NodeId {
+        let id = self.insert(node);
+        self.node_mut(parent).children.push(id);
+        self.node_mut(id).parent = Some(parent);
+        id
+    }
+
+    fn insert(&mut self, mut node: Node) -> NodeId {
+        node.parent = None;
+        node.children.clear();
+        node.dirty.set(true);
+
+        self.nodes.push(Some(node));
+        NodeId(self.nodes.len() as u32 - 1)
+    }
+
+    /// Move `id` below `parent`, or to the top level for `None`. Its local
+    /// transform is kept, so it moves along with the new parent.
+    ///
+    /// Panics if `parent` is `id` or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(
+                !self.is_ancestor(id, parent),
+                "Node {:?} can not be moved below itself.",
+                id
+            );
+        }
+
+        self.detach(id);
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+        self.node_mut(id).parent = parent;
+        self.mark_dirty(id);
+    }
+
+    /// Whether `ancestor` is `id` or above it.
+    pub fn is_ancestor(&self, ancestor: NodeId, mut id: NodeId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.node(id).parent {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn detach(&mut self, id: NodeId) {
+        let siblings = match self.node(id).parent {
+            Some(parent) => &mut self.node_mut(parent).children,
+            None => &mut self.roots,
+        };
+        siblings.retain(|&child| child != id);
+    }
+
+    /// Remove `id` and everything below it.
+    pub fn remove(&mut self, id: NodeId) {
+        self.detach(id);
+
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes[id.0 as usize].take() {
+                stack.extend(node.children);
+            }
+        }
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0 as usize)?.as_ref()
+    }
+
+    /// Panics for removed nodes.
+    pub fn node(&self, id: NodeId) -> &Node {
+        self.get(id)
+            .unwrap_or_else(|| panic!("Node {:?} was removed.", id))
+    }
+
+    /// Access the components of a node, its transform goes through
+    /// [`Scene::set_local`].
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes
+            .get_mut(id.0 as usize)
+            .and_then(Option::as_mut)
+            .unwrap_or_else(|| panic!("Node {:?} was removed.", id))
+    }
+
+    /// The first node called `name`, in depth-first order.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        let mut found = None;
+        self.visit(false, |id, node| {
+            if found.is_none() && node.name == name {
+                found = Some(id);
+            }
+        });
+        found
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Number of nodes in the scene.
+    pub fn len(&self) -> usize {
+        self.nodes.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    pub fn local(&self, id: NodeId) -> &Transform {
+        &self.node(id).local
+    }
+
+    pub fn set_local(&mut self, id: NodeId, transform: Transform) {
+        self.node_mut(id).local = transform;
+        self.mark_dirty(id);
+    }
+
+    /// Change the local transform in place.
+    pub fn update_local(&mut self, id: NodeId, f: impl FnOnce(&mut Transform)) {
+        f(&mut self.node_mut(id).local);
+        self.mark_dirty(id);
+    }
+
+    /// Flag `id` and its subtree for recomputation.
+    fn mark_dirty(&self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            // A dirty node always has a dirty subtree already.
+            if node.dirty.replace(true) {
+                continue;
+            }
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    /// Local to world matrix of `id`, recomputed only after a change above it.
+    pub fn world_matrix(&self, id: NodeId) -> Matrix4<f32> {
+        let node = self.node(id);
+        if node.dirty.get() {
+            let parent = node
+                .parent
+                .map_or_else(Matrix4::identity, |parent| self.world_matrix(parent));
+            node.world.set(parent * node.local.matrix());
+            node.dirty.set(false);
+        }
+        node.world.get()
+    }
+
+    /// World position of `id`.
+    pub fn world_position(&self, id: NodeId) -> Point3<f32> {
+        Point3::from(self.world_matrix(id).fixed_view::<3, 1>(0, 3).into_owned())
+    }
+
+    /// Call `f` for every node in depth-first order, parents before children.
+    /// With `visible_only`, hidden nodes and their subtrees are skipped.
+    pub fn visit(&self, visible_only: bool, mut f: impl FnMut(NodeId, &Node)) {
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if visible_only && !node.visible {
+                continue;
+            }
+            f(id, node);
+            stack.extend(node.children.iter().rev().copied());
+        }
+    }
+
+    /// Every visible light placed in the world.
+    pub fn lights(&self) -> Vec<SceneLight> {
+        let mut lights = vec![];
+        self.visit(true, |id, node| {
+            if let Some(light) = node.light {
+                let world = self.world_matrix(id);
+                lights.push(SceneLight {
+                    light,
+                    position: world.transform_point(&Point3::origin()),
+                    direction: world.transform_vector(&-Vector3::z()).normalize(),
+                });
+            }
+        });
+        lights
+    }
+
+    /// Skinning matrices of the skin on `id`, from its mesh's space to where
+    /// each joint has moved it, still in the mesh's space. Empty without a
+    /// skin.
+    pub fn joint_matrices(&self, id: NodeId) -> Vec<Matrix4<f32>> {
+        let Some(skin) = &self.node(id).skin else {
+            return vec![];
+        };
+        // The node's own placement is applied by the model matrix.
+        let inverse_world = self
+            .world_matrix(id)
+            .try_inverse()
+            .unwrap_or_else(Matrix4::identity);
+        skin.joints
+            .iter()
+            .zip(&skin.inverse_bind)
+            .map(|(&joint, inverse_bind)| inverse_world * self.world_matrix(joint) * inverse_bind)
+            .collect()
+    }
+
+    /// The camera attached to `id`, placed where the node is.
+    pub fn camera(&self, id: NodeId) -> Option<Camera> {
+        let mut camera = self.node(id).camera.clone()?;
+        let world = self.world_matrix(id);
+
+        camera.position = world.transform_point(&Point3::origin());
+        let mut rotation: Matrix3<f32> = world.fixed_view::<3, 3>(0, 0).into_owned();
+        // Scale above the node would skew the rotation.
+        for mut axis in rotation.column_iter_mut() {
+            axis.normalize_mut();
+        }
+        camera.orientation = UnitQuaternion::from_matrix(&rotation);
+        Some(camera)
+    }
+}
+
+/// Length of the `u_joints` array in the skinning shaders, and so the most
+/// joints a [`Skin`] can have.
+pub const MAX_JOINTS: usize = 64;
+
+/// Length of the `u_morph_weights` array in the morphing shaders. Targets
+/// past it are not blended.
+pub const MAX_MORPH_TARGETS: usize = 32;
+
+/// Draw every visible node with a mesh as seen by `camera`, each submesh
+/// with its material. Submeshes without a material are skipped.
+///
+/// Skinned nodes pass their [`Scene::joint_matrices`] as `u_joints`, which
+/// panics for skins with more than [`MAX_JOINTS`] joints. Meshes
+/// with morph targets bind them to slot 1 as `u_morph_deltas`, along with
+/// `u_morph_count`, `u_morph_vertices` and the node's `u_morph_weights`.
+/// Programs with `u_camera_position` get the camera's position, which lit
+/// materials need along with the bound [`crate::lighting::LightBuffer`].
+pub fn render_scene(
+    gl: &gl::Gl,
+    resources: &ResourceManager,
+    state: &StateTracker,
+    scene: &Scene,
+    camera: &Camera,
+) {
+    let matrices = MatrixUniforms::new(camera, Matrix4::identity());
+    let eye = camera.position;
+
+    scene.visit(true, |id, node| {
+        let Some(mesh) = &node.mesh else {
+            return;
+        };
+        let mesh = resources.get(mesh);
+        let matrices = matrices.with_model(scene.world_matrix(id));
+        let joints = scene.joint_matrices(id);
+        // Vertices would index past the end of `u_joints`.
+        assert!(
+            joints.len() <= MAX_JOINTS,
+            "Skin of {} has {} joints, at most {} are supported.",
+            node.name,
+            joints.len(),
+            MAX_JOINTS
+        );
+        let morph = mesh.morph_targets();
+        let mut weights = node.morph_weights.clone();
+        let count = morph.map_or(0, |morph| morph.targets() as usize);
+        weights.resize(count.min(MAX_MORPH_TARGETS), 0.);
+
+        for (index, submesh) in mesh.submeshes().iter().enumerate() {
+            let Some(material) = node.materials.get(submesh.material) else {
+                continue;
+            };
+
+            state.apply(gl, &material.state);
+
+            let shader = resources.get(&material.shader);
+            shader.set_matrices(gl, &matrices);
+            if !joints.is_empty() && shader.has_uniform(gl, c"u_joints") {
+                shader.set_uniform_mat_4f_array(gl, c"u_joints", &joints);
+            }
+            if shader.has_uniform(gl, c"u_color") {
+                let [r, g, b, a] = material.color;
+                shader.set_uniform_4f(gl, c"u_color", r, g, b, a);
+            }
+            if shader.has_uniform(gl, c"u_camera_position") {
+                shader.set_uniform_3f(gl, c"u_camera_position", eye.x, eye.y, eye.z);
+            }
+
+            if shader.has_uniform(gl, c"u_morph_count") {
+                shader.set_uniform_1i(gl, c"u_morph_count", weights.len() as i32);
+            }
+            let morph = morph.filter(|_| shader.has_uniform(gl, c"u_morph_deltas"));
+            if let Some(morph) = morph {
+                let slot = 1;
+                morph.texture().bind(gl, slot, None);
+                shader.set_uniform_1i(gl, c"u_morph_deltas", slot);
+                // Either may be optimized out of a program that still reads
+                // the deltas.
+                if shader.has_uniform(gl, c"u_morph_vertices") {
+                    let vertices = mesh.vertex_count() as i32;
+                    shader.set_uniform_1i(gl, c"u_morph_vertices", vertices);
+                }
+                if !weights.is_empty() && shader.has_uniform(gl, c"u_morph_weights") {
+                    shader.set_uniform_1f_array(gl, c"u_morph_weights", &weights);
+                }
+            }
+
+            let texture = material.texture.as_ref().map(|t| resources.get(t));
+            if let Some(texture) = &texture {
+                let slot = 0;
+                texture.bind(gl, slot, None);
+                if shader.has_uniform(gl, c"u_texture") {
+                    shader.set_uniform_1i(gl, c"u_texture", slot);
+                }
+            }
+            if let Some(lighting) = &material.lighting {
+                lighting.apply(gl, resources, &shader);
+            }
+
+            mesh.draw_submesh(gl, index);
+
+            if let Some(lighting) = &material.lighting {
+                lighting.unbind(gl, resources);
+            }
+            if let Some(morph) = morph {
+                gl_call!(gl, ActiveTexture(gl::TEXTURE1));
+                morph.texture().unbind(gl);
+                gl_call!(gl, ActiveTexture(gl::TEXTURE0));
+            }
+            if let Some(texture) = &texture {
+                texture.unbind(gl);
+            }
+            shader.unbind(gl);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// `a` with children `b` and `c`, `b` with child `d`, and a second root `e`.
+    fn tree() -> (Scene, [NodeId; 5]) {
+        let mut scene = Scene::new();
+        let a = scene.add(Node::new("a").with_transform(Transform::from_translation(Vector3::x())));
+        let b = scene.add_child(a, Node::new("b"));
+        let c = scene.add_child(a, Node::new("c"));
+        let d = scene.add_child(b, Node::new("d"));
+        let e = scene.add(Node::new("e"));
+        (scene, [a, b, c, d, e])
+    }
+
+    fn names(scene: &Scene, visible_only: bool) -> String {
+        let mut names = String::new();
+        scene.visit(visible_only, |_, node| names.push_str(&node.name));
+        names
+    }
+
+    #[test]
+    fn world_matrix_follows_changes_above() {
+        let (mut scene, [a, b, _, d, _]) = tree();
+        scene.set_local(b, Transform::from_translation(Vector3::y()));
+        assert_relative_eq!(scene.world_position(d), Point3::new(1., 1., 0.));
+
+        scene.update_local(a, |local| local.scale = Vector3::repeat(2.));
+        assert_relative_eq!(scene.world_position(d), Point3::new(1., 2., 0.));
+    }
+
+    #[test]
+    fn mark_dirty_flags_only_the_subtree() {
+        let (mut scene, ids) = tree();
+        let [a, b, c, d, e] = ids;
+        for id in ids {
+            scene.world_matrix(id);
+        }
+        let dirty = |scene: &Scene, id| scene.node(id).dirty.get();
+        assert!(!ids.iter().any(|&id| dirty(&scene, id)));
+
+        scene.set_local(b, Transform::default());
+        assert!(dirty(&scene, b) && dirty(&scene, d));
+        assert!(!dirty(&scene, a) && !dirty(&scene, c) && !dirty(&scene, e));
+
+        // Reading a leaf cleans the path to it.
+        scene.world_matrix(d);
+        assert!(!dirty(&scene, b) && !dirty(&scene, d));
+    }
+
+    #[test]
+    fn set_parent_moves_along_with_the_new_parent() {
+        let (mut scene, [a, _, _, d, e]) = tree();
+        scene.set_local(e, Transform::from_translation(Vector3::z()));
+        scene.world_matrix(d);
+
+        scene.set_parent(d, Some(e));
+        assert_relative_eq!(scene.world_position(d), Point3::new(0., 0., 1.));
+        assert_eq!(scene.node(e).children(), [d]);
+
+        scene.set_parent(e, Some(a));
+        assert_relative_eq!(scene.world_position(d), Point3::new(1., 0., 1.));
+        assert_eq!(scene.roots(), [a]);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be moved below itself")]
+    fn set_parent_rejects_cycles() {
+        let (mut scene, [a, _, _, d, _]) = tree();
+        scene.set_parent(a, Some(d));
+    }
+
+    #[test]
+    fn remove_takes_the_subtree() {
+        let (mut scene, [a, b, c, d, e]) = tree();
+        scene.remove(b);
+
+        assert_eq!(scene.len(), 3);
+        assert!(scene.get(b).is_none() && scene.get(d).is_none());
+        assert_eq!(scene.node(a).children(), [c]);
+        assert_eq!(scene.find("d"), None);
+
+        scene.remove(e);
+        assert_eq!(scene.roots(), [a]);
+    }
+
+    #[test]
+    fn visit_is_depth_first_in_order() {
+        let (mut scene, [_, b, _, _, _]) = tree();
+        scene.add_child(b, Node::new("c"));
+        assert_eq!(names(&scene, false), "abdcce");
+
+        scene.node_mut(b).visible = false;
+        assert_eq!(names(&scene, true), "ace");
+        assert_eq!(names(&scene, false), "abdcce");
+    }
+
+    #[test]
+    fn find_returns_the_first_in_visit_order() {
+        let (mut scene, [_, b, c, d, _]) = tree();
+        let late = scene.add_child(b, Node::new("c"));
+        assert_eq!(scene.find("c"), Some(late));
+        assert_eq!(scene.find("d"), Some(d));
+
+        // Hidden nodes are still found.
+        scene.node_mut(b).visible = false;
+        assert_eq!(scene.find("c"), Some(late));
+        scene.remove(late);
+        assert_eq!(scene.find("c"), Some(c));
+        assert_eq!(scene.find("f"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 64 joints")]
+    fn skins_fit_the_joint_uniforms() {
+        let (_, [a, ..]) = tree();
+        let count = MAX_JOINTS + 1;
+        Skin::new(vec![a; count], vec![Matrix4::identity(); count]);
+    }
+
+    #[test]
+    fn camera_ignores_scale() {
+        let rotation = UnitQuaternion::from_euler_angles(0.3, -0.7, 0.2);
+        let mut scene = Scene::new();
+        let rig = scene.add(
+            Node::new("rig").with_transform(Transform::default().with_scale(Vector3::repeat(2.))),
+        );
+        let eye = scene.add_child(
+            rig,
+            Node::new("eye")
+                .with_transform(
+                    Transform::from_rotation(rotation).with_scale(Vector3::new(1., 3., 0.5)),
+                )
+                .with_camera(Camera::perspective(0.8, 0.1, 100.)),
+        );
+
+        let camera = scene.camera(eye).unwrap();
+        assert_relative_eq!(camera.orientation, rotation, epsilon = 1e-5);
+    }
+}
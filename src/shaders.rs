@@ -1,11 +1,11 @@
 use crate::renderer::gl;
-use ::gl::types::{GLchar, GLenum};
+use ::gl::types::GLenum;
 use gl::types::GLuint;
-use nalgebra::Matrix4;
-use std::{fs::File, io::Read, path::PathBuf};
+use nalgebra::{Matrix3, Matrix4};
+use std::{ffi::CStr, fs::File, io::Read, path::PathBuf};
 use winit::dpi::Pixel;
 
-use crate::gl_call;
+use crate::{camera::Camera, gl_call, transform::normal_matrix};
 
 pub enum ShaderType {
     Vertex,
@@ -26,31 +26,127 @@ impl ShaderType {
         }
     }
 
-    fn create_shader<T: ToString>(&self, gl: &gl::Gl, name: T) -> GLuint {
+    fn create_shader<T: ToString>(&self, gl: &gl::Gl, name: T, prelude: &[u8]) -> GLuint {
         let mut name = name.to_string();
         name.push_str(self.suffix());
 
-        let path = PathBuf::default().join("res/shaders").join(name);
-        let os_string = path.as_os_str().to_os_string();
-        let path_str = os_string.to_string_lossy();
-
-        let mut file = File::open(path).expect(&format!("Open file {} failed.", path_str));
-        let mut buf = vec![];
-        file.read_to_end(&mut buf)
-            .expect(&format!("Read file {} failed.", path_str));
+        let mut buf = read_source(&name);
+        insert_after_version(&mut buf, prelude);
         buf.push(0);
 
         unsafe { create_shader(gl, self.gl_type(), &buf) }
     }
 }
 
+/// The file called `name` in `res/shaders`.
+fn read_source(name: &str) -> Vec<u8> {
+    let path = PathBuf::default().join("res/shaders").join(name);
+    let os_string = path.as_os_str().to_os_string();
+    let path_str = os_string.to_string_lossy();
+
+    let mut file = File::open(path).expect(&format!("Open file {} failed.", path_str));
+    let mut buf = vec![];
+    file.read_to_end(&mut buf)
+        .expect(&format!("Read file {} failed.", path_str));
+    buf
+}
+
+/// Source put after the `#version` line of both stages of a program.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ShaderPrelude {
+    /// `#define name value` lines, such as array sizes that depend on the
+    /// context's limits.
+    pub defines: Vec<(String, String)>,
+    /// Files of `res/shaders` pasted after the defines, such as the
+    /// declarations and functions several programs share.
+    pub includes: Vec<String>,
+}
+
+impl ShaderPrelude {
+    pub fn with_define<T: ToString>(mut self, name: &str, value: T) -> Self {
+        self.defines.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_include(mut self, name: &str) -> Self {
+        self.includes.push(name.to_string());
+        self
+    }
+
+    /// The defines, then the included files, each ending in a newline.
+    fn source(&self, read: impl Fn(&str) -> Vec<u8>) -> Vec<u8> {
+        let mut source: Vec<u8> = self
+            .defines
+            .iter()
+            .flat_map(|(name, value)| format!("#define {} {}\n", name, value).into_bytes())
+            .collect();
+        for name in &self.includes {
+            source.extend(read(name));
+            if source.last() != Some(&b'\n') {
+                source.push(b'\n');
+            }
+        }
+        source
+    }
+}
+
+/// The per-draw matrices every built-in shader reads, as
+/// `u_proj * u_view * u_model * position`, plus `u_normal` for normals in
+/// world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatrixUniforms {
+    pub model: Matrix4<f32>,
+    pub view: Matrix4<f32>,
+    pub proj: Matrix4<f32>,
+}
+
+impl Default for MatrixUniforms {
+    fn default() -> Self {
+        Self {
+            model: Matrix4::identity(),
+            view: Matrix4::identity(),
+            proj: Matrix4::identity(),
+        }
+    }
+}
+
+impl MatrixUniforms {
+    pub fn new(camera: &Camera, model: Matrix4<f32>) -> Self {
+        Self {
+            model,
+            view: camera.view(),
+            proj: camera.projection_matrix(),
+        }
+    }
+
+    pub fn with_model(mut self, model: Matrix4<f32>) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn normal(&self) -> Matrix3<f32> {
+        normal_matrix(&self.model)
+    }
+}
+
 pub struct Shader {
     program: GLuint,
 }
 impl Shader {
     pub fn load<T: ToString>(gl: &gl::Gl, vertex_name: T, fragment_name: T) -> Self {
-        let vertex_shader = ShaderType::Vertex.create_shader(gl, vertex_name);
-        let fragment_shader = ShaderType::Fragment.create_shader(gl, fragment_name);
+        Self::load_with_prelude(gl, vertex_name, fragment_name, &ShaderPrelude::default())
+    }
+
+    /// Like `load`, with `prelude` after the `#version` line of both stages.
+    pub fn load_with_prelude<T: ToString>(
+        gl: &gl::Gl,
+        vertex_name: T,
+        fragment_name: T,
+        prelude: &ShaderPrelude,
+    ) -> Self {
+        let prelude = prelude.source(read_source);
+        let vertex_shader = ShaderType::Vertex.create_shader(gl, vertex_name, &prelude);
+        let fragment_shader = ShaderType::Fragment.create_shader(gl, fragment_name, &prelude);
 
         let program = gl_call!(gl, CreateProgram());
         gl_call!(gl, AttachShader(program, vertex_shader));
@@ -65,22 +161,116 @@ impl Shader {
         Self { program }
     }
 
-    pub fn set_uniform_1i(&self, gl: &gl::Gl, name: &str, v0: i32) {
+    pub fn set_uniform_1i(&self, gl: &gl::Gl, name: &CStr, v0: i32) {
         self.bind(gl);
 
         gl_call!(gl, Uniform1i(self.location(gl, name), v0));
     }
 
-    pub fn set_uniform_4f(&self, gl: &gl::Gl, name: &str, v0: f32, v1: f32, v2: f32, v3: f32) {
+    pub fn set_uniform_1f(&self, gl: &gl::Gl, name: &CStr, v0: f32) {
+        self.bind(gl);
+
+        gl_call!(gl, Uniform1f(self.location(gl, name), v0));
+    }
+
+    pub fn set_uniform_3f(&self, gl: &gl::Gl, name: &CStr, v0: f32, v1: f32, v2: f32) {
+        self.bind(gl);
+
+        gl_call!(gl, Uniform3f(self.location(gl, name), v0, v1, v2));
+    }
+
+    /// Set the elements of a `float` array uniform from its first one on.
+    pub fn set_uniform_1f_array(&self, gl: &gl::Gl, name: &CStr, values: &[f32]) {
+        self.bind(gl);
+
+        gl_call!(
+            gl,
+            Uniform1fv(
+                self.location(gl, name),
+                values.len() as i32,
+                values.as_ptr()
+            )
+        )
+    }
+
+    pub fn set_uniform_4f(&self, gl: &gl::Gl, name: &CStr, v0: f32, v1: f32, v2: f32, v3: f32) {
         self.bind(gl);
 
         gl_call!(gl, Uniform4f(self.location(gl, name), v0, v1, v2, v3));
     }
 
-    pub fn set_uniform_mat_4f(&self, gl: &gl::Gl, name: &str, mat: &Matrix4<f32>) {
+    pub fn set_uniform_mat_4f(&self, gl: &gl::Gl, name: &CStr, mat: &Matrix4<f32>) {
         self.bind(gl);
 
-        gl_call!(gl, UniformMatrix4fv(self.location(gl, name), 1, gl::FALSE, mat.as_ptr()))
+        gl_call!(
+            gl,
+            UniformMatrix4fv(self.location(gl, name), 1, gl::FALSE, mat.as_ptr())
+        )
+    }
+
+    /// Set the elements of a `mat4` array uniform from its first one on.
+    pub fn set_uniform_mat_4f_array(&self, gl: &gl::Gl, name: &CStr, mats: &[Matrix4<f32>]) {
+        self.bind(gl);
+
+        gl_call!(
+            gl,
+            UniformMatrix4fv(
+                self.location(gl, name),
+                mats.len() as i32,
+                gl::FALSE,
+                mats.as_ptr().cast()
+            )
+        )
+    }
+
+    pub fn set_uniform_mat_3f(&self, gl: &gl::Gl, name: &CStr, mat: &Matrix3<f32>) {
+        self.bind(gl);
+
+        gl_call!(
+            gl,
+            UniformMatrix3fv(self.location(gl, name), 1, gl::FALSE, mat.as_ptr())
+        )
+    }
+
+    /// Set the standard matrices, skipping those the program does not use.
+    pub fn set_matrices(&self, gl: &gl::Gl, matrices: &MatrixUniforms) {
+        self.bind(gl);
+
+        let mat4 = [
+            (c"u_model", &matrices.model),
+            (c"u_view", &matrices.view),
+            (c"u_proj", &matrices.proj),
+        ];
+        for (name, mat) in mat4 {
+            if let Some(location) = self.find_location(gl, name) {
+                gl_call!(gl, UniformMatrix4fv(location, 1, gl::FALSE, mat.as_ptr()));
+            }
+        }
+
+        if let Some(location) = self.find_location(gl, c"u_normal") {
+            let normal = matrices.normal();
+            gl_call!(
+                gl,
+                UniformMatrix3fv(location, 1, gl::FALSE, normal.as_ptr())
+            );
+        }
+    }
+
+    /// Read the uniform block called `name` from the buffer bound to
+    /// `binding`. Returns whether the program has the block.
+    pub fn bind_uniform_block(&self, gl: &gl::Gl, name: &CStr, binding: u32) -> bool {
+        let index = gl_call!(gl, GetUniformBlockIndex(self.program, name.as_ptr()));
+        if index == gl::INVALID_INDEX {
+            return false;
+        }
+
+        gl_call!(gl, UniformBlockBinding(self.program, index, binding));
+        true
+    }
+
+    /// Whether the program has an active uniform called `name`.
+    pub fn has_uniform(&self, gl: &gl::Gl, name: &CStr) -> bool {
+        self.find_location(gl, name).is_some()
     }
 
     pub fn bind(&self, gl: &gl::Gl) {
@@ -101,11 +291,18 @@ impl Shader {
         self.program
     }
 
-    unsafe fn location(&self, gl: &gl::Gl, name: &str) -> i32 {
-        let location = gl_call!(gl, GetUniformLocation(self.program, as_gl_char_ptr(name)));
+    unsafe fn location(&self, gl: &gl::Gl, name: &CStr) -> i32 {
+        let location = gl_call!(gl, GetUniformLocation(self.program, name.as_ptr()));
         assert!(location != -1);
         location
     }
+
+    /// Like `location`, but `None` for uniforms the program does not have or
+    /// the compiler optimized out.
+    fn find_location(&self, gl: &gl::Gl, name: &CStr) -> Option<i32> {
+        let location = gl_call!(gl, GetUniformLocation(self.program, name.as_ptr()));
+        (location != -1).then_some(location)
+    }
 }
 
 pub unsafe fn create_shader(
@@ -127,7 +324,7 @@ pub unsafe fn create_shader(
 
     let mut result = std::mem::zeroed();
     gl_call!(gl, GetShaderiv(shader, gl::COMPILE_STATUS, &mut result));
-    if result == gl::FALSE.cast() {
+    if result == gl::FALSE.cast::<gl::types::GLint>() {
         let mut length = std::mem::zeroed();
         gl_call!(gl, GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length));
 
@@ -143,8 +340,36 @@ pub unsafe fn create_shader(
     shader
 }
 
-/// #### The string that interacts with OpenGl Api needs to end with '\0'
-#[inline]
-pub fn as_gl_char_ptr(str: &str) -> *const GLchar {
-    str.as_bytes() as *const [u8] as *const u8 as *const GLchar
+/// Put `lines` after the first line of `source`, which has to be the
+/// `#version` directive.
+fn insert_after_version(source: &mut Vec<u8>, lines: &[u8]) {
+    let at = source
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(source.len(), |i| i + 1);
+    source.splice(at..at, lines.iter().copied());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prelude_follows_the_version() {
+        let prelude = ShaderPrelude::default()
+            .with_define("MAX_LIGHTS", 4)
+            .with_include("a.glsl")
+            .with_include("b.glsl");
+        let read = |name: &str| match name {
+            "a.glsl" => b"float a;".to_vec(),
+            _ => b"float b;\n".to_vec(),
+        };
+
+        let mut source = b"#version 330 core\r\nvoid main() {}".to_vec();
+        insert_after_version(&mut source, &prelude.source(read));
+        assert_eq!(
+            String::from_utf8(source).unwrap(),
+            "#version 330 core\r\n#define MAX_LIGHTS 4\nfloat a;\nfloat b;\nvoid main() {}"
+        );
+    }
 }
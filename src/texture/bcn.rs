@@ -0,0 +1,189 @@
+//! Cpu decoders for the S3TC block formats, used as a fallback on drivers
+//! without `GL_EXT_texture_compression_s3tc`.
+
+pub fn decode_bc1(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decode_blocks(data, width, height, 8, |block, out| {
+        decode_color(block, true, out);
+    })
+}
+
+pub fn decode_bc2(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decode_blocks(data, width, height, 16, |block, out| {
+        decode_color(&block[8..], false, out);
+
+        let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+        for (i, texel) in out.iter_mut().enumerate() {
+            texel[3] = ((alpha >> (i * 4)) & 0xF) as u8 * 17;
+        }
+    })
+}
+
+pub fn decode_bc3(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    decode_blocks(data, width, height, 16, |block, out| {
+        decode_color(&block[8..], false, out);
+
+        let (a0, a1) = (block[0] as u32, block[1] as u32);
+        let mut palette = [0u8; 8];
+        palette[0] = a0 as u8;
+        palette[1] = a1 as u8;
+        if a0 > a1 {
+            for i in 1..7 {
+                palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+            }
+        } else {
+            for i in 1..5 {
+                palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+            }
+            palette[6] = 0;
+            palette[7] = 255;
+        }
+
+        let mut indices = [0u8; 8];
+        indices[..6].copy_from_slice(&block[2..8]);
+        let indices = u64::from_le_bytes(indices);
+        for (i, texel) in out.iter_mut().enumerate() {
+            texel[3] = palette[((indices >> (i * 3)) & 0x7) as usize];
+        }
+    })
+}
+
+/// Walk the 4x4 blocks of an image, letting `decode` fill each block's 16
+/// texels before they are copied into the RGBA8 output.
+fn decode_blocks(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    block_bytes: usize,
+    decode: impl Fn(&[u8], &mut [[u8; 4]; 16]),
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let blocks_x = width.div_ceil(4);
+    let mut rgba = vec![0u8; width * height * 4];
+
+    for (index, block) in data.chunks_exact(block_bytes).enumerate() {
+        let (bx, by) = (index % blocks_x * 4, index / blocks_x * 4);
+        if by >= height {
+            break;
+        }
+
+        let mut texels = [[0u8; 4]; 16];
+        decode(block, &mut texels);
+
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (bx + i % 4, by + i / 4);
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                rgba[offset..offset + 4].copy_from_slice(texel);
+            }
+        }
+    }
+
+    rgba
+}
+
+/// Decode the 8 byte color part shared by BC1-BC3.
+fn decode_color(block: &[u8], punch_through: bool, out: &mut [[u8; 4]; 16]) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (rgb0, rgb1) = (expand_565(c0), expand_565(c1));
+
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((a as u32 * wa + b as u32 * wb) / (wa + wb)) as u8;
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [rgb0[0], rgb0[1], rgb0[2], 255];
+    palette[1] = [rgb1[0], rgb1[1], rgb1[2], 255];
+    if c0 > c1 || !punch_through {
+        for c in 0..3 {
+            palette[2][c] = mix(rgb0[c], rgb1[c], 2, 1);
+            palette[3][c] = mix(rgb0[c], rgb1[c], 1, 2);
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for c in 0..3 {
+            palette[2][c] = mix(rgb0[c], rgb1[c], 1, 1);
+        }
+        palette[2][3] = 255;
+        palette[3] = [0, 0, 0, 0];
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = palette[((indices >> (i * 2)) & 0x3) as usize];
+    }
+}
+
+fn expand_565(c: u16) -> [u8; 3] {
+    let r = ((c >> 11) & 0x1F) as u8;
+    let g = ((c >> 5) & 0x3F) as u8;
+    let b = (c & 0x1F) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u16 = 0xF800;
+    const BLUE: u16 = 0x001F;
+
+    /// A color block of two endpoints, texel `i` picking palette entry `i % 4`.
+    fn color_block(c0: u16, c1: u16) -> [u8; 8] {
+        let [a, b] = c0.to_le_bytes();
+        let [c, d] = c1.to_le_bytes();
+        [a, b, c, d, 0xE4, 0xE4, 0xE4, 0xE4]
+    }
+
+    fn texel(rgba: &[u8], index: usize) -> [u8; 4] {
+        rgba[index * 4..index * 4 + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn bc1_interpolates_between_endpoints() {
+        let rgba = decode_bc1(&color_block(RED, BLUE), 4, 4);
+        assert_eq!(rgba.len(), 64);
+        assert_eq!(texel(&rgba, 0), [255, 0, 0, 255]);
+        assert_eq!(texel(&rgba, 1), [0, 0, 255, 255]);
+        assert_eq!(texel(&rgba, 2), [170, 0, 85, 255]);
+        assert_eq!(texel(&rgba, 7), [85, 0, 170, 255]);
+
+        // Swapped endpoints switch to three colors and transparent black.
+        let rgba = decode_bc1(&color_block(BLUE, RED), 4, 4);
+        assert_eq!(texel(&rgba, 2), [127, 0, 127, 255]);
+        assert_eq!(texel(&rgba, 3), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn partial_blocks_are_cropped() {
+        let rgba = decode_bc1(&color_block(RED, BLUE), 2, 3);
+        assert_eq!(rgba.len(), 2 * 3 * 4);
+        // Row 1 starts with the block's texel 4.
+        assert_eq!(texel(&rgba, 2), [255, 0, 0, 255]);
+        assert_eq!(texel(&rgba, 5), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn bc2_and_bc3_alpha() {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&0x8F_u64.to_le_bytes());
+        block[8..].copy_from_slice(&color_block(RED, BLUE));
+        let rgba = decode_bc2(&block, 4, 4);
+        assert_eq!(texel(&rgba, 0), [255, 0, 0, 255]);
+        assert_eq!(texel(&rgba, 1)[3], 136);
+        assert_eq!(texel(&rgba, 2)[3], 0);
+
+        // Alpha indices 1, 2 and 7 for the first three texels.
+        let mut block = [0u8; 16];
+        block[0] = 255;
+        block[2..8].copy_from_slice(&(1_u64 | 2 << 3 | 7 << 6).to_le_bytes()[..6]);
+        block[8..].copy_from_slice(&color_block(RED, BLUE));
+        let rgba = decode_bc3(&block, 4, 4);
+        assert_eq!(texel(&rgba, 0)[3], 0);
+        assert_eq!(texel(&rgba, 1)[3], 218);
+        assert_eq!(texel(&rgba, 2)[3], 36);
+        assert_eq!(texel(&rgba, 3)[3], 255);
+    }
+}
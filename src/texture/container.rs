@@ -0,0 +1,317 @@
+use super::{bcn, TextureError, TextureFormat};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+
+/// A 2D image with its full mip chain, as stored in a KTX2 or DDS container.
+///
+/// Levels are ordered from the base level down, each one holding the raw
+/// (possibly block compressed) bytes ready for upload.
+pub struct MipChain {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl MipChain {
+    pub fn read_ktx2(bytes: &[u8]) -> Result<Self, TextureError> {
+        let reader = ktx2::Reader::new(bytes)
+            .map_err(|e| TextureError::Container(format!("invalid KTX2: {e:?}")))?;
+        let header = reader.header();
+
+        if let Some(scheme) = header.supercompression_scheme {
+            return Err(TextureError::Container(format!(
+                "KTX2 supercompression {scheme:?} is not supported"
+            )));
+        }
+        if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+            return Err(TextureError::Container(
+                "only single layer 2D KTX2 textures are supported".to_string(),
+            ));
+        }
+
+        let ktx_format = header
+            .format
+            .ok_or_else(|| TextureError::Container("KTX2 file has no vk format".to_string()))?;
+        let format = format_from_ktx2(ktx_format)
+            .ok_or_else(|| TextureError::UnsupportedFormat(format!("{ktx_format:?}")))?;
+
+        let chain = Self {
+            format,
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            levels: reader.levels().map(|level| level.data.to_vec()).collect(),
+        };
+        chain.validate()?;
+        Ok(chain)
+    }
+
+    pub fn read_dds(bytes: &[u8]) -> Result<Self, TextureError> {
+        let dds =
+            Dds::read(bytes).map_err(|e| TextureError::Container(format!("invalid DDS: {e}")))?;
+
+        if dds.get_depth() > 1 || dds.get_num_array_layers() > 1 {
+            return Err(TextureError::Container(
+                "only single layer 2D DDS textures are supported".to_string(),
+            ));
+        }
+
+        let format = match (dds.get_dxgi_format(), dds.get_d3d_format()) {
+            (Some(dxgi), _) => format_from_dxgi(dxgi)
+                .ok_or_else(|| TextureError::UnsupportedFormat(format!("{dxgi:?}")))?,
+            (None, Some(d3d)) => format_from_d3d(d3d)
+                .ok_or_else(|| TextureError::UnsupportedFormat(format!("{d3d:?}")))?,
+            (None, None) => {
+                return Err(TextureError::Container(
+                    "DDS file has an unknown pixel format".to_string(),
+                ))
+            }
+        };
+
+        let (width, height) = (dds.get_width(), dds.get_height());
+        let data = dds
+            .get_data(0)
+            .map_err(|e| TextureError::Container(format!("invalid DDS: {e}")))?;
+
+        // DDS stores the mip levels back to back, slice them by their sizes.
+        let mut levels = vec![];
+        let mut offset = 0;
+        for level in 0..dds.get_num_mipmap_levels() {
+            let size = format.level_size(width >> level, height >> level);
+            let Some(bytes) = data.get(offset..offset + size) else {
+                return Err(TextureError::Container(format!(
+                    "DDS mip level {} is truncated",
+                    level
+                )));
+            };
+            levels.push(bytes.to_vec());
+            offset += size;
+        }
+
+        let chain = Self {
+            format,
+            width,
+            height,
+            levels,
+        };
+        chain.validate()?;
+        Ok(chain)
+    }
+
+    /// Decode the chain to RGBA8 on the cpu, for when the gpu can not sample
+    /// the compressed format directly.
+    pub fn decompress(self) -> Result<Self, TextureError> {
+        let decode: fn(&[u8], u32, u32) -> Vec<u8> = match self.format {
+            TextureFormat::Bc1Rgb
+            | TextureFormat::Bc1Srgb
+            | TextureFormat::Bc1Rgba
+            | TextureFormat::Bc1SrgbAlpha => bcn::decode_bc1,
+            TextureFormat::Bc2 | TextureFormat::Bc2Srgb => bcn::decode_bc2,
+            TextureFormat::Bc3 | TextureFormat::Bc3Srgb => bcn::decode_bc3,
+            other if !other.is_compressed() => return Ok(self),
+            other => return Err(TextureError::UnsupportedCompression(other)),
+        };
+
+        let levels = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| decode(level, (self.width >> i).max(1), (self.height >> i).max(1)))
+            .collect();
+
+        Ok(Self {
+            format: if self.format.is_srgb() {
+                TextureFormat::Srgb8Alpha8
+            } else {
+                TextureFormat::Rgba8
+            },
+            width: self.width,
+            height: self.height,
+            levels,
+        })
+    }
+
+    fn validate(&self) -> Result<(), TextureError> {
+        if self.width == 0 || self.levels.is_empty() {
+            return Err(TextureError::Container(
+                "texture has no image data".to_string(),
+            ));
+        }
+
+        for (i, level) in self.levels.iter().enumerate() {
+            let expected = self.format.level_size(self.width >> i, self.height >> i);
+            if level.len() < expected {
+                return Err(TextureError::Container(format!(
+                    "mip level {} has {} bytes, expected {}",
+                    i,
+                    level.len(),
+                    expected
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_from_ktx2(format: ktx2::Format) -> Option<TextureFormat> {
+    use ktx2::Format as F;
+
+    Some(match format {
+        F::R8G8B8A8_UNORM => TextureFormat::Rgba8,
+        F::R8G8B8A8_SRGB => TextureFormat::Srgb8Alpha8,
+
+        F::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8,
+        F::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Srgb8,
+        F::ETC2_R8G8B8A1_UNORM_BLOCK => TextureFormat::Etc2Rgb8A1,
+        F::ETC2_R8G8B8A1_SRGB_BLOCK => TextureFormat::Etc2Srgb8A1,
+        F::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8,
+        F::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Srgb8Alpha8,
+        F::EAC_R11_UNORM_BLOCK => TextureFormat::EacR11,
+        F::EAC_R11_SNORM_BLOCK => TextureFormat::EacR11Snorm,
+        F::EAC_R11G11_UNORM_BLOCK => TextureFormat::EacRg11,
+        F::EAC_R11G11_SNORM_BLOCK => TextureFormat::EacRg11Snorm,
+
+        F::BC1_RGB_UNORM_BLOCK => TextureFormat::Bc1Rgb,
+        F::BC1_RGB_SRGB_BLOCK => TextureFormat::Bc1Srgb,
+        F::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1Rgba,
+        F::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1SrgbAlpha,
+        F::BC2_UNORM_BLOCK => TextureFormat::Bc2,
+        F::BC2_SRGB_BLOCK => TextureFormat::Bc2Srgb,
+        F::BC3_UNORM_BLOCK => TextureFormat::Bc3,
+        F::BC3_SRGB_BLOCK => TextureFormat::Bc3Srgb,
+        F::BC4_UNORM_BLOCK => TextureFormat::Bc4,
+        F::BC4_SNORM_BLOCK => TextureFormat::Bc4Snorm,
+        F::BC5_UNORM_BLOCK => TextureFormat::Bc5,
+        F::BC5_SNORM_BLOCK => TextureFormat::Bc5Snorm,
+        F::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hUfloat,
+        F::BC6H_SFLOAT_BLOCK => TextureFormat::Bc6hSfloat,
+        F::BC7_UNORM_BLOCK => TextureFormat::Bc7,
+        F::BC7_SRGB_BLOCK => TextureFormat::Bc7Srgb,
+
+        _ => {
+            // The vk ASTC LDR formats come in unorm/srgb pairs from 157 on.
+            let value = format.value();
+            if !(157..=184).contains(&value) {
+                return None;
+            }
+            let (w, h) = [
+                (4, 4),
+                (5, 4),
+                (5, 5),
+                (6, 5),
+                (6, 6),
+                (8, 5),
+                (8, 6),
+                (8, 8),
+                (10, 5),
+                (10, 6),
+                (10, 8),
+                (10, 10),
+                (12, 10),
+                (12, 12),
+            ][(value - 157) as usize / 2];
+            return TextureFormat::astc(w, h, (value - 157) % 2 == 1);
+        }
+    })
+}
+
+fn format_from_dxgi(format: DxgiFormat) -> Option<TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8G8B8A8_UNorm => TextureFormat::Rgba8,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => TextureFormat::Srgb8Alpha8,
+        DxgiFormat::BC1_UNorm => TextureFormat::Bc1Rgba,
+        DxgiFormat::BC1_UNorm_sRGB => TextureFormat::Bc1SrgbAlpha,
+        DxgiFormat::BC2_UNorm => TextureFormat::Bc2,
+        DxgiFormat::BC2_UNorm_sRGB => TextureFormat::Bc2Srgb,
+        DxgiFormat::BC3_UNorm => TextureFormat::Bc3,
+        DxgiFormat::BC3_UNorm_sRGB => TextureFormat::Bc3Srgb,
+        DxgiFormat::BC4_UNorm => TextureFormat::Bc4,
+        DxgiFormat::BC4_SNorm => TextureFormat::Bc4Snorm,
+        DxgiFormat::BC5_UNorm => TextureFormat::Bc5,
+        DxgiFormat::BC5_SNorm => TextureFormat::Bc5Snorm,
+        DxgiFormat::BC6H_UF16 => TextureFormat::Bc6hUfloat,
+        DxgiFormat::BC6H_SF16 => TextureFormat::Bc6hSfloat,
+        DxgiFormat::BC7_UNorm => TextureFormat::Bc7,
+        DxgiFormat::BC7_UNorm_sRGB => TextureFormat::Bc7Srgb,
+        _ => return None,
+    })
+}
+
+fn format_from_d3d(format: D3DFormat) -> Option<TextureFormat> {
+    Some(match format {
+        D3DFormat::A8B8G8R8 => TextureFormat::Rgba8,
+        D3DFormat::DXT1 => TextureFormat::Bc1Rgba,
+        D3DFormat::DXT2 | D3DFormat::DXT3 => TextureFormat::Bc2,
+        D3DFormat::DXT4 | D3DFormat::DXT5 => TextureFormat::Bc3,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ddsfile::{AlphaMode, D3D10ResourceDimension, NewDxgiParams};
+
+    fn dds_bytes(width: u32, height: u32, levels: u32) -> Vec<u8> {
+        let mut dds = Dds::new_dxgi(NewDxgiParams {
+            height,
+            width,
+            depth: None,
+            format: DxgiFormat::BC1_UNorm,
+            mipmap_levels: Some(levels),
+            array_layers: None,
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })
+        .unwrap();
+        dds.data.fill(0xAB);
+        let mut bytes = vec![];
+        dds.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn dds_levels_are_sliced_by_size() {
+        let chain = MipChain::read_dds(&dds_bytes(8, 8, 4)).unwrap();
+        assert_eq!(chain.format, TextureFormat::Bc1Rgba);
+        let sizes: Vec<_> = chain.levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [32, 8, 8, 8]);
+    }
+
+    #[test]
+    fn truncated_dds_is_an_error() {
+        let bytes = dds_bytes(8, 8, 4);
+        let result = MipChain::read_dds(&bytes[..bytes.len() - 4]);
+        assert!(matches!(result, Err(TextureError::Container(_))));
+    }
+
+    #[test]
+    fn container_formats_map_to_texture_formats() {
+        use ktx2::Format as F;
+
+        let ktx2 = [
+            (F::BC7_SRGB_BLOCK, Some(TextureFormat::Bc7Srgb)),
+            (F::EAC_R11G11_SNORM_BLOCK, Some(TextureFormat::EacRg11Snorm)),
+            (F::ASTC_4x4_UNORM_BLOCK, TextureFormat::astc(4, 4, false)),
+            (F::ASTC_8x5_SRGB_BLOCK, TextureFormat::astc(8, 5, true)),
+            (F::ASTC_12x12_SRGB_BLOCK, TextureFormat::astc(12, 12, true)),
+            (F::R8_UNORM, None),
+        ];
+        for (format, expected) in ktx2 {
+            assert_eq!(format_from_ktx2(format), expected, "{format:?}");
+        }
+
+        let dxgi = [
+            (DxgiFormat::BC3_UNorm_sRGB, Some(TextureFormat::Bc3Srgb)),
+            (DxgiFormat::R16_Float, None),
+        ];
+        for (format, expected) in dxgi {
+            assert_eq!(format_from_dxgi(format), expected, "{format:?}");
+        }
+
+        assert_eq!(format_from_d3d(D3DFormat::DXT3), Some(TextureFormat::Bc2));
+        assert_eq!(format_from_d3d(D3DFormat::R5G6B5), None);
+    }
+}
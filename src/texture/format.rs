@@ -0,0 +1,364 @@
+use crate::{capabilities::Capabilities, renderer::gl};
+use gl::types::GLenum;
+
+/// Block compression schemes, grouped the way gl exposes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionFamily {
+    /// ETC2/EAC, core in GLES 3.0.
+    Etc2,
+    /// BC1-BC3 (DXT1-DXT5).
+    S3tc,
+    /// BC4-BC5.
+    Rgtc,
+    /// BC6H-BC7.
+    Bptc,
+    /// ASTC LDR.
+    Astc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    Srgb8Alpha8,
+    R16f,
+    Rg16f,
+    Rgba16f,
+    R32f,
+    Rg32f,
+    Rgba32f,
+    Depth16,
+    Depth24,
+    Depth32f,
+    Depth24Stencil8,
+    Depth32fStencil8,
+
+    Etc2Rgb8,
+    Etc2Srgb8,
+    Etc2Rgb8A1,
+    Etc2Srgb8A1,
+    Etc2Rgba8,
+    Etc2Srgb8Alpha8,
+    EacR11,
+    EacR11Snorm,
+    EacRg11,
+    EacRg11Snorm,
+
+    Bc1Rgb,
+    Bc1Srgb,
+    Bc1Rgba,
+    Bc1SrgbAlpha,
+    Bc2,
+    Bc2Srgb,
+    Bc3,
+    Bc3Srgb,
+    Bc4,
+    Bc4Snorm,
+    Bc5,
+    Bc5Snorm,
+    Bc6hUfloat,
+    Bc6hSfloat,
+    Bc7,
+    Bc7Srgb,
+
+    Astc {
+        block_width: u8,
+        block_height: u8,
+        srgb: bool,
+    },
+}
+
+#[rustfmt::skip]
+const ASTC_FORMATS: [(u8, u8, GLenum, GLenum); 14] = [
+    (4, 4, gl::COMPRESSED_RGBA_ASTC_4x4_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_4x4_KHR),
+    (5, 4, gl::COMPRESSED_RGBA_ASTC_5x4_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_5x4_KHR),
+    (5, 5, gl::COMPRESSED_RGBA_ASTC_5x5_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_5x5_KHR),
+    (6, 5, gl::COMPRESSED_RGBA_ASTC_6x5_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_6x5_KHR),
+    (6, 6, gl::COMPRESSED_RGBA_ASTC_6x6_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_6x6_KHR),
+    (8, 5, gl::COMPRESSED_RGBA_ASTC_8x5_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x5_KHR),
+    (8, 6, gl::COMPRESSED_RGBA_ASTC_8x6_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x6_KHR),
+    (8, 8, gl::COMPRESSED_RGBA_ASTC_8x8_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x8_KHR),
+    (10, 5, gl::COMPRESSED_RGBA_ASTC_10x5_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x5_KHR),
+    (10, 6, gl::COMPRESSED_RGBA_ASTC_10x6_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x6_KHR),
+    (10, 8, gl::COMPRESSED_RGBA_ASTC_10x8_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x8_KHR),
+    (10, 10, gl::COMPRESSED_RGBA_ASTC_10x10_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x10_KHR),
+    (12, 10, gl::COMPRESSED_RGBA_ASTC_12x10_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_12x10_KHR),
+    (12, 12, gl::COMPRESSED_RGBA_ASTC_12x12_KHR, gl::COMPRESSED_SRGB8_ALPHA8_ASTC_12x12_KHR),
+];
+
+impl TextureFormat {
+    /// The sized internal format passed to `glTexImage2D`/`glCompressedTexImage2D`.
+    pub fn gl_internal_format(&self) -> GLenum {
+        match self {
+            Self::R8 => gl::R8,
+            Self::Rg8 => gl::RG8,
+            Self::Rgb8 => gl::RGB8,
+            Self::Rgba8 => gl::RGBA8,
+            Self::Srgb8Alpha8 => gl::SRGB8_ALPHA8,
+            Self::R16f => gl::R16F,
+            Self::Rg16f => gl::RG16F,
+            Self::Rgba16f => gl::RGBA16F,
+            Self::R32f => gl::R32F,
+            Self::Rg32f => gl::RG32F,
+            Self::Rgba32f => gl::RGBA32F,
+            Self::Depth16 => gl::DEPTH_COMPONENT16,
+            Self::Depth24 => gl::DEPTH_COMPONENT24,
+            Self::Depth32f => gl::DEPTH_COMPONENT32F,
+            Self::Depth24Stencil8 => gl::DEPTH24_STENCIL8,
+            Self::Depth32fStencil8 => gl::DEPTH32F_STENCIL8,
+
+            Self::Etc2Rgb8 => gl::COMPRESSED_RGB8_ETC2,
+            Self::Etc2Srgb8 => gl::COMPRESSED_SRGB8_ETC2,
+            Self::Etc2Rgb8A1 => gl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            Self::Etc2Srgb8A1 => gl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            Self::Etc2Rgba8 => gl::COMPRESSED_RGBA8_ETC2_EAC,
+            Self::Etc2Srgb8Alpha8 => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+            Self::EacR11 => gl::COMPRESSED_R11_EAC,
+            Self::EacR11Snorm => gl::COMPRESSED_SIGNED_R11_EAC,
+            Self::EacRg11 => gl::COMPRESSED_RG11_EAC,
+            Self::EacRg11Snorm => gl::COMPRESSED_SIGNED_RG11_EAC,
+
+            Self::Bc1Rgb => gl::COMPRESSED_RGB_S3TC_DXT1_EXT,
+            Self::Bc1Srgb => gl::COMPRESSED_SRGB_S3TC_DXT1_EXT,
+            Self::Bc1Rgba => gl::COMPRESSED_RGBA_S3TC_DXT1_EXT,
+            Self::Bc1SrgbAlpha => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT,
+            Self::Bc2 => gl::COMPRESSED_RGBA_S3TC_DXT3_EXT,
+            Self::Bc2Srgb => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT,
+            Self::Bc3 => gl::COMPRESSED_RGBA_S3TC_DXT5_EXT,
+            Self::Bc3Srgb => gl::COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT,
+            Self::Bc4 => gl::COMPRESSED_RED_RGTC1_EXT,
+            Self::Bc4Snorm => gl::COMPRESSED_SIGNED_RED_RGTC1_EXT,
+            Self::Bc5 => gl::COMPRESSED_RED_GREEN_RGTC2_EXT,
+            Self::Bc5Snorm => gl::COMPRESSED_SIGNED_RED_GREEN_RGTC2_EXT,
+            Self::Bc6hUfloat => gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT_EXT,
+            Self::Bc6hSfloat => gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT_EXT,
+            Self::Bc7 => gl::COMPRESSED_RGBA_BPTC_UNORM_EXT,
+            Self::Bc7Srgb => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM_EXT,
+
+            Self::Astc {
+                block_width,
+                block_height,
+                srgb,
+            } => ASTC_FORMATS
+                .iter()
+                .find(|(w, h, ..)| w == block_width && h == block_height)
+                .map(|&(_, _, linear, srgb_format)| if *srgb { srgb_format } else { linear })
+                .expect("Invalid ASTC block size."),
+        }
+    }
+
+    /// The pixel transfer format used with `glTexImage2D`/`glReadPixels`.
+    ///
+    /// Compressed formats have none and panic.
+    pub fn gl_format(&self) -> GLenum {
+        match self {
+            Self::R8 | Self::R16f | Self::R32f => gl::RED,
+            Self::Rg8 | Self::Rg16f | Self::Rg32f => gl::RG,
+            Self::Rgb8 => gl::RGB,
+            Self::Rgba8 | Self::Srgb8Alpha8 | Self::Rgba16f | Self::Rgba32f => gl::RGBA,
+            Self::Depth16 | Self::Depth24 | Self::Depth32f => gl::DEPTH_COMPONENT,
+            Self::Depth24Stencil8 | Self::Depth32fStencil8 => gl::DEPTH_STENCIL,
+            _ => panic!("Compressed format {:?} has no pixel transfer format.", self),
+        }
+    }
+
+    /// The pixel transfer type matching [`TextureFormat::gl_format`].
+    pub fn gl_type(&self) -> GLenum {
+        match self {
+            Self::R8 | Self::Rg8 | Self::Rgb8 | Self::Rgba8 | Self::Srgb8Alpha8 => {
+                gl::UNSIGNED_BYTE
+            }
+            Self::R16f | Self::Rg16f | Self::Rgba16f => gl::HALF_FLOAT,
+            Self::R32f | Self::Rg32f | Self::Rgba32f | Self::Depth32f => gl::FLOAT,
+            Self::Depth16 => gl::UNSIGNED_SHORT,
+            Self::Depth24 => gl::UNSIGNED_INT,
+            Self::Depth24Stencil8 => gl::UNSIGNED_INT_24_8,
+            Self::Depth32fStencil8 => gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+            _ => panic!("Compressed format {:?} has no pixel transfer type.", self),
+        }
+    }
+
+    /// Build an ASTC format, `None` if the block footprint is not a legal 2D one.
+    pub fn astc(block_width: u8, block_height: u8, srgb: bool) -> Option<Self> {
+        ASTC_FORMATS
+            .iter()
+            .any(|&(w, h, ..)| w == block_width && h == block_height)
+            .then_some(Self::Astc {
+                block_width,
+                block_height,
+                srgb,
+            })
+    }
+
+    pub fn compression_family(&self) -> Option<CompressionFamily> {
+        match self {
+            Self::R8
+            | Self::Rg8
+            | Self::Rgb8
+            | Self::Rgba8
+            | Self::Srgb8Alpha8
+            | Self::R16f
+            | Self::Rg16f
+            | Self::Rgba16f
+            | Self::R32f
+            | Self::Rg32f
+            | Self::Rgba32f
+            | Self::Depth16
+            | Self::Depth24
+            | Self::Depth32f
+            | Self::Depth24Stencil8
+            | Self::Depth32fStencil8 => None,
+            Self::Etc2Rgb8
+            | Self::Etc2Srgb8
+            | Self::Etc2Rgb8A1
+            | Self::Etc2Srgb8A1
+            | Self::Etc2Rgba8
+            | Self::Etc2Srgb8Alpha8
+            | Self::EacR11
+            | Self::EacR11Snorm
+            | Self::EacRg11
+            | Self::EacRg11Snorm => Some(CompressionFamily::Etc2),
+            Self::Bc1Rgb
+            | Self::Bc1Srgb
+            | Self::Bc1Rgba
+            | Self::Bc1SrgbAlpha
+            | Self::Bc2
+            | Self::Bc2Srgb
+            | Self::Bc3
+            | Self::Bc3Srgb => Some(CompressionFamily::S3tc),
+            Self::Bc4 | Self::Bc4Snorm | Self::Bc5 | Self::Bc5Snorm => {
+                Some(CompressionFamily::Rgtc)
+            }
+            Self::Bc6hUfloat | Self::Bc6hSfloat | Self::Bc7 | Self::Bc7Srgb => {
+                Some(CompressionFamily::Bptc)
+            }
+            Self::Astc { .. } => Some(CompressionFamily::Astc),
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compression_family().is_some()
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            Self::Depth16
+                | Self::Depth24
+                | Self::Depth32f
+                | Self::Depth24Stencil8
+                | Self::Depth32fStencil8
+        )
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        matches!(self, Self::Depth24Stencil8 | Self::Depth32fStencil8)
+    }
+
+    /// Whether color reads come back as floats rather than normalized bytes.
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Self::R16f | Self::Rg16f | Self::Rgba16f | Self::R32f | Self::Rg32f | Self::Rgba32f
+        )
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            Self::Srgb8Alpha8
+                | Self::Etc2Srgb8
+                | Self::Etc2Srgb8A1
+                | Self::Etc2Srgb8Alpha8
+                | Self::Bc1Srgb
+                | Self::Bc1SrgbAlpha
+                | Self::Bc2Srgb
+                | Self::Bc3Srgb
+                | Self::Bc7Srgb
+                | Self::Astc { srgb: true, .. }
+        )
+    }
+
+    /// Texel footprint of one block, `(1, 1)` for uncompressed formats.
+    pub fn block_dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Astc {
+                block_width,
+                block_height,
+                ..
+            } => (*block_width as u32, *block_height as u32),
+            _ if self.is_compressed() => (4, 4),
+            _ => (1, 1),
+        }
+    }
+
+    /// Bytes per block, or per texel for uncompressed formats.
+    pub fn block_bytes(&self) -> u32 {
+        match self {
+            Self::R8 => 1,
+            Self::Rg8 | Self::R16f | Self::Depth16 => 2,
+            Self::Rgb8 => 3,
+            Self::Rgba8
+            | Self::Srgb8Alpha8
+            | Self::Rg16f
+            | Self::R32f
+            | Self::Depth24
+            | Self::Depth32f
+            | Self::Depth24Stencil8 => 4,
+            Self::Rgba16f | Self::Rg32f | Self::Depth32fStencil8 => 8,
+            Self::Rgba32f => 16,
+            Self::Etc2Rgb8
+            | Self::Etc2Srgb8
+            | Self::Etc2Rgb8A1
+            | Self::Etc2Srgb8A1
+            | Self::EacR11
+            | Self::EacR11Snorm
+            | Self::Bc1Rgb
+            | Self::Bc1Srgb
+            | Self::Bc1Rgba
+            | Self::Bc1SrgbAlpha
+            | Self::Bc4
+            | Self::Bc4Snorm => 8,
+            _ => 16,
+        }
+    }
+
+    /// Size in bytes of one mip level of the given dimensions.
+    pub fn level_size(&self, width: u32, height: u32) -> usize {
+        let (bw, bh) = self.block_dimensions();
+        let blocks_x = width.max(1).div_ceil(bw) as usize;
+        let blocks_y = height.max(1).div_ceil(bh) as usize;
+        blocks_x * blocks_y * self.block_bytes() as usize
+    }
+
+    /// Whether the current context can sample this format directly.
+    pub fn is_supported(&self, caps: &Capabilities) -> bool {
+        match self.compression_family() {
+            None => true,
+            Some(CompressionFamily::S3tc) if self.is_srgb() => caps.supports_s3tc_srgb(),
+            Some(family) => caps.supports_compression(family),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_sizes_round_up_to_whole_blocks() {
+        assert_eq!(TextureFormat::Rgba8.level_size(4, 4), 64);
+        assert_eq!(TextureFormat::Rgb8.level_size(3, 0), 9);
+        assert_eq!(TextureFormat::Rgba32f.level_size(2, 3), 96);
+        assert_eq!(TextureFormat::Bc1Rgba.level_size(5, 5), 32);
+        assert_eq!(TextureFormat::Bc3.level_size(1, 1), 16);
+        assert_eq!(TextureFormat::EacR11.level_size(8, 4), 16);
+
+        let astc = TextureFormat::astc(10, 8, false).unwrap();
+        assert_eq!(astc.block_dimensions(), (10, 8));
+        assert_eq!(astc.level_size(20, 20), 2 * 3 * 16);
+        assert_eq!(TextureFormat::astc(7, 7, false), None);
+    }
+}
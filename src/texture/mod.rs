@@ -0,0 +1,501 @@
+mod bcn;
+mod container;
+mod format;
+mod readback;
+mod sampler;
+mod streaming;
+
+pub use container::MipChain;
+pub use format::{CompressionFamily, TextureFormat};
+pub use readback::{
+    read_pixels_depth, read_pixels_rgba32f, read_pixels_rgba8, AsyncReadback, ReadbackFormat,
+};
+pub use sampler::{Filter, MipmapFilter, Sampler, SamplerCache, SamplerDesc, Wrap};
+pub use streaming::{StreamedTexture, TextureStreamer, DEFAULT_UPLOAD_BUDGET};
+
+use crate::{
+    capabilities::Capabilities,
+    gl_call,
+    renderer::gl::{self, types::GLuint},
+};
+use image::{
+    DynamicImage, EncodableLayout, ImageBuffer, ImageError, Luma, Rgba32FImage, RgbaImage,
+};
+use std::{fmt, path::PathBuf};
+use winit::dpi::Pixel;
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(PathBuf, std::io::Error),
+    /// The KTX2/DDS container is malformed or uses a layout we can not upload.
+    Container(String),
+    /// The file stores a pixel format that has no `TextureFormat` mapping.
+    UnsupportedFormat(String),
+    /// The gpu can not sample this format and there is no cpu decoder for it.
+    UnsupportedCompression(TextureFormat),
+    /// An encoded image, such as a PNG, could not be decoded.
+    Image(image::ImageError),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Can not read texture {}: {}", path.display(), e),
+            Self::Container(msg) => write!(f, "Invalid texture container: {}", msg),
+            Self::UnsupportedFormat(format) => write!(f, "Unsupported texture format: {}", format),
+            Self::UnsupportedCompression(format) => write!(
+                f,
+                "Compressed format {:?} is not supported by this gpu and can not be decoded on the cpu",
+                format
+            ),
+            Self::Image(e) => write!(f, "Can not decode image: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+/// How an image file is turned into a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureOptions {
+    /// Sampling state baked into the texture, used when no [`Sampler`] is bound.
+    pub sampler: SamplerDesc,
+    /// Generate the full mip chain after upload. Implied by a sampler with a
+    /// mipmap filter.
+    pub mipmaps: bool,
+    /// Flip the rows so the first image row ends up at `v = 1`.
+    pub flip_v: bool,
+    /// Store the texels as sRGB so sampling returns linear values.
+    pub srgb: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            sampler: SamplerDesc::default(),
+            mipmaps: false,
+            flip_v: true,
+            srgb: false,
+        }
+    }
+}
+
+pub struct Texture {
+    id: GLuint,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+    levels: u32,
+}
+
+impl Texture {
+    /// Load a texture from `res/textures`.
+    ///
+    /// `.ktx2` and `.dds` files keep their stored format and mip chain, see
+    /// [`Texture::load_compressed`]; everything else is decoded to RGBA8.
+    pub fn load(gl: &gl::Gl, caps: &Capabilities, name: &str) -> Self {
+        Self::load_with(gl, caps, name, &TextureOptions::default())
+    }
+
+    /// Like [`Texture::load`] with explicit [`TextureOptions`], which only
+    /// apply to decoded images.
+    pub fn load_with(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        name: &str,
+        options: &TextureOptions,
+    ) -> Self {
+        Self::try_load_with(gl, caps, name, options)
+            .unwrap_or_else(|e| panic!("Can not load texture {}: {}", name, e))
+    }
+
+    /// Like [`Texture::load_with`], returning missing and broken files as
+    /// errors.
+    pub fn try_load_with(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        name: &str,
+        options: &TextureOptions,
+    ) -> Result<Self, TextureError> {
+        if is_container(name) {
+            return Self::load_compressed(gl, caps, name);
+        }
+
+        let path = PathBuf::default().join("res/textures").join(name);
+        let img = image::open(&path).map_err(|e| match e {
+            ImageError::IoError(e) => TextureError::Io(path, e),
+            e => TextureError::Image(e),
+        })?;
+        Ok(Self::from_image(gl, img, options))
+    }
+
+    /// Decode an image file held in memory, such as one embedded in a model.
+    pub fn decode(
+        gl: &gl::Gl,
+        bytes: &[u8],
+        options: &TextureOptions,
+    ) -> Result<Self, TextureError> {
+        let img = image::load_from_memory(bytes).map_err(TextureError::Image)?;
+        Ok(Self::from_image(gl, img, options))
+    }
+
+    /// Upload a decoded image as RGBA8.
+    pub fn from_image(gl: &gl::Gl, mut img: DynamicImage, options: &TextureOptions) -> Self {
+        if options.flip_v {
+            img = img.flipv();
+        }
+        let img = img.to_rgba8();
+        let (width, height) = img.dimensions();
+        let bytes = img.as_bytes();
+
+        let format = if options.srgb {
+            TextureFormat::Srgb8Alpha8
+        } else {
+            TextureFormat::Rgba8
+        };
+
+        let mut id = 0;
+        gl_call!(gl, GenTextures(1, &mut id));
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, id));
+
+        options.sampler.apply_to_bound_texture(gl);
+
+        gl_call!(
+            gl,
+            TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format.gl_internal_format().cast(),
+                width.cast(),
+                height.cast(),
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                bytes.as_ptr() as *const _
+            )
+        );
+
+        let levels = if options.mipmaps || options.sampler.mipmap_filter != MipmapFilter::None {
+            gl_call!(gl, GenerateMipmap(gl::TEXTURE_2D));
+            32 - width.max(height).leading_zeros()
+        } else {
+            1
+        };
+
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+
+        Self {
+            id,
+            width,
+            height,
+            format,
+            levels,
+        }
+    }
+
+    /// Load a KTX2 or DDS texture from `res/textures` with its full mip chain.
+    ///
+    /// Block compressed data is uploaded as is when `caps` allows it. Otherwise
+    /// S3TC data is decoded to RGBA8 on the cpu, and any other format fails with
+    /// [`TextureError::UnsupportedCompression`].
+    ///
+    /// Unlike [`Texture::load`] the image is not flipped, containers are
+    /// expected to be authored with a bottom-left origin.
+    pub fn load_compressed(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        name: &str,
+    ) -> Result<Self, TextureError> {
+        let path = PathBuf::default().join("res/textures").join(name);
+        let bytes = std::fs::read(&path).map_err(|e| TextureError::Io(path.clone(), e))?;
+
+        let chain = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("ktx2") => MipChain::read_ktx2(&bytes)?,
+            Some(ext) if ext.eq_ignore_ascii_case("dds") => MipChain::read_dds(&bytes)?,
+            _ => return Err(TextureError::UnsupportedFormat(name.to_string())),
+        };
+
+        let chain = if chain.format.is_supported(caps) {
+            chain
+        } else {
+            chain.decompress()?
+        };
+
+        Ok(Self::from_mip_chain(gl, &chain))
+    }
+
+    /// Create a texture of the given size and format with undefined content,
+    /// to be filled with [`Texture::update`] or rendered to.
+    ///
+    /// Formats `caps` can not filter linearly are point sampled, so the
+    /// texture is complete everywhere.
+    pub fn new(
+        gl: &gl::Gl,
+        caps: &Capabilities,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> Self {
+        assert!(
+            !format.is_compressed(),
+            "Empty textures can not use compressed format {:?}.",
+            format
+        );
+
+        let mut id = 0;
+        gl_call!(gl, GenTextures(1, &mut id));
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, id));
+
+        if caps.is_filterable(format) {
+            SamplerDesc::bilinear().apply_to_bound_texture(gl);
+        } else {
+            SamplerDesc::point().apply_to_bound_texture(gl);
+        }
+
+        gl_call!(
+            gl,
+            TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format.gl_internal_format().cast(),
+                width.cast(),
+                height.cast(),
+                0,
+                format.gl_format(),
+                format.gl_type(),
+                std::ptr::null()
+            )
+        );
+
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+
+        Self {
+            id,
+            width,
+            height,
+            format,
+            levels: 1,
+        }
+    }
+
+    /// Replace a rectangle of the base level with tightly packed `data` in the
+    /// texture's own pixel transfer format, e.g. for video frames.
+    pub fn update(&self, gl: &gl::Gl, x: u32, y: u32, width: u32, height: u32, data: &[u8]) {
+        let right = x.checked_add(width);
+        let bottom = y.checked_add(height);
+        assert!(
+            right.is_some_and(|right| right <= self.width)
+                && bottom.is_some_and(|bottom| bottom <= self.height),
+            "Update region is out of the texture bounds."
+        );
+        assert_eq!(
+            data.len(),
+            self.format.level_size(width, height),
+            "Update data does not match the region size."
+        );
+
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, self.id));
+        gl_call!(gl, PixelStorei(gl::UNPACK_ALIGNMENT, 1));
+        gl_call!(
+            gl,
+            TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x.cast(),
+                y.cast(),
+                width.cast(),
+                height.cast(),
+                self.format.gl_format(),
+                self.format.gl_type(),
+                data.as_ptr() as *const _
+            )
+        );
+        gl_call!(gl, PixelStorei(gl::UNPACK_ALIGNMENT, 4));
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+    }
+
+    /// Read the base level of a color texture back, see [`read_pixels_rgba8`].
+    pub fn read_rgba8(&self, gl: &gl::Gl) -> RgbaImage {
+        assert!(
+            !self.format.is_depth(),
+            "Depth texture {} can not be read as color, use read_depth.",
+            self.id
+        );
+        readback::with_texture_read_framebuffer(gl, self.id, self.format, || {
+            read_pixels_rgba8(gl, 0, 0, self.width, self.height)
+        })
+    }
+
+    /// Read the base level of a float texture back, see [`read_pixels_rgba32f`].
+    pub fn read_rgba32f(&self, gl: &gl::Gl) -> Rgba32FImage {
+        assert!(
+            self.format.is_float(),
+            "Texture {} of format {:?} can not be read as floats, use read_rgba8.",
+            self.id,
+            self.format
+        );
+        readback::with_texture_read_framebuffer(gl, self.id, self.format, || {
+            read_pixels_rgba32f(gl, 0, 0, self.width, self.height)
+        })
+    }
+
+    /// Read the base level of a depth texture back, see [`read_pixels_depth`].
+    /// `None` unless `caps` can read depth.
+    pub fn read_depth(
+        &self,
+        gl: &gl::Gl,
+        caps: &Capabilities,
+    ) -> Option<ImageBuffer<Luma<f32>, Vec<f32>>> {
+        assert!(
+            self.format.is_depth(),
+            "Texture {} of format {:?} has no depth.",
+            self.id,
+            self.format
+        );
+        if !caps.can_read_depth() {
+            return None;
+        }
+        let image = readback::with_texture_read_framebuffer(gl, self.id, self.format, || {
+            read_pixels_depth(gl, 0, 0, self.width, self.height)
+        });
+        Some(image)
+    }
+
+    /// Queue a non-blocking read of the base level through a pixel pack
+    /// buffer. `None` for depth textures unless `caps` can read depth.
+    pub fn read_async(&self, gl: &gl::Gl, caps: &Capabilities) -> Option<AsyncReadback> {
+        if self.format.is_depth() && !caps.can_read_depth() {
+            return None;
+        }
+        let format = if self.format.is_depth() {
+            ReadbackFormat::Depth32f
+        } else if self.format.is_float() {
+            ReadbackFormat::Rgba32f
+        } else {
+            ReadbackFormat::Rgba8
+        };
+
+        let readback = readback::with_texture_read_framebuffer(gl, self.id, self.format, || {
+            AsyncReadback::start(gl, 0, 0, self.width, self.height, format)
+        });
+        Some(readback)
+    }
+
+    /// Upload every level of `chain`, the format must be supported by the context.
+    pub fn from_mip_chain(gl: &gl::Gl, chain: &MipChain) -> Self {
+        let format = chain.format;
+        let levels = chain.levels.len() as u32;
+
+        let mut id = 0;
+        gl_call!(gl, GenTextures(1, &mut id));
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, id));
+
+        if levels > 1 {
+            SamplerDesc::trilinear().apply_to_bound_texture(gl);
+        } else {
+            SamplerDesc::bilinear().apply_to_bound_texture(gl);
+        }
+        gl_call!(
+            gl,
+            TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, (levels - 1) as i32)
+        );
+
+        for (level, data) in chain.levels.iter().enumerate() {
+            let width = (chain.width >> level).max(1);
+            let height = (chain.height >> level).max(1);
+
+            if format.is_compressed() {
+                let size = format.level_size(width, height);
+                gl_call!(
+                    gl,
+                    CompressedTexImage2D(
+                        gl::TEXTURE_2D,
+                        level as i32,
+                        format.gl_internal_format(),
+                        width.cast(),
+                        height.cast(),
+                        0,
+                        size as i32,
+                        data.as_ptr() as *const _
+                    )
+                );
+            } else {
+                gl_call!(
+                    gl,
+                    TexImage2D(
+                        gl::TEXTURE_2D,
+                        level as i32,
+                        format.gl_internal_format().cast(),
+                        width.cast(),
+                        height.cast(),
+                        0,
+                        format.gl_format(),
+                        format.gl_type(),
+                        data.as_ptr() as *const _
+                    )
+                );
+            }
+        }
+
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+
+        Self {
+            id,
+            width: chain.width,
+            height: chain.height,
+            format,
+            levels,
+        }
+    }
+
+    /// Bind to texture unit `slot`, sampled with `sampler` if given or with the
+    /// state baked into the texture otherwise.
+    pub fn bind(&self, gl: &gl::Gl, slot: i32, sampler: Option<&Sampler>) {
+        bind_texture(gl, self.id, slot, sampler);
+    }
+
+    pub fn unbind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe { gl.DeleteTextures(1, &self.id) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Number of mip levels uploaded.
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+}
+
+fn bind_texture(gl: &gl::Gl, id: GLuint, slot: i32, sampler: Option<&Sampler>) {
+    if slot >= 32 || slot < 0 {
+        panic!("Texture slot only support 0-31.");
+    }
+    gl_call!(gl, ActiveTexture(gl::TEXTURE0 + slot as u32));
+    gl_call!(gl, BindTexture(gl::TEXTURE_2D, id));
+
+    // Always rebind, a sampler left on the unit would override the texture's own state.
+    gl_call!(gl, BindSampler(slot as u32, sampler.map_or(0, |s| s.id())));
+}
+
+fn is_container(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".ktx2") || name.ends_with(".dds")
+}
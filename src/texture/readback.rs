@@ -0,0 +1,311 @@
+use super::TextureFormat;
+use crate::{
+    gl_call,
+    renderer::gl::{self, types::GLuint},
+};
+use image::{ImageBuffer, Luma, Rgba, Rgba32FImage, RgbaImage};
+use std::ptr::null;
+
+/// Pixel type a readback is transferred as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadbackFormat {
+    /// `GL_RGBA`/`GL_UNSIGNED_BYTE`, valid for every normalized color buffer.
+    Rgba8,
+    /// `GL_RGBA`/`GL_FLOAT`, for float color buffers.
+    Rgba32f,
+    /// `GL_DEPTH_COMPONENT`/`GL_FLOAT`, for depth buffers where
+    /// [`crate::capabilities::Capabilities::can_read_depth`].
+    Depth32f,
+}
+
+impl ReadbackFormat {
+    fn gl_format(&self) -> gl::types::GLenum {
+        match self {
+            Self::Rgba8 | Self::Rgba32f => gl::RGBA,
+            Self::Depth32f => gl::DEPTH_COMPONENT,
+        }
+    }
+
+    fn gl_type(&self) -> gl::types::GLenum {
+        match self {
+            Self::Rgba8 => gl::UNSIGNED_BYTE,
+            Self::Rgba32f | Self::Depth32f => gl::FLOAT,
+        }
+    }
+
+    fn texel_bytes(&self) -> usize {
+        match self {
+            Self::Rgba8 | Self::Depth32f => 4,
+            Self::Rgba32f => 16,
+        }
+    }
+}
+
+/// Read a rectangle of the bound read framebuffer as RGBA8.
+///
+/// Rows are flipped so the image has a top-left origin like any other
+/// `image` buffer.
+pub fn read_pixels_rgba8(gl: &gl::Gl, x: i32, y: i32, width: u32, height: u32) -> RgbaImage {
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    read_pixels(
+        gl,
+        x,
+        y,
+        width,
+        height,
+        ReadbackFormat::Rgba8,
+        pixels.as_mut_ptr() as *mut _,
+    );
+
+    let image = ImageBuffer::from_raw(width, height, pixels).unwrap();
+    image::imageops::flip_vertical(&image)
+}
+
+/// Read a rectangle of the bound read framebuffer, which must be a float
+/// color buffer, as RGBA32F.
+pub fn read_pixels_rgba32f(gl: &gl::Gl, x: i32, y: i32, width: u32, height: u32) -> Rgba32FImage {
+    let mut pixels = vec![0f32; width as usize * height as usize * 4];
+    read_pixels(
+        gl,
+        x,
+        y,
+        width,
+        height,
+        ReadbackFormat::Rgba32f,
+        pixels.as_mut_ptr() as *mut _,
+    );
+
+    let image: Rgba32FImage = ImageBuffer::from_raw(width, height, pixels).unwrap();
+    image::imageops::flip_vertical(&image)
+}
+
+/// Read a rectangle of the bound read framebuffer's depth buffer, see
+/// [`ReadbackFormat::Depth32f`].
+pub fn read_pixels_depth(
+    gl: &gl::Gl,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> ImageBuffer<Luma<f32>, Vec<f32>> {
+    let mut pixels = vec![0f32; width as usize * height as usize];
+    read_pixels(
+        gl,
+        x,
+        y,
+        width,
+        height,
+        ReadbackFormat::Depth32f,
+        pixels.as_mut_ptr() as *mut _,
+    );
+
+    let image: ImageBuffer<Luma<f32>, _> = ImageBuffer::from_raw(width, height, pixels).unwrap();
+    image::imageops::flip_vertical(&image)
+}
+
+fn read_pixels(
+    gl: &gl::Gl,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    format: ReadbackFormat,
+    dst: *mut std::ffi::c_void,
+) {
+    let mut alignment = 4;
+    gl_call!(gl, GetIntegerv(gl::PACK_ALIGNMENT, &mut alignment));
+    gl_call!(gl, PixelStorei(gl::PACK_ALIGNMENT, 1));
+    gl_call!(
+        gl,
+        ReadPixels(
+            x,
+            y,
+            width as i32,
+            height as i32,
+            format.gl_format(),
+            format.gl_type(),
+            dst
+        )
+    );
+    gl_call!(gl, PixelStorei(gl::PACK_ALIGNMENT, alignment));
+}
+
+/// Run `f` with `texture`, of `format`, attached to a temporary read
+/// framebuffer.
+pub(super) fn with_texture_read_framebuffer<R>(
+    gl: &gl::Gl,
+    texture: GLuint,
+    format: TextureFormat,
+    f: impl FnOnce() -> R,
+) -> R {
+    let mut previous = 0;
+    gl_call!(gl, GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous));
+
+    let point = if format.has_stencil() {
+        gl::DEPTH_STENCIL_ATTACHMENT
+    } else if format.is_depth() {
+        gl::DEPTH_ATTACHMENT
+    } else {
+        gl::COLOR_ATTACHMENT0
+    };
+
+    let mut fbo = 0;
+    gl_call!(gl, GenFramebuffers(1, &mut fbo));
+    gl_call!(gl, BindFramebuffer(gl::READ_FRAMEBUFFER, fbo));
+    gl_call!(
+        gl,
+        FramebufferTexture2D(gl::READ_FRAMEBUFFER, point, gl::TEXTURE_2D, texture, 0)
+    );
+    if format.is_depth() {
+        // There is no color attachment to read from.
+        gl_call!(gl, ReadBuffer(gl::NONE));
+    }
+
+    let status = gl_call!(gl, CheckFramebufferStatus(gl::READ_FRAMEBUFFER));
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        panic!(
+            "Texture {} can not be read back, framebuffer status 0x{:04X}.",
+            texture, status
+        );
+    }
+
+    let res = f();
+
+    gl_call!(
+        gl,
+        BindFramebuffer(gl::READ_FRAMEBUFFER, previous as GLuint)
+    );
+    unsafe { gl.DeleteFramebuffers(1, &fbo) };
+
+    res
+}
+
+/// A `glReadPixels` into a pixel pack buffer, completed asynchronously.
+///
+/// The copy is queued by [`AsyncReadback::start`] and guarded by a fence, poll
+/// with [`AsyncReadback::is_ready`] and collect the image once it signals
+/// instead of stalling the pipeline.
+pub struct AsyncReadback {
+    pbo: GLuint,
+    sync: gl::types::GLsync,
+    width: u32,
+    height: u32,
+    format: ReadbackFormat,
+}
+
+impl AsyncReadback {
+    /// Queue a read of a rectangle of the bound read framebuffer.
+    pub fn start(
+        gl: &gl::Gl,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        format: ReadbackFormat,
+    ) -> Self {
+        let size = width as usize * height as usize * format.texel_bytes();
+
+        let mut pbo = 0;
+        gl_call!(gl, GenBuffers(1, &mut pbo));
+        gl_call!(gl, BindBuffer(gl::PIXEL_PACK_BUFFER, pbo));
+        gl_call!(
+            gl,
+            BufferData(
+                gl::PIXEL_PACK_BUFFER,
+                size as isize,
+                null(),
+                gl::STREAM_READ
+            )
+        );
+
+        // With a pack buffer bound the pointer is an offset into it.
+        read_pixels(gl, x, y, width, height, format, std::ptr::null_mut());
+
+        gl_call!(gl, BindBuffer(gl::PIXEL_PACK_BUFFER, 0));
+        let sync = gl_call!(gl, FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+
+        Self {
+            pbo,
+            sync,
+            width,
+            height,
+            format,
+        }
+    }
+
+    /// Whether the gpu has finished the copy, never blocks.
+    pub fn is_ready(&self, gl: &gl::Gl) -> bool {
+        let res = gl_call!(gl, ClientWaitSync(self.sync, 0, 0));
+        res == gl::ALREADY_SIGNALED || res == gl::CONDITION_SATISFIED
+    }
+
+    /// Collect the image as RGBA8, blocking until the copy is done.
+    pub fn into_rgba8(self, gl: &gl::Gl) -> RgbaImage {
+        let bytes = self.map(gl);
+        let pixels = match self.format {
+            ReadbackFormat::Rgba8 => bytes,
+            ReadbackFormat::Rgba32f => floats(&bytes)
+                .map(|v| (v.clamp(0., 1.) * 255. + 0.5) as u8)
+                .collect(),
+            ReadbackFormat::Depth32f => floats(&bytes)
+                .flat_map(|v| {
+                    let v = (v.clamp(0., 1.) * 255. + 0.5) as u8;
+                    [v, v, v, 255]
+                })
+                .collect(),
+        };
+        self.drop(gl);
+
+        let image = ImageBuffer::from_raw(self.width, self.height, pixels).unwrap();
+        image::imageops::flip_vertical(&image)
+    }
+
+    /// Collect the image as RGBA32F, blocking until the copy is done.
+    pub fn into_rgba32f(self, gl: &gl::Gl) -> Rgba32FImage {
+        let bytes = self.map(gl);
+        let pixels: Vec<f32> = match self.format {
+            ReadbackFormat::Rgba8 => bytes.iter().map(|&v| v as f32 / 255.).collect(),
+            ReadbackFormat::Rgba32f => floats(&bytes).collect(),
+            // Depth as grey.
+            ReadbackFormat::Depth32f => floats(&bytes).flat_map(|v| [v, v, v, 1.]).collect(),
+        };
+        self.drop(gl);
+
+        let image: ImageBuffer<Rgba<f32>, _> =
+            ImageBuffer::from_raw(self.width, self.height, pixels).unwrap();
+        image::imageops::flip_vertical(&image)
+    }
+
+    fn map(&self, gl: &gl::Gl) -> Vec<u8> {
+        gl_call!(
+            gl,
+            ClientWaitSync(self.sync, gl::SYNC_FLUSH_COMMANDS_BIT, gl::TIMEOUT_IGNORED)
+        );
+
+        let size = self.width as usize * self.height as usize * self.format.texel_bytes();
+        gl_call!(gl, BindBuffer(gl::PIXEL_PACK_BUFFER, self.pbo));
+        let ptr = gl_call!(
+            gl,
+            MapBufferRange(gl::PIXEL_PACK_BUFFER, 0, size as isize, gl::MAP_READ_BIT)
+        );
+        let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, size) }.to_vec();
+        gl_call!(gl, UnmapBuffer(gl::PIXEL_PACK_BUFFER));
+        gl_call!(gl, BindBuffer(gl::PIXEL_PACK_BUFFER, 0));
+
+        bytes
+    }
+
+    /// Release the buffer and fence, also discards a readback that is never collected.
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe {
+            gl.DeleteSync(self.sync);
+            gl.DeleteBuffers(1, &self.pbo);
+        }
+    }
+}
+
+fn floats(bytes: &[u8]) -> impl Iterator<Item = f32> + '_ {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
@@ -0,0 +1,192 @@
+use crate::{
+    gl_call,
+    renderer::gl::{self, types::GLenum, types::GLuint},
+};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+/// How texels are picked between mip levels, `None` samples the base level only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MipmapFilter {
+    None,
+    Nearest,
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wrap {
+    ClampToEdge,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl Wrap {
+    fn gl_enum(&self) -> GLenum {
+        match self {
+            Self::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Self::Repeat => gl::REPEAT,
+            Self::MirroredRepeat => gl::MIRRORED_REPEAT,
+        }
+    }
+}
+
+/// Filter and wrap state, either baked into a texture or held by a [`Sampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerDesc {
+    pub min_filter: Filter,
+    pub mag_filter: Filter,
+    pub mipmap_filter: MipmapFilter,
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+}
+
+impl Default for SamplerDesc {
+    /// Bilinear and clamped, what [`super::Texture::load`] has always used.
+    fn default() -> Self {
+        Self::bilinear()
+    }
+}
+
+impl SamplerDesc {
+    pub fn point() -> Self {
+        Self {
+            min_filter: Filter::Nearest,
+            mag_filter: Filter::Nearest,
+            mipmap_filter: MipmapFilter::None,
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+        }
+    }
+
+    pub fn bilinear() -> Self {
+        Self {
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+            mipmap_filter: MipmapFilter::None,
+            wrap_s: Wrap::ClampToEdge,
+            wrap_t: Wrap::ClampToEdge,
+        }
+    }
+
+    pub fn trilinear() -> Self {
+        Self {
+            mipmap_filter: MipmapFilter::Linear,
+            ..Self::bilinear()
+        }
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    fn gl_min_filter(&self) -> GLenum {
+        match (self.min_filter, self.mipmap_filter) {
+            (Filter::Nearest, MipmapFilter::None) => gl::NEAREST,
+            (Filter::Linear, MipmapFilter::None) => gl::LINEAR,
+            (Filter::Nearest, MipmapFilter::Nearest) => gl::NEAREST_MIPMAP_NEAREST,
+            (Filter::Linear, MipmapFilter::Nearest) => gl::LINEAR_MIPMAP_NEAREST,
+            (Filter::Nearest, MipmapFilter::Linear) => gl::NEAREST_MIPMAP_LINEAR,
+            (Filter::Linear, MipmapFilter::Linear) => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    fn gl_mag_filter(&self) -> GLenum {
+        match self.mag_filter {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+        }
+    }
+
+    fn parameters(&self) -> [(GLenum, GLenum); 4] {
+        [
+            (gl::TEXTURE_MIN_FILTER, self.gl_min_filter()),
+            (gl::TEXTURE_MAG_FILTER, self.gl_mag_filter()),
+            (gl::TEXTURE_WRAP_S, self.wrap_s.gl_enum()),
+            (gl::TEXTURE_WRAP_T, self.wrap_t.gl_enum()),
+        ]
+    }
+
+    /// Bake this state into the texture bound to `GL_TEXTURE_2D`.
+    pub fn apply_to_bound_texture(&self, gl: &gl::Gl) {
+        for (name, value) in self.parameters() {
+            gl_call!(gl, TexParameteri(gl::TEXTURE_2D, name, value as i32));
+        }
+    }
+}
+
+/// A GLES 3.0 sampler object, overriding the sampling state of whatever
+/// texture is bound to the same unit.
+pub struct Sampler {
+    id: GLuint,
+    desc: SamplerDesc,
+}
+
+impl Sampler {
+    pub fn new(gl: &gl::Gl, desc: SamplerDesc) -> Self {
+        let mut id = 0;
+        gl_call!(gl, GenSamplers(1, &mut id));
+
+        for (name, value) in desc.parameters() {
+            gl_call!(gl, SamplerParameteri(id, name, value as i32));
+        }
+
+        Self { id, desc }
+    }
+
+    pub fn bind(&self, gl: &gl::Gl, slot: i32) {
+        gl_call!(gl, BindSampler(slot as u32, self.id));
+    }
+
+    pub fn unbind(&self, gl: &gl::Gl, slot: i32) {
+        gl_call!(gl, BindSampler(slot as u32, 0));
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe { gl.DeleteSamplers(1, &self.id) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn desc(&self) -> &SamplerDesc {
+        &self.desc
+    }
+}
+
+/// Hands out one shared [`Sampler`] per unique [`SamplerDesc`].
+#[derive(Default)]
+pub struct SamplerCache {
+    samplers: RefCell<HashMap<SamplerDesc, Rc<Sampler>>>,
+}
+
+impl SamplerCache {
+    pub fn get(&self, gl: &gl::Gl, desc: SamplerDesc) -> Rc<Sampler> {
+        self.samplers
+            .borrow_mut()
+            .entry(desc)
+            .or_insert_with(|| Rc::new(Sampler::new(gl, desc)))
+            .clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samplers.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samplers.borrow().is_empty()
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        for (_, sampler) in self.samplers.borrow_mut().drain() {
+            Sampler::drop(&sampler, gl);
+        }
+    }
+}
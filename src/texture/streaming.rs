@@ -0,0 +1,458 @@
+use super::{bind_texture, Sampler, SamplerDesc, Texture, TextureFormat};
+use crate::{
+    error::gl_clear_error,
+    gl_call,
+    renderer::gl::{self, types::GLuint},
+};
+use image::RgbaImage;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    ptr::{copy_nonoverlapping, null},
+    rc::Rc,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use winit::dpi::Pixel;
+
+/// Default amount of pixel data pushed to the gpu per frame.
+pub const DEFAULT_UPLOAD_BUDGET: usize = 4 * 1024 * 1024;
+
+/// Number of pixel unpack buffers cycled through, so that writing the next
+/// chunk does not wait on the previous transfer.
+const PBO_COUNT: usize = 3;
+
+const PLACEHOLDER_SIZE: u32 = 8;
+
+struct DecodeJob {
+    job: usize,
+    path: PathBuf,
+}
+
+struct DecodeResult {
+    job: usize,
+    image: Result<RgbaImage, String>,
+}
+
+struct StreamSlot {
+    id: Cell<GLuint>,
+    size: Cell<(u32, u32)>,
+    ready: Cell<bool>,
+    /// The uploaded image, owned by the slot once it is ready.
+    texture: RefCell<Option<Texture>>,
+    /// Set by [`StreamedTexture::drop`], the streamer skips the rest of the
+    /// decode and upload.
+    released: Cell<bool>,
+}
+
+/// Handle to a texture loaded by [`TextureStreamer`].
+///
+/// Binds a placeholder until the decoded image has been fully uploaded. The
+/// uploaded texture has to be deleted with [`StreamedTexture::drop`], or is
+/// deleted along with the handle from
+/// [`crate::resources::ResourceManager::stream_texture`].
+///
+/// It must not outlive its streamer, whose placeholder it may still bind.
+/// Using it afterwards panics, and the manager's handles go stale with the
+/// streamer.
+#[derive(Clone)]
+pub struct StreamedTexture {
+    slot: Rc<StreamSlot>,
+    placeholder: GLuint,
+    /// Cleared by [`TextureStreamer::drop`].
+    streamer_alive: Rc<Cell<bool>>,
+}
+
+impl StreamedTexture {
+    /// See [`Texture::bind`].
+    pub fn bind(&self, gl: &gl::Gl, slot: i32, sampler: Option<&Sampler>) {
+        bind_texture(gl, self.slot().id.get(), slot, sampler);
+    }
+
+    pub fn unbind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+    }
+
+    /// Whether the real image is bound instead of the placeholder.
+    pub fn is_ready(&self) -> bool {
+        self.slot().ready.get()
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.slot().id.get()
+    }
+
+    /// Size of the currently bound image.
+    pub fn size(&self) -> (u32, u32) {
+        self.slot().size.get()
+    }
+
+    fn slot(&self) -> &StreamSlot {
+        assert!(
+            self.streamer_alive.get(),
+            "Streamed texture used after its streamer was dropped."
+        );
+        &self.slot
+    }
+
+    /// Delete the uploaded texture, or cancel the load if it is still on its
+    /// way. Clones bind the placeholder from then on.
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.slot.released.set(true);
+        self.slot.ready.set(false);
+        self.slot.id.set(self.placeholder);
+        if let Some(texture) = self.slot.texture.borrow_mut().take() {
+            texture.drop(gl);
+        }
+    }
+}
+
+struct PendingUpload {
+    slot: Rc<StreamSlot>,
+    texture: Texture,
+    image: RgbaImage,
+    next_row: u32,
+}
+
+#[derive(Default)]
+struct StreamState {
+    /// Slots waiting on a decode job, by job number.
+    decoding: HashMap<usize, Rc<StreamSlot>>,
+    next_job: usize,
+    uploads: VecDeque<PendingUpload>,
+}
+
+/// Decodes images on a worker pool and uploads them on the gl thread through
+/// pixel unpack buffers, at most `budget` bytes per [`TextureStreamer::update`].
+pub struct TextureStreamer {
+    jobs: Sender<DecodeJob>,
+    results: Receiver<DecodeResult>,
+    _workers: Vec<JoinHandle<()>>,
+    placeholder: Texture,
+    pbos: [GLuint; PBO_COUNT],
+    next_pbo: Cell<usize>,
+    budget: usize,
+    state: RefCell<StreamState>,
+    /// Shared with every [`StreamedTexture`] handed out.
+    alive: Rc<Cell<bool>>,
+}
+
+impl TextureStreamer {
+    pub fn new(gl: &gl::Gl, budget: usize) -> Self {
+        let workers = thread::available_parallelism()
+            .map(|n| n.get().clamp(1, 4))
+            .unwrap_or(2);
+
+        let (job_tx, job_rx) = channel::<DecodeJob>();
+        let (result_tx, result_rx) = channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let workers = (0..workers)
+            .map(|i| {
+                let jobs = job_rx.clone();
+                let results = result_tx.clone();
+                thread::Builder::new()
+                    .name(format!("texture-decode-{}", i))
+                    .spawn(move || decode_worker(jobs, results))
+                    .expect("Spawn texture decode worker failed.")
+            })
+            .collect();
+
+        let mut pbos = [0; PBO_COUNT];
+        gl_call!(gl, GenBuffers(PBO_COUNT as i32, pbos.as_mut_ptr()));
+
+        Self {
+            jobs: job_tx,
+            results: result_rx,
+            _workers: workers,
+            placeholder: create_placeholder(gl),
+            pbos,
+            next_pbo: Cell::new(0),
+            budget: budget.max(1),
+            state: RefCell::new(StreamState::default()),
+            alive: Rc::new(Cell::new(true)),
+        }
+    }
+
+    /// Queue `name` from `res/textures` for background decoding.
+    pub fn load(&self, name: &str) -> StreamedTexture {
+        let slot = Rc::new(StreamSlot {
+            id: Cell::new(self.placeholder.id()),
+            size: Cell::new((self.placeholder.width(), self.placeholder.height())),
+            ready: Cell::new(false),
+            texture: RefCell::new(None),
+            released: Cell::new(false),
+        });
+
+        let mut state = self.state.borrow_mut();
+        let job = state.next_job;
+        state.next_job += 1;
+        state.decoding.insert(job, slot.clone());
+
+        self.jobs
+            .send(DecodeJob {
+                job,
+                path: PathBuf::default().join("res/textures").join(name),
+            })
+            .expect("Texture decode workers are gone.");
+
+        StreamedTexture {
+            slot,
+            placeholder: self.placeholder.id(),
+            streamer_alive: self.alive.clone(),
+        }
+    }
+
+    /// Whether any texture is still being decoded or uploaded.
+    pub fn is_busy(&self) -> bool {
+        let state = self.state.borrow();
+        !state.decoding.is_empty() || !state.uploads.is_empty()
+    }
+
+    /// Collect finished decodes and upload up to the byte budget, call once per frame.
+    pub fn update(&self, gl: &gl::Gl) {
+        let mut state = self.state.borrow_mut();
+
+        while let Ok(DecodeResult { job, image }) = self.results.try_recv() {
+            let Some(slot) = state.decoding.remove(&job) else {
+                continue;
+            };
+            if slot.released.get() {
+                continue;
+            }
+
+            match image {
+                Ok(image) => {
+                    let texture = allocate_texture(gl, image.width(), image.height());
+                    state.uploads.push_back(PendingUpload {
+                        slot,
+                        texture,
+                        image,
+                        next_row: 0,
+                    });
+                }
+                Err(e) => eprintln!("Texture streaming failed: {}", e),
+            }
+        }
+
+        let mut budget = self.budget;
+        while budget > 0 {
+            let Some(upload) = state.uploads.front_mut() else {
+                break;
+            };
+            if upload.slot.released.get() {
+                state.uploads.pop_front().unwrap().texture.drop(gl);
+                continue;
+            }
+
+            let (width, height) = upload.image.dimensions();
+            let row_bytes = width as usize * 4;
+            let rows = (budget / row_bytes).clamp(1, (height - upload.next_row) as usize) as u32;
+            let start = upload.next_row as usize * row_bytes;
+            let data = &upload.image.as_raw()[start..start + rows as usize * row_bytes];
+
+            self.upload_rows(gl, &upload.texture, upload.next_row, width, rows, data);
+
+            upload.next_row += rows;
+            budget = budget.saturating_sub(data.len());
+
+            if upload.next_row == height {
+                let upload = state.uploads.pop_front().unwrap();
+                upload.slot.id.set(upload.texture.id());
+                upload.slot.size.set((width, height));
+                upload.slot.ready.set(true);
+                *upload.slot.texture.borrow_mut() = Some(upload.texture);
+            }
+        }
+    }
+
+    /// Delete the placeholder and cancel unfinished uploads. Finished
+    /// textures belong to their [`StreamedTexture`], which can only be
+    /// dropped from then on.
+    pub fn drop(&self, gl: &gl::Gl) {
+        self.alive.set(false);
+        let state = self.state.borrow();
+        for upload in state.uploads.iter() {
+            upload.texture.drop(gl);
+        }
+        self.placeholder.drop(gl);
+
+        unsafe { gl.DeleteBuffers(PBO_COUNT as i32, self.pbos.as_ptr()) }
+    }
+
+    fn upload_rows(
+        &self,
+        gl: &gl::Gl,
+        texture: &Texture,
+        y: u32,
+        width: u32,
+        rows: u32,
+        data: &[u8],
+    ) {
+        let pbo = self.pbos[self.next_pbo.get()];
+        self.next_pbo.set((self.next_pbo.get() + 1) % PBO_COUNT);
+
+        gl_call!(gl, BindBuffer(gl::PIXEL_UNPACK_BUFFER, pbo));
+        // Orphan the previous storage so the driver does not have to wait on it.
+        gl_call!(
+            gl,
+            BufferData(
+                gl::PIXEL_UNPACK_BUFFER,
+                data.len() as isize,
+                null(),
+                gl::STREAM_DRAW
+            )
+        );
+        // Not a `gl_call!`, a failed map is not fatal.
+        let ptr = unsafe {
+            gl.MapBufferRange(
+                gl::PIXEL_UNPACK_BUFFER,
+                0,
+                data.len() as isize,
+                gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT,
+            )
+        };
+        let pixels = if ptr.is_null() {
+            // Upload straight from client memory instead.
+            gl_clear_error(gl);
+            gl_call!(gl, BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0));
+            data.as_ptr() as *const _
+        } else {
+            unsafe { copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len()) };
+            gl_call!(gl, UnmapBuffer(gl::PIXEL_UNPACK_BUFFER));
+            null()
+        };
+
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, texture.id()));
+        gl_call!(
+            gl,
+            TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                0,
+                y.cast(),
+                width.cast(),
+                rows.cast(),
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels
+            )
+        );
+        gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+        gl_call!(gl, BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0));
+    }
+}
+
+fn decode_worker(jobs: Arc<Mutex<Receiver<DecodeJob>>>, results: Sender<DecodeResult>) {
+    loop {
+        // The lock is only held while waiting, the guard drops before decoding.
+        let job = jobs.lock().unwrap().recv();
+        let Ok(DecodeJob { job, path }) = job else {
+            return;
+        };
+
+        let image = image::open(&path)
+            .map(|img| img.flipv().to_rgba8())
+            .map_err(|e| format!("Can not open image {}: {}", path.display(), e))
+            .and_then(|img| match img.dimensions() {
+                (0, _) | (_, 0) => Err(format!("Image {} is empty", path.display())),
+                _ => Ok(img),
+            });
+
+        if results.send(DecodeResult { job, image }).is_err() {
+            return;
+        }
+    }
+}
+
+fn allocate_texture(gl: &gl::Gl, width: u32, height: u32) -> Texture {
+    let mut id = 0;
+    gl_call!(gl, GenTextures(1, &mut id));
+    gl_call!(gl, BindTexture(gl::TEXTURE_2D, id));
+    SamplerDesc::default().apply_to_bound_texture(gl);
+    gl_call!(
+        gl,
+        TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA8.cast(),
+            width.cast(),
+            height.cast(),
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            null()
+        )
+    );
+    gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+
+    Texture {
+        id,
+        width,
+        height,
+        format: TextureFormat::Rgba8,
+        levels: 1,
+    }
+}
+
+/// A small grey checkerboard shown while the real image is on its way.
+fn create_placeholder(gl: &gl::Gl) -> Texture {
+    let pixels: Vec<u8> = (0..PLACEHOLDER_SIZE * PLACEHOLDER_SIZE)
+        .flat_map(|i| {
+            let (x, y) = (i % PLACEHOLDER_SIZE, i / PLACEHOLDER_SIZE);
+            let v = if (x + y) % 2 == 0 { 0x80 } else { 0x50 };
+            [v, v, v, 0xFF]
+        })
+        .collect();
+
+    let texture = allocate_texture(gl, PLACEHOLDER_SIZE, PLACEHOLDER_SIZE);
+    gl_call!(gl, BindTexture(gl::TEXTURE_2D, texture.id()));
+    SamplerDesc::point().apply_to_bound_texture(gl);
+    gl_call!(
+        gl,
+        TexSubImage2D(
+            gl::TEXTURE_2D,
+            0,
+            0,
+            0,
+            PLACEHOLDER_SIZE.cast(),
+            PLACEHOLDER_SIZE.cast(),
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_ptr() as *const _
+        )
+    );
+    gl_call!(gl, BindTexture(gl::TEXTURE_2D, 0));
+
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "after its streamer was dropped")]
+    fn textures_do_not_outlive_their_streamer() {
+        let alive = Rc::new(Cell::new(true));
+        let texture = StreamedTexture {
+            slot: Rc::new(StreamSlot {
+                id: Cell::new(7),
+                size: Cell::new((PLACEHOLDER_SIZE, PLACEHOLDER_SIZE)),
+                ready: Cell::new(false),
+                texture: RefCell::new(None),
+                released: Cell::new(false),
+            }),
+            placeholder: 7,
+            streamer_alive: alive.clone(),
+        };
+        assert_eq!(texture.id(), 7);
+
+        alive.set(false);
+        texture.id();
+    }
+}
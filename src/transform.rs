@@ -0,0 +1,292 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Translation3, UnitQuaternion, Vector3};
+
+/// Placement of an object: scaled first, then rotated, then translated.
+///
+/// Matrices follow nalgebra's column-vector convention, `matrix() * point`,
+/// and are uploaded as is, so shaders multiply `u_proj * u_view * u_model *
+/// position`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vector3::new(0., 0., 0.),
+        rotation: UnitQuaternion::new_unchecked(nalgebra::Quaternion::new(1., 0., 0., 0.)),
+        scale: Vector3::new(1., 1., 1.),
+    };
+
+    pub fn new(
+        translation: Vector3<f32>,
+        rotation: UnitQuaternion<f32>,
+        scale: Vector3<f32>,
+    ) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: UnitQuaternion<f32>) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vector3<f32>) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Vector3<f32>) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: UnitQuaternion<f32>) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Blend towards `other` by `t`, along the shortest arc for the rotation.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Self {
+            translation: self.translation.lerp(&other.translation, t),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+
+    /// The model matrix, `T * R * S`.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let mut m = self.rotation.to_rotation_matrix().into_inner();
+        for (mut column, s) in m.column_iter_mut().zip(self.scale.iter()) {
+            column *= *s;
+        }
+
+        let mut matrix = m.to_homogeneous();
+        matrix
+            .fixed_view_mut::<3, 1>(0, 3)
+            .copy_from(&self.translation);
+        matrix
+    }
+
+    /// The inverse of [`Transform::matrix`], `None` when a scale is zero.
+    pub fn inverse_matrix(&self) -> Option<Matrix4<f32>> {
+        if self.scale.iter().any(|s| *s == 0.) {
+            return None;
+        }
+
+        let inv_scale = Matrix4::new_nonuniform_scaling(&self.scale.map(|s| 1. / s));
+        let inv_rotation = self.rotation.inverse().to_homogeneous();
+        let inv_translation = Translation3::from(-self.translation).to_homogeneous();
+        Some(inv_scale * inv_rotation * inv_translation)
+    }
+
+    /// Matrix for normals, which stay perpendicular to surfaces under
+    /// non-uniform scale.
+    pub fn normal_matrix(&self) -> Matrix3<f32> {
+        normal_matrix(&self.matrix())
+    }
+
+    pub fn transform_point(&self, point: &Point3<f32>) -> Point3<f32> {
+        self.rotation * Point3::from(point.coords.component_mul(&self.scale)) + self.translation
+    }
+
+    /// Transform a direction, ignoring translation.
+    pub fn transform_vector(&self, vector: &Vector3<f32>) -> Vector3<f32> {
+        self.rotation * vector.component_mul(&self.scale)
+    }
+}
+
+impl From<Transform> for Matrix4<f32> {
+    fn from(transform: Transform) -> Self {
+        transform.matrix()
+    }
+}
+
+/// Inverse transpose of the upper 3x3 of `model`, identity if it is singular.
+pub fn normal_matrix(model: &Matrix4<f32>) -> Matrix3<f32> {
+    model
+        .fixed_view::<3, 3>(0, 0)
+        .into_owned()
+        .try_inverse()
+        .map_or_else(Matrix3::identity, |m| m.transpose())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use approx::assert_relative_eq;
+    use nalgebra::{Isometry3, Perspective3, Point4};
+
+    fn sample() -> Transform {
+        Transform::new(
+            Vector3::new(1., -2., 3.),
+            UnitQuaternion::from_euler_angles(0.3, -1.1, 2.),
+            Vector3::new(2., 0.5, -3.),
+        )
+    }
+
+    #[test]
+    fn lerp_reaches_both_ends() {
+        let a = sample();
+        let b = Transform::from_translation(Vector3::new(-1., 0., 4.));
+        assert_relative_eq!(a.lerp(&b, 0.).matrix(), a.matrix(), epsilon = 1e-6);
+        assert_relative_eq!(a.lerp(&b, 1.).matrix(), b.matrix(), epsilon = 1e-6);
+
+        let half = a.lerp(&b, 0.5);
+        assert_relative_eq!(half.translation, (a.translation + b.translation) / 2.);
+        assert_relative_eq!(
+            half.rotation.angle_to(&a.rotation),
+            half.rotation.angle_to(&b.rotation),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn identity_is_identity_matrix() {
+        assert_eq!(Transform::default().matrix(), Matrix4::identity());
+        assert_eq!(Transform::IDENTITY.rotation, UnitQuaternion::identity());
+    }
+
+    #[test]
+    fn matrix_is_translation_rotation_scale() {
+        let t = sample();
+        let expected = Translation3::from(t.translation).to_homogeneous()
+            * t.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&t.scale);
+
+        assert_relative_eq!(t.matrix(), expected, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn matrix_matches_point_and_vector_transforms() {
+        let t = sample();
+        let p = Point3::new(0.25, 4., -1.5);
+        let v = Vector3::new(-2., 1., 0.5);
+
+        assert_relative_eq!(
+            t.matrix().transform_point(&p),
+            t.transform_point(&p),
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(
+            t.matrix().transform_vector(&v),
+            t.transform_vector(&v),
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn inverse_matrix_matches_nalgebra() {
+        let t = sample();
+        let expected = t.matrix().try_inverse().unwrap();
+
+        assert_relative_eq!(t.inverse_matrix().unwrap(), expected, epsilon = 1e-5);
+        assert!(t
+            .with_scale(Vector3::new(1., 0., 1.))
+            .inverse_matrix()
+            .is_none());
+    }
+
+    #[test]
+    fn rigid_transform_matches_isometry() {
+        let t = sample().with_scale(Vector3::new(1., 1., 1.));
+        let iso = Isometry3::from_parts(Translation3::from(t.translation), t.rotation);
+
+        assert_relative_eq!(t.matrix(), iso.to_homogeneous(), epsilon = 1e-6);
+        assert_relative_eq!(
+            t.inverse_matrix().unwrap(),
+            iso.inverse().to_homogeneous(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let t = sample();
+        // A surface spanned by two tangents, and its normal.
+        let (a, b) = (Vector3::new(1., 2., 0.), Vector3::new(0., -1., 3.));
+        let n = a.cross(&b);
+
+        let n = t.normal_matrix() * n;
+        assert_relative_eq!(n.dot(&t.transform_vector(&a)), 0., epsilon = 1e-4);
+        assert_relative_eq!(n.dot(&t.transform_vector(&b)), 0., epsilon = 1e-4);
+
+        // Without scale it is just the rotation.
+        let rigid = t.with_scale(Vector3::new(1., 1., 1.));
+        assert_relative_eq!(
+            rigid.normal_matrix(),
+            rigid.rotation.to_rotation_matrix().into_inner(),
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn upload_layout_is_column_major() {
+        // GL reads `as_slice` with `transpose = FALSE` as columns, the
+        // translation has to be the last four floats.
+        let t = Transform::from_translation(Vector3::new(1., 2., 3.));
+        assert_eq!(&t.matrix().as_slice()[12..], &[1., 2., 3., 1.]);
+    }
+
+    #[test]
+    fn camera_matches_nalgebra() {
+        let (fovy, near, far) = (1.2, 0.1, 100.);
+        let eye = Point3::new(3., 2., 5.);
+        let target = Point3::new(0., 0.5, 0.);
+
+        let mut camera = Camera::perspective(fovy, near, far);
+        camera.resize(1600, 900);
+        camera.look_at(eye, target, Vector3::y());
+
+        let view = Isometry3::look_at_rh(&eye, &target, &Vector3::y()).to_homogeneous();
+        let proj = Perspective3::new(16. / 9., fovy, near, far).to_homogeneous();
+        assert_relative_eq!(camera.view(), view, epsilon = 1e-5);
+        assert_relative_eq!(camera.projection_matrix(), proj, epsilon = 1e-5);
+
+        // The target lands in the middle of the screen.
+        let clip = camera.view_projection() * Point4::new(target.x, target.y, target.z, 1.);
+        assert_relative_eq!(clip.x / clip.w, 0., epsilon = 1e-5);
+        assert_relative_eq!(clip.y / clip.w, 0., epsilon = 1e-5);
+    }
+
+    #[test]
+    fn mvp_order_keeps_translation() {
+        // `u_proj * u_view * u_model * position` with a non-symmetric model,
+        // which row-vector order would get wrong.
+        let mut camera = Camera::orthographic(2., -1., 1.);
+        camera.resize(100, 100);
+        let model = Transform::from_translation(Vector3::new(0.5, 0.25, 0.)).matrix();
+
+        let clip = camera.view_projection() * model * Point4::new(0., 0., 0., 1.);
+        assert_relative_eq!(clip, Point4::new(0.5, 0.25, 0., 1.), epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{
+    buffer::{Buffer, BufferTarget, BufferUsage},
+    gl_call,
+    renderer::gl::{
+        self,
+        types::{GLboolean, GLenum, GLuint},
+    },
+};
+use bytemuck::Pod;
+use half::f16;
+use std::cell::Cell;
+
+pub use rust_gl_derive::Vertex;
+
+/// A scalar type that can make up a vertex attribute.
+pub trait VertexElementType {
+    const GL_TYPE: GLenum;
+    /// Whether the type can be read as an integer attribute by the shader.
+    const INTEGER: bool;
+}
+
+macro_rules! vertex_element_type {
+    ($($ty:ty => $gl_type:ident, $integer:literal;)*) => {
+        $(
+            impl VertexElementType for $ty {
+                const GL_TYPE: GLenum = gl::$gl_type;
+                const INTEGER: bool = $integer;
+            }
+        )*
+    };
+}
+
+vertex_element_type! {
+    f32 => FLOAT, false;
+    f16 => HALF_FLOAT, false;
+    i8 => BYTE, true;
+    u8 => UNSIGNED_BYTE, true;
+    i16 => SHORT, true;
+    u16 => UNSIGNED_SHORT, true;
+    i32 => INT, true;
+    u32 => UNSIGNED_INT, true;
+}
+
+/// A field type that makes up one vertex attribute, a scalar or an array of
+/// up to four of them.
+pub trait VertexAttribute {
+    type Element: VertexElementType;
+    const COUNT: usize;
+}
+
+impl<T: VertexElementType> VertexAttribute for T {
+    type Element = T;
+    const COUNT: usize = 1;
+}
+
+impl<T: VertexElementType, const N: usize> VertexAttribute for [T; N] {
+    type Element = T;
+    const COUNT: usize = N;
+}
+
+/// A `#[repr(C)]` struct describing one vertex, usually derived:
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+/// struct TexVertex {
+///     #[location(0)]
+///     pos: [f32; 2],
+///     #[location(1)]
+///     uv: [f32; 2],
+/// }
+/// ```
+///
+/// Fields without `#[location(n)]` take the next free location. Integer
+/// fields can be marked `#[normalized]` or `#[integer]`, see [`AttributeKind`].
+pub trait Vertex: Pod {
+    const ATTRIBUTES: &'static [VertexBufferElement];
+}
+
+/// How the shader sees an attribute's components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeKind {
+    /// Converted to float as is.
+    Float,
+    /// Integers mapped to `[0, 1]` or `[-1, 1]`.
+    Normalized,
+    /// Kept as integers, for `int`/`uint` shader inputs.
+    Integer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexBufferElement {
+    pub gl_type: GLenum,
+    pub count: i32,
+    pub kind: AttributeKind,
+    pub offset: usize,
+    /// 0 advances per vertex, `n` per `n` instances.
+    pub divisor: u32,
+    /// Fixed shader location, or the next free one if `None`.
+    pub location: Option<GLuint>,
+}
+
+impl VertexBufferElement {
+    /// Describe a field of type `A` at `offset`, checked at compile time
+    /// when used in a constant.
+    pub const fn of<A: VertexAttribute>(
+        kind: AttributeKind,
+        offset: usize,
+        location: Option<GLuint>,
+    ) -> Self {
+        assert!(
+            A::COUNT >= 1 && A::COUNT <= 4,
+            "Attributes have 1 to 4 components."
+        );
+        assert!(
+            matches!(kind, AttributeKind::Float) || A::Element::INTEGER,
+            "Only integer types can be normalized or integer attributes."
+        );
+
+        Self {
+            gl_type: A::Element::GL_TYPE,
+            count: A::COUNT as i32,
+            kind,
+            offset,
+            divisor: 0,
+            location,
+        }
+    }
+}
+
+/// Describes the interleaved attributes of one vertex buffer.
+///
+/// ```ignore
+/// let mut layout = VertexBufferLayout::new();
+/// layout.push::<f32>(2); // position
+/// layout.push_normalized::<u8>(4); // color
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VertexBufferLayout {
+    elements: Vec<VertexBufferElement>,
+    stride: usize,
+}
+
+impl VertexBufferLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The layout of a buffer of `V`s.
+    pub fn of<V: Vertex>() -> Self {
+        Self {
+            elements: V::ATTRIBUTES.to_vec(),
+            stride: std::mem::size_of::<V>(),
+        }
+    }
+
+    /// Append `count` components of `T`, read as floats.
+    pub fn push<T: VertexElementType>(&mut self, count: i32) -> &mut Self {
+        self.push_element::<T>(count, AttributeKind::Float)
+    }
+
+    /// Append `count` integer components of `T`, normalized to floats.
+    pub fn push_normalized<T: VertexElementType>(&mut self, count: i32) -> &mut Self {
+        assert!(T::INTEGER, "Only integer attributes can be normalized.");
+        self.push_element::<T>(count, AttributeKind::Normalized)
+    }
+
+    /// Append `count` integer components of `T`, passed to the shader unconverted.
+    pub fn push_integer<T: VertexElementType>(&mut self, count: i32) -> &mut Self {
+        assert!(T::INTEGER, "Only integer types can be integer attributes.");
+        self.push_element::<T>(count, AttributeKind::Integer)
+    }
+
+    /// Skip `bytes` of padding in each vertex.
+    pub fn skip(&mut self, bytes: usize) -> &mut Self {
+        self.stride += bytes;
+        self
+    }
+
+    /// Bind the last pushed attribute to a fixed shader `location`.
+    pub fn location(&mut self, location: GLuint) -> &mut Self {
+        self.last_element().location = Some(location);
+        self
+    }
+
+    /// Advance the last pushed attribute once every `divisor` instances
+    /// instead of once per vertex.
+    pub fn divisor(&mut self, divisor: u32) -> &mut Self {
+        self.last_element().divisor = divisor;
+        self
+    }
+
+    pub fn elements(&self) -> &[VertexBufferElement] {
+        &self.elements
+    }
+
+    /// Size of one vertex in bytes.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    fn push_element<T: VertexElementType>(&mut self, count: i32, kind: AttributeKind) -> &mut Self {
+        assert!(
+            (1..=4).contains(&count),
+            "Attributes have 1 to 4 components."
+        );
+
+        self.elements.push(VertexBufferElement {
+            gl_type: T::GL_TYPE,
+            count,
+            kind,
+            offset: self.stride,
+            divisor: 0,
+            location: None,
+        });
+        self.stride += std::mem::size_of::<T>() * count as usize;
+        self
+    }
+
+    fn last_element(&mut self) -> &mut VertexBufferElement {
+        self.elements
+            .last_mut()
+            .expect("Push an attribute before configuring it.")
+    }
+}
+
+/// A gl vertex array object.
+///
+/// Each [`VertexArray::add_buffer`] takes the next free attribute locations,
+/// so a first buffer with two attributes uses locations 0 and 1 and a second
+/// one continues at 2. Attributes with a fixed location move that counter
+/// past themselves.
+pub struct VertexArray {
+    id: GLuint,
+    next_location: Cell<GLuint>,
+}
+
+impl VertexArray {
+    /// Create a vertex array, it is left bound.
+    pub fn new(gl: &gl::Gl) -> Self {
+        let mut id = 0;
+        gl_call!(gl, GenVertexArrays(1, &mut id));
+        gl_call!(gl, BindVertexArray(id));
+
+        Self {
+            id,
+            next_location: Cell::new(0),
+        }
+    }
+
+    /// Source the next attributes from `buffer` as described by `layout`.
+    ///
+    /// Both the vertex array and the buffer are left bound.
+    pub fn add_buffer<T: Pod>(&self, gl: &gl::Gl, buffer: &Buffer<T>, layout: &VertexBufferLayout) {
+        self.bind(gl);
+        gl_call!(gl, BindBuffer(gl::ARRAY_BUFFER, buffer.id()));
+
+        let stride = layout.stride() as i32;
+        for element in layout.elements() {
+            let location = element.location.unwrap_or(self.next_location.get());
+            self.next_location
+                .set(self.next_location.get().max(location + 1));
+
+            gl_call!(gl, EnableVertexAttribArray(location));
+            let offset = element.offset as *const _;
+            match element.kind {
+                AttributeKind::Integer => gl_call!(
+                    gl,
+                    VertexAttribIPointer(location, element.count, element.gl_type, stride, offset)
+                ),
+                kind => gl_call!(
+                    gl,
+                    VertexAttribPointer(
+                        location,
+                        element.count,
+                        element.gl_type,
+                        (kind == AttributeKind::Normalized) as GLboolean,
+                        stride,
+                        offset
+                    )
+                ),
+            }
+            if element.divisor != 0 {
+                gl_call!(gl, VertexAttribDivisor(location, element.divisor));
+            }
+        }
+    }
+
+    /// Upload `vertices` into a new buffer and source attributes from it with
+    /// the layout of `V`.
+    pub fn add_vertices<V: Vertex>(
+        &self,
+        gl: &gl::Gl,
+        vertices: &[V],
+        usage: BufferUsage,
+    ) -> Buffer<V> {
+        self.bind(gl);
+        let buffer = Buffer::new(gl, BufferTarget::Array, vertices, usage);
+        self.add_buffer(gl, &buffer, &VertexBufferLayout::of::<V>());
+        buffer
+    }
+
+    pub fn bind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindVertexArray(self.id));
+    }
+
+    pub fn unbind(&self, gl: &gl::Gl) {
+        gl_call!(gl, BindVertexArray(0));
+    }
+
+    pub fn drop(&self, gl: &gl::Gl) {
+        unsafe { gl.DeleteVertexArrays(1, &self.id) }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use std::mem::{offset_of, size_of};
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Pod, Zeroable, Vertex)]
+    struct TestVertex {
+        position: [f32; 3],
+        #[normalized]
+        color: [u8; 4],
+        #[integer]
+        joints: [u16; 2],
+        #[location(5)]
+        weight: f32,
+    }
+
+    #[test]
+    fn layout_offsets_follow_pushes() {
+        let mut layout = VertexBufferLayout::new();
+        layout
+            .push::<f32>(3)
+            .push_normalized::<u8>(4)
+            .skip(4)
+            .push_integer::<u16>(2)
+            .location(3)
+            .divisor(1);
+
+        let offsets: Vec<_> = layout.elements().iter().map(|e| e.offset).collect();
+        assert_eq!(offsets, [0, 12, 20]);
+        assert_eq!(layout.stride(), 24);
+
+        let joints = layout.elements()[2];
+        assert_eq!(joints.gl_type, gl::UNSIGNED_SHORT);
+        assert_eq!(joints.kind, AttributeKind::Integer);
+        assert_eq!(joints.location, Some(3));
+        assert_eq!(joints.divisor, 1);
+    }
+
+    #[test]
+    fn derived_attributes_match_the_struct() {
+        let expected = [
+            (
+                gl::FLOAT,
+                3,
+                AttributeKind::Float,
+                offset_of!(TestVertex, position),
+                None,
+            ),
+            (
+                gl::UNSIGNED_BYTE,
+                4,
+                AttributeKind::Normalized,
+                offset_of!(TestVertex, color),
+                None,
+            ),
+            (
+                gl::UNSIGNED_SHORT,
+                2,
+                AttributeKind::Integer,
+                offset_of!(TestVertex, joints),
+                None,
+            ),
+            (
+                gl::FLOAT,
+                1,
+                AttributeKind::Float,
+                offset_of!(TestVertex, weight),
+                Some(5),
+            ),
+        ];
+
+        assert_eq!(TestVertex::ATTRIBUTES.len(), expected.len());
+        for (element, (gl_type, count, kind, offset, location)) in
+            TestVertex::ATTRIBUTES.iter().zip(expected)
+        {
+            assert_eq!(element.gl_type, gl_type);
+            assert_eq!(element.count, count);
+            assert_eq!(element.kind, kind);
+            assert_eq!(element.offset, offset);
+            assert_eq!(element.location, location);
+            assert_eq!(element.divisor, 0);
+        }
+
+        let layout = VertexBufferLayout::of::<TestVertex>();
+        assert_eq!(layout.stride(), size_of::<TestVertex>());
+        assert_eq!(layout.elements(), TestVertex::ATTRIBUTES);
+    }
+}